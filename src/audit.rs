@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::sync::SyncResult;
+use crate::types::TaskItem;
+
+/// Where each sync records what it did, relative to the project root,
+/// unless `audit_file` says otherwise
+pub const AUDIT_FILE: &str = ".projectmd/last-sync.json";
+
+/// What the last sync did, overwritten by each sync
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncAudit {
+    /// When the sync finished, as RFC 3339
+    pub finished_at: String,
+    /// projectmd version that ran it
+    pub version: String,
+    /// Commit checked out in the project's git repo, if it is in one
+    pub git_sha: Option<String>,
+    pub created: Vec<AuditEntry>,
+    pub updated: Vec<AuditEntry>,
+    pub skipped: Vec<AuditEntry>,
+    pub queued: Vec<AuditEntry>,
+    pub errors: Vec<AuditEntry>,
+}
+
+/// A task in the audit record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub task: String,
+    pub issue: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    fn new(task: &TaskItem, issue: Option<u64>) -> Self {
+        Self { task: task.name(), issue, error: None }
+    }
+}
+
+impl SyncAudit {
+    pub fn new(result: &SyncResult, finished_at: DateTime<Utc>, git_sha: Option<String>) -> Self {
+        let entries = |tasks: &[(TaskItem, u64)]| tasks.iter().map(|(task, num)| AuditEntry::new(task, Some(*num))).collect();
+        let unnumbered = |tasks: &[TaskItem]| tasks.iter().map(|task| AuditEntry::new(task, task.status.issue_id())).collect();
+
+        Self {
+            finished_at: finished_at.to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha,
+            created: entries(&result.created),
+            updated: entries(&result.updated),
+            skipped: unnumbered(&result.skipped),
+            queued: unnumbered(&result.queued),
+            errors: result
+                .errors
+                .iter()
+                .map(|(task, error)| AuditEntry { error: Some(error.clone()), ..AuditEntry::new(task, task.status.issue_id()) })
+                .collect(),
+        }
+    }
+
+    /// Write the record, replacing the previous one
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create audit directory")?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write audit record: {:?}", path))
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+/// What went wrong with a backend request, as far as deciding what to do next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The issue (or repo) doesn't exist, e.g. it was deleted (404, 410)
+    NotFound,
+    /// The backend rejected the request's content (422)
+    Validation,
+    /// Too many requests (429, or 403 with a rate limit message)
+    RateLimited,
+    /// The backend failed to handle the request (5xx)
+    Server,
+    /// The request never got a response
+    Network,
+    Other,
+}
+
+impl ErrorKind {
+    /// Classify an HTTP error response by its status and message
+    pub fn from_status(status: u16, message: &str) -> Self {
+        match status {
+            404 | 410 => ErrorKind::NotFound,
+            422 => ErrorKind::Validation,
+            429 => ErrorKind::RateLimited,
+            403 if message.to_lowercase().contains("rate limit") => ErrorKind::RateLimited,
+            500..=599 => ErrorKind::Server,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// A classified backend failure, for backends that don't have their own error
+/// type to inspect
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct BackendError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl BackendError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self { kind, message: message.into() }
+    }
+}
+
+/// Classify an error from any backend by looking through its causes
+pub fn classify(error: &anyhow::Error) -> ErrorKind {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<BackendError>() {
+            return error.kind;
+        }
+        if let Some(error) = cause.downcast_ref::<octocrab::Error>() {
+            return super::github::error_kind(error);
+        }
+    }
+
+    ErrorKind::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify_responses() {
+        assert_eq!(ErrorKind::from_status(404, "Not Found"), ErrorKind::NotFound);
+        assert_eq!(ErrorKind::from_status(410, "This issue was deleted"), ErrorKind::NotFound);
+        assert_eq!(ErrorKind::from_status(422, "Validation Failed"), ErrorKind::Validation);
+        assert_eq!(ErrorKind::from_status(403, "API rate limit exceeded for user"), ErrorKind::RateLimited);
+        assert_eq!(ErrorKind::from_status(403, "Resource not accessible"), ErrorKind::Other);
+        assert_eq!(ErrorKind::from_status(429, ""), ErrorKind::RateLimited);
+        assert_eq!(ErrorKind::from_status(502, "Bad Gateway"), ErrorKind::Server);
+        assert_eq!(ErrorKind::from_status(401, "Bad credentials"), ErrorKind::Other);
+
+        // Context added on the way up doesn't hide the cause
+        let error = Err::<(), _>(BackendError::new(ErrorKind::NotFound, "Issue #5 not found"))
+            .context("Failed to update issue")
+            .unwrap_err();
+        assert_eq!(classify(&error), ErrorKind::NotFound);
+        assert_eq!(classify(&anyhow::anyhow!("Something else")), ErrorKind::Other);
+    }
+}
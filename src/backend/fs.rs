@@ -0,0 +1,268 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::error::{BackendError, ErrorKind};
+use super::{Backend, CloseReason, Comment, Issue};
+use crate::body::normalize_remote_body;
+
+/// Name of the file holding all issues inside the backend directory
+const ISSUES_FILE: &str = "issues.json";
+
+/// An issue as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredIssue {
+    number: u64,
+    title: String,
+    body: String,
+    state: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    assignees: Vec<String>,
+    #[serde(default)]
+    comments: Vec<Comment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    milestone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    closed_at: Option<DateTime<Utc>>,
+    /// `completed` or `not_planned`, when closed with a reason
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    state_reason: Option<String>,
+}
+
+fn not_found(number: u64) -> BackendError {
+    BackendError::new(ErrorKind::NotFound, format!("Issue #{} not found", number))
+}
+
+/// Backend keeping issues in a JSON file in a local directory
+///
+/// Useful for trying projectmd out and for tests, without a GitHub repo or
+/// token. The directory is the project's `repo`, relative to project.md.
+pub struct FileSystemBackend {
+    dir: PathBuf,
+    // Serializes read-modify-write cycles of the issues file
+    lock: Mutex<()>,
+}
+
+impl FileSystemBackend {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn issues_file(&self) -> PathBuf {
+        self.dir.join(ISSUES_FILE)
+    }
+
+    fn load(&self) -> Result<Vec<StoredIssue>> {
+        let path = self.issues_file();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read issues file: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse issues file: {:?}", path))
+    }
+
+    fn save(&self, issues: &[StoredIssue]) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create issues directory: {:?}", self.dir))?;
+        fs::write(self.issues_file(), serde_json::to_string_pretty(issues)?)
+            .context("Failed to write issues file")
+    }
+
+    /// Apply `change` to issue `number` and save, returning the changed issue
+    fn modify(&self, number: u64, change: impl FnOnce(&mut StoredIssue)) -> Result<Issue> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut issues = self.load()?;
+
+        let issue = issues
+            .iter_mut()
+            .find(|i| i.number == number)
+            .ok_or_else(|| not_found(number))?;
+        change(issue);
+        let issue = self.convert_issue(issue);
+
+        self.save(&issues)?;
+        Ok(issue)
+    }
+
+    fn convert_issue(&self, issue: &StoredIssue) -> Issue {
+        Issue {
+            id: issue.number,
+            node_id: format!("fs-{}", issue.number),
+            number: issue.number,
+            title: issue.title.clone(),
+            // issues.json may have been edited by hand
+            body: normalize_remote_body(&issue.body),
+            state: issue.state.clone(),
+            labels: issue.labels.clone(),
+            url: format!("{}#{}", self.issues_file().display(), issue.number),
+            comments: Some(issue.comments.len() as u64),
+            reactions: None,
+            closed_at: issue.closed_at,
+            milestone: issue.milestone.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for FileSystemBackend {
+    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut issues = self.load()?;
+
+        let issue = StoredIssue {
+            number: issues.iter().map(|i| i.number).max().unwrap_or(0) + 1,
+            title: title.to_string(),
+            body: body.to_string(),
+            state: "open".to_string(),
+            labels,
+            assignees,
+            comments: Vec::new(),
+            milestone: None,
+            closed_at: None,
+            state_reason: None,
+        };
+        let created = self.convert_issue(&issue);
+
+        issues.push(issue);
+        self.save(&issues)?;
+        Ok(created)
+    }
+
+    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
+        self.modify(number, |issue| {
+            issue.title = title.to_string();
+            issue.body = body.to_string();
+            issue.labels = labels;
+            if !assignees.is_empty() {
+                issue.assignees = assignees;
+            }
+        })
+    }
+
+    async fn close_issue(&self, number: u64) -> Result<Issue> {
+        self.modify(number, |issue| {
+            issue.state = "closed".to_string();
+            issue.closed_at = Some(Utc::now());
+        })
+    }
+
+    async fn close_issue_with_reason(&self, number: u64, reason: CloseReason) -> Result<Issue> {
+        self.modify(number, |issue| {
+            issue.state = "closed".to_string();
+            issue.closed_at = Some(Utc::now());
+            issue.state_reason = Some(reason.as_str().to_string());
+        })
+    }
+
+    async fn set_milestone(&self, number: u64, milestone: &str) -> Result<()> {
+        self.modify(number, |issue| issue.milestone = Some(milestone.to_string()))?;
+        Ok(())
+    }
+
+    async fn get_issue(&self, number: u64) -> Result<Issue> {
+        self.load()?
+            .iter()
+            .find(|i| i.number == number)
+            .map(|i| self.convert_issue(i))
+            .ok_or_else(|| not_found(number).into())
+    }
+
+    async fn get_issues_batch(&self, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+        Ok(self
+            .load()?
+            .iter()
+            .filter(|i| numbers.contains(&i.number))
+            .map(|i| (i.number, self.convert_issue(i)))
+            .collect())
+    }
+
+    async fn list_issues(&self) -> Result<Vec<Issue>> {
+        Ok(self.load()?.iter().map(|i| self.convert_issue(i)).collect())
+    }
+
+    async fn find_issue_by_key(&self, key: &str) -> Result<Option<Issue>> {
+        Ok(self
+            .load()?
+            .iter()
+            .find(|i| i.body.contains(key))
+            .map(|i| self.convert_issue(i)))
+    }
+
+    async fn list_comments(&self, number: u64) -> Result<Vec<Comment>> {
+        self.load()?
+            .into_iter()
+            .find(|i| i.number == number)
+            .map(|i| i.comments)
+            .ok_or_else(|| not_found(number).into())
+    }
+
+    async fn add_comment(&self, number: u64, body: &str) -> Result<()> {
+        self.modify(number, |issue| {
+            issue.comments.push(Comment {
+                author: "projectmd".to_string(),
+                body: body.to_string(),
+                created_at: Utc::now().to_rfc3339(),
+            })
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_issues_by_milestone_and_add_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = FileSystemBackend::new(dir.path());
+        for (title, milestone) in [("A", Some("v2.0")), ("B", Some("v1.0")), ("C", Some("v2.0")), ("D", None)] {
+            let issue = backend.create_issue(title, "", vec!["bug".to_string()], Vec::new()).await.unwrap();
+            if let Some(milestone) = milestone {
+                backend.set_milestone(issue.number, milestone).await.unwrap();
+            }
+        }
+        backend.close_issue(3).await.unwrap();
+
+        // Closed issues are still in their milestone
+        let numbers = |issues: Vec<Issue>| issues.iter().map(|i| i.number).collect::<Vec<_>>();
+        assert_eq!(numbers(backend.list_issues_by_milestone("v2.0").await.unwrap()), vec![1, 3]);
+        assert_eq!(numbers(backend.list_issues_by_milestone("v3.0").await.unwrap()), Vec::<u64>::new());
+
+        backend.add_label(1, "released").await.unwrap();
+        backend.add_label(1, "released").await.unwrap();
+        assert_eq!(backend.get_issue(1).await.unwrap().labels, vec!["bug", "released"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_issues_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = FileSystemBackend::new(dir.path());
+        for title in ["A", "B", "C"] {
+            backend.create_issue(title, "", Vec::new(), Vec::new()).await.unwrap();
+        }
+
+        // Missing issues are left out, as when fetching one at a time
+        let titles = |issues: HashMap<u64, Issue>| {
+            let mut titles: Vec<(u64, String)> = issues.into_iter().map(|(n, i)| (n, i.title)).collect();
+            titles.sort();
+            titles
+        };
+        let expected = vec![(1, "A".to_string()), (3, "C".to_string())];
+        assert_eq!(titles(backend.get_issues_batch(&[3, 1, 9]).await.unwrap()), expected);
+        assert_eq!(titles(super::super::get_issues_one_by_one(&backend, &[3, 1, 9]).await.unwrap()), expected);
+    }
+}
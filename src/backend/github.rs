@@ -1,23 +1,80 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use octocrab::models::issues::IssueStateReason;
 use octocrab::Octocrab;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use super::{Backend, Issue};
+use super::error::ErrorKind;
+use super::{get_issues_one_by_one, Backend, CloseReason, Comment, Issue, RateLimit, RateLimitState, RepoAccess, Transfer};
+use crate::body::normalize_remote_body;
+use crate::types::ProjectBoardConfig;
+
+/// Attempts made at a search request before giving up on rate limits
+const SEARCH_ATTEMPTS: u32 = 4;
+
+/// Check if an error is GitHub rejecting a request for exceeding a rate limit
+fn is_rate_limited(error: &octocrab::Error) -> bool {
+    error_kind(error) == ErrorKind::RateLimited
+}
+
+/// Classify an octocrab error by the response status, if there was one
+pub(crate) fn error_kind(error: &octocrab::Error) -> ErrorKind {
+    match error {
+        octocrab::Error::GitHub { source, .. } => {
+            ErrorKind::from_status(source.status_code.as_u16(), &source.message)
+        }
+        octocrab::Error::Hyper { .. } | octocrab::Error::Http { .. } | octocrab::Error::Service { .. } => {
+            ErrorKind::Network
+        }
+        _ => ErrorKind::Other,
+    }
+}
+
+/// Settings the GitHub backend reads from `backend_config`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct GitHubOptions {
+    /// API root for GitHub Enterprise Server, e.g. `https://github.example.com/api/v3`
+    pub base_url: Option<String>,
+}
+
+impl GitHubOptions {
+    pub fn from_backend_config(settings: &HashMap<String, serde_json::Value>) -> Result<Self> {
+        let settings = serde_json::Value::Object(settings.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
+        serde_json::from_value(settings).context("Invalid backend_config for the github backend")
+    }
+}
 
 /// GitHub backend using octocrab
 pub struct GitHubBackend {
     client: Octocrab,
     owner: String,
     repo: String,
+    /// Lowercased names of the repo's labels, listed on first use by `ensure_label`
+    labels: Mutex<Option<HashSet<String>>>,
+    /// Quota left as of the latest issue fetched
+    rate_limits: Arc<RateLimitState>,
 }
 
 impl GitHubBackend {
     /// Create a new GitHub backend with a personal access token
     pub fn new(token: &str, repo: &str) -> Result<Self> {
-        let client = Octocrab::builder()
-            .personal_token(token.to_string())
-            .build()
-            .context("Failed to create GitHub client")?;
+        Self::with_options(token, repo, GitHubOptions::default())
+    }
+
+    /// Create a GitHub backend with settings from `backend_config`
+    pub fn with_options(token: &str, repo: &str, options: GitHubOptions) -> Result<Self> {
+        let mut builder = Octocrab::builder().personal_token(token.to_string());
+        if let Some(base_url) = &options.base_url {
+            builder = builder
+                .base_uri(base_url.as_str())
+                .with_context(|| format!("Invalid GitHub base_url: {}", base_url))?;
+        }
+        let client = builder.build().context("Failed to create GitHub client")?;
 
         // Parse owner/repo format
         let parts: Vec<&str> = repo.split('/').collect();
@@ -29,6 +86,8 @@ impl GitHubBackend {
             client,
             owner: parts[0].to_string(),
             repo: parts[1].to_string(),
+            labels: Mutex::new(None),
+            rate_limits: Arc::default(),
         })
     }
 
@@ -42,23 +101,238 @@ impl GitHubBackend {
 
         Issue {
             id: issue.id.0,
+            node_id: issue.node_id,
             number: issue.number,
             title: issue.title,
-            body: issue.body.unwrap_or_default(),
+            body: normalize_remote_body(&issue.body.unwrap_or_default()),
             state: state.to_string(),
+            labels: issue.labels.into_iter().map(|l| l.name).collect(),
+            url: issue.html_url.to_string(),
+            comments: Some(issue.comments as u64),
+            // Not part of octocrab's issue model; see `list_issues`
+            reactions: None,
+            closed_at: issue.closed_at,
+            milestone: issue.milestone.map(|m| m.title),
+        }
+    }
+}
+
+impl GitHubBackend {
+    /// Run a GraphQL request, turning GraphQL-level errors into failures
+    async fn graphql(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value> {
+        let mut response: serde_json::Value = self
+            .client
+            .graphql(&json!({ "query": query, "variables": variables }))
+            .await
+            .context("GitHub GraphQL request failed")?;
+
+        if let Some(errors) = response.get("errors") {
+            anyhow::bail!("GitHub GraphQL request failed: {}", errors);
+        }
+
+        Ok(response["data"].take())
+    }
+
+    /// Number of the milestone with the given title
+    async fn milestone_number(&self, milestone: &str) -> Result<u64> {
+        let milestones: Vec<serde_json::Value> = self
+            .client
+            .get(
+                format!("/repos/{}/{}/milestones", self.owner, self.repo),
+                Some(&[("state", "all"), ("per_page", "100")]),
+            )
+            .await
+            .context("Failed to list GitHub milestones")?;
+
+        milestones
+            .iter()
+            .find(|m| m["title"].as_str() == Some(milestone))
+            .and_then(|m| m["number"].as_u64())
+            .with_context(|| format!("Repository has no milestone '{}'", milestone))
+    }
+
+    /// Fetch an issue, conditionally on `etag` if given: `None` when GitHub
+    /// answers 304 Not Modified
+    ///
+    /// Fetched without octocrab's issue handler so the rate limit headers and
+    /// ETag of the response can be read.
+    async fn fetch_issue(&self, number: u64, etag: Option<&str>) -> Result<Option<(Issue, Option<String>)>> {
+        let route = format!("/repos/{}/{}/issues/{}", self.owner, self.repo, number);
+        let headers = match etag {
+            Some(etag) => {
+                let mut headers = http::HeaderMap::new();
+                headers.insert(http::header::IF_NONE_MATCH, etag.parse().context("Invalid ETag")?);
+                Some(headers)
+            }
+            None => None,
+        };
+        let response = self
+            .client
+            ._get_with_headers(route, headers)
+            .await
+            .context("Failed to get GitHub issue")?;
+        let header = |name: &str| response.headers().get(name).and_then(|value| value.to_str().ok());
+        if let Some(limit) = RateLimit::from_headers(header) {
+            self.rate_limits.update(limit);
+        }
+        if response.status() == http::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let etag = header("etag").map(str::to_string);
+
+        let response = octocrab::map_github_error(response).await.context("Failed to get GitHub issue")?;
+        let body = self.client.body_to_string(response).await.context("Failed to read GitHub issue")?;
+        let issue = serde_json::from_str(&body).context("Failed to parse GitHub issue")?;
+
+        Ok(Some((self.convert_issue(issue), etag)))
+    }
+
+    /// Issues in the repo, optionally only those in milestone `milestone`
+    async fn list_issues_in(&self, milestone: Option<u64>) -> Result<Vec<Issue>> {
+        // Fetched as raw JSON because octocrab's issue model drops the
+        // `reactions` summary GitHub includes with each issue
+        let milestone = milestone.map(|m| m.to_string());
+        let mut issues = Vec::new();
+
+        // Every page, until one comes back short
+        for page in 1.. {
+            let page = page.to_string();
+            let mut params = vec![("state", "all"), ("per_page", "100"), ("page", page.as_str())];
+            if let Some(milestone) = &milestone {
+                params.push(("milestone", milestone));
+            }
+            let items: Vec<serde_json::Value> = self
+                .client
+                .get(format!("/repos/{}/{}/issues", self.owner, self.repo), Some(&params))
+                .await
+                .context("Failed to list GitHub issues")?;
+            let last_page = items.len() < 100;
+
+            for item in items {
+                let reactions = item["reactions"]["total_count"].as_u64();
+                let issue: octocrab::models::issues::Issue = serde_json::from_value(item)
+                    .context("Failed to parse GitHub issue")?;
+
+                // The issues endpoint also returns pull requests
+                if issue.pull_request.is_some() {
+                    continue;
+                }
+
+                issues.push(Issue { reactions, ..self.convert_issue(issue) });
+            }
+
+            if last_page {
+                break;
+            }
         }
+
+        Ok(issues)
     }
 }
 
+/// Issues fetched per GraphQL request by `get_issues_batch`
+const ISSUE_BATCH_SIZE: usize = 50;
+
+/// What `get_issues_batch` asks for about each issue
+const ISSUE_FIELDS: &str = "databaseId id number title body state url closedAt \
+    labels(first: 100) { nodes { name } } comments { totalCount } reactions { totalCount } milestone { title }";
+
+impl GitHubBackend {
+    /// Fetch issues with one GraphQL request, each aliased `i<number>`
+    async fn get_issues_query(&self, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+        let issues: String = numbers
+            .iter()
+            .map(|n| format!("    i{}: issue(number: {}) {{ {} }}\n", n, n, ISSUE_FIELDS))
+            .collect();
+        let query = format!("query($owner: String!, $repo: String!) {{\n  repository(owner: $owner, name: $repo) {{\n{}  }}\n}}", issues);
+        let response: serde_json::Value = self
+            .client
+            .graphql(&json!({ "query": query, "variables": { "owner": self.owner, "repo": self.repo } }))
+            .await
+            .context("GitHub GraphQL request failed")?;
+
+        // Issues that don't exist come back null, each with a NOT_FOUND error
+        if let Some(errors) = response["errors"].as_array().filter(|errors| errors.iter().any(|e| e["type"] != "NOT_FOUND")) {
+            anyhow::bail!("GitHub GraphQL request failed: {}", serde_json::Value::from(errors.clone()));
+        }
+
+        let repository = &response["data"]["repository"];
+        numbers
+            .iter()
+            .map(|n| (*n, &repository[format!("i{}", n)]))
+            .filter(|(_, node)| !node.is_null())
+            .map(|(n, node)| Ok((n, graphql_issue(node).with_context(|| format!("Unexpected GraphQL response for issue #{}", n))?)))
+            .collect()
+    }
+}
+
+/// An issue from a GraphQL response with `ISSUE_FIELDS`
+fn graphql_issue(node: &serde_json::Value) -> Result<Issue> {
+    let text = |field: &str| node[field].as_str().map(str::to_string).with_context(|| format!("No {}", field));
+    let count = |field: &str| node[field]["totalCount"].as_u64();
+
+    Ok(Issue {
+        id: node["databaseId"].as_u64().context("No databaseId")?,
+        node_id: text("id")?,
+        number: node["number"].as_u64().context("No number")?,
+        title: text("title")?,
+        body: normalize_remote_body(node["body"].as_str().unwrap_or_default()),
+        state: text("state")?.to_lowercase(),
+        labels: node["labels"]["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|label| label["name"].as_str().map(str::to_string))
+            .collect(),
+        url: text("url")?,
+        comments: count("comments"),
+        reactions: count("reactions"),
+        closed_at: node["closedAt"]
+            .as_str()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| at.with_timezone(&Utc)),
+        milestone: node["milestone"]["title"].as_str().map(str::to_string),
+    })
+}
+
+const BOARD_FIELD_QUERY: &str = r#"
+query($project: ID!, $field: String!) {
+  node(id: $project) {
+    ... on ProjectV2 {
+      field(name: $field) {
+        ... on ProjectV2SingleSelectField { id options { id name } }
+      }
+    }
+  }
+}"#;
+
+const ADD_BOARD_ITEM_MUTATION: &str = r#"
+mutation($project: ID!, $content: ID!) {
+  addProjectV2ItemById(input: {projectId: $project, contentId: $content}) {
+    item { id }
+  }
+}"#;
+
+const SET_BOARD_COLUMN_MUTATION: &str = r#"
+mutation($project: ID!, $item: ID!, $field: ID!, $option: String!) {
+  updateProjectV2ItemFieldValue(input: {
+    projectId: $project, itemId: $item, fieldId: $field,
+    value: {singleSelectOptionId: $option}
+  }) {
+    projectV2Item { id }
+  }
+}"#;
+
 #[async_trait]
 impl Backend for GitHubBackend {
-    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>) -> Result<Issue> {
+    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
         let issue = self
             .client
             .issues(&self.owner, &self.repo)
             .create(title)
             .body(body)
             .labels(labels)
+            .assignees(assignees)
             .send()
             .await
             .context("Failed to create GitHub issue")?;
@@ -66,43 +340,429 @@ impl Backend for GitHubBackend {
         Ok(self.convert_issue(issue))
     }
 
-    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>) -> Result<Issue> {
+    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
+        let issues = self.client.issues(&self.owner, &self.repo);
+        let mut request = issues
+            .update(number)
+            .title(title)
+            .body(body)
+            .labels(&labels);
+
+        if !assignees.is_empty() {
+            request = request.assignees(&assignees);
+        }
+
+        let issue = request
+            .send()
+            .await
+            .context("Failed to update GitHub issue")?;
+
+        Ok(self.convert_issue(issue))
+    }
+
+    async fn update_title(&self, number: u64, title: &str) -> Result<Issue> {
         let issue = self
             .client
             .issues(&self.owner, &self.repo)
             .update(number)
             .title(title)
-            .body(body)
-            .labels(&labels)
             .send()
             .await
-            .context("Failed to update GitHub issue")?;
+            .with_context(|| format!("Failed to update the title of GitHub issue #{}", number))?;
 
         Ok(self.convert_issue(issue))
     }
 
-    async fn get_issue(&self, number: u64) -> Result<Issue> {
+    async fn close_issue(&self, number: u64) -> Result<Issue> {
         let issue = self
             .client
             .issues(&self.owner, &self.repo)
-            .get(number)
+            .update(number)
+            .state(octocrab::models::IssueState::Closed)
+            .send()
             .await
-            .context("Failed to get GitHub issue")?;
+            .with_context(|| format!("Failed to close GitHub issue #{}", number))?;
 
         Ok(self.convert_issue(issue))
     }
 
+    async fn close_issue_with_reason(&self, number: u64, reason: CloseReason) -> Result<Issue> {
+        let reason = match reason {
+            CloseReason::Completed => IssueStateReason::Completed,
+            CloseReason::NotPlanned => IssueStateReason::NotPlanned,
+        };
+        let issue = self
+            .client
+            .issues(&self.owner, &self.repo)
+            .update(number)
+            .state(octocrab::models::IssueState::Closed)
+            .state_reason(reason)
+            .send()
+            .await
+            .with_context(|| format!("Failed to close GitHub issue #{}", number))?;
+
+        Ok(self.convert_issue(issue))
+    }
+
+    async fn get_issue(&self, number: u64) -> Result<Issue> {
+        let (issue, _) = self
+            .fetch_issue(number, None)
+            .await?
+            .context("GitHub answered an unconditional request with 304 Not Modified")?;
+        Ok(issue)
+    }
+
+    async fn get_issue_if_changed(&self, number: u64, etag: Option<&str>) -> Result<Option<(Issue, Option<String>)>> {
+        self.fetch_issue(number, etag).await
+    }
+
     async fn list_issues(&self) -> Result<Vec<Issue>> {
+        self.list_issues_in(None).await
+    }
+
+    async fn list_issues_by_milestone(&self, milestone: &str) -> Result<Vec<Issue>> {
+        let number = self.milestone_number(milestone).await?;
+        self.list_issues_in(Some(number)).await
+    }
+
+    async fn add_label(&self, number: u64, label: &str) -> Result<()> {
+        self.client
+            .issues(&self.owner, &self.repo)
+            .add_labels(number, &[label.to_string()])
+            .await
+            .with_context(|| format!("Failed to label GitHub issue #{}", number))?;
+
+        Ok(())
+    }
+
+    async fn find_issue_by_key(&self, key: &str) -> Result<Option<Issue>> {
+        let query = format!("\"{}\" repo:{}/{} is:issue in:body", key, self.owner, self.repo);
+
+        // The search API has a much lower rate limit than the rest of the API,
+        // so back off and retry instead of failing the whole task
+        let mut attempt = 0;
+        let page = loop {
+            match self.client.search().issues_and_pull_requests(&query).send().await {
+                Ok(page) => break page,
+                Err(e) if is_rate_limited(&e) && attempt + 1 < SEARCH_ATTEMPTS => {
+                    let delay = Duration::from_secs(10 << attempt);
+                    eprintln!("Search rate limit hit, retrying in {}s", delay.as_secs());
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e).context("Failed to search GitHub issues"),
+            }
+        };
+
+        // Search matches loosely, so confirm the key is really in the body
+        Ok(page
+            .items
+            .into_iter()
+            .find(|i| i.body.as_deref().is_some_and(|b| b.contains(key)))
+            .map(|i| self.convert_issue(i)))
+    }
+
+    async fn list_comments(&self, number: u64) -> Result<Vec<Comment>> {
         let page = self
             .client
             .issues(&self.owner, &self.repo)
-            .list()
-            .state(octocrab::params::State::All)
+            .list_comments(number)
             .per_page(100)
             .send()
             .await
-            .context("Failed to list GitHub issues")?;
+            .context("Failed to list GitHub issue comments")?;
+
+        let comments = self
+            .client
+            .all_pages(page)
+            .await
+            .context("Failed to list GitHub issue comments")?;
+
+        Ok(comments
+            .into_iter()
+            .map(|c| Comment {
+                author: c.user.login,
+                body: c.body.unwrap_or_default(),
+                created_at: c.created_at.to_rfc3339(),
+            })
+            .collect())
+    }
+
+    async fn add_comment(&self, number: u64, body: &str) -> Result<()> {
+        self.client
+            .issues(&self.owner, &self.repo)
+            .create_comment(number, body)
+            .await
+            .with_context(|| format!("Failed to comment on GitHub issue #{}", number))?;
+        Ok(())
+    }
+
+    async fn set_board_column(&self, issue: &Issue, board: &ProjectBoardConfig, column: &str) -> Result<()> {
+        let data = self
+            .graphql(BOARD_FIELD_QUERY, json!({ "project": board.project_id, "field": board.status_field }))
+            .await?;
+
+        let field = &data["node"]["field"];
+        let field_id = field["id"]
+            .as_str()
+            .with_context(|| format!("Project board has no single-select field '{}'", board.status_field))?;
+        let option_id = field["options"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|o| o["name"].as_str().is_some_and(|n| n.eq_ignore_ascii_case(column)))
+            .and_then(|o| o["id"].as_str())
+            .with_context(|| format!("Project board has no '{}' column", column))?;
+
+        // Adding an issue that's already on the board returns the existing item
+        let data = self
+            .graphql(ADD_BOARD_ITEM_MUTATION, json!({ "project": board.project_id, "content": issue.node_id }))
+            .await?;
+        let item_id = data["addProjectV2ItemById"]["item"]["id"]
+            .as_str()
+            .context("GitHub did not return a project item id")?;
+
+        self.graphql(
+            SET_BOARD_COLUMN_MUTATION,
+            json!({ "project": board.project_id, "item": item_id, "field": field_id, "option": option_id }),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn ensure_label(&self, name: &str, color: &str) -> Result<()> {
+        // GitHub label names are case-insensitive
+        let key = name.to_lowercase();
+
+        let known = self.labels.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let known = match known {
+            Some(known) => known,
+            None => {
+                let page = self
+                    .client
+                    .issues(&self.owner, &self.repo)
+                    .list_labels_for_repo()
+                    .per_page(100)
+                    .send()
+                    .await
+                    .context("Failed to list GitHub labels")?;
+                let labels = self
+                    .client
+                    .all_pages(page)
+                    .await
+                    .context("Failed to list GitHub labels")?;
+
+                let known: HashSet<String> = labels.into_iter().map(|l| l.name.to_lowercase()).collect();
+                *self.labels.lock().unwrap_or_else(|e| e.into_inner()) = Some(known.clone());
+                known
+            }
+        };
+
+        if known.contains(&key) {
+            return Ok(());
+        }
+
+        self.client
+            .issues(&self.owner, &self.repo)
+            .create_label(name, color, "")
+            .await
+            .with_context(|| format!("Failed to create GitHub label '{}'", name))?;
+
+        if let Some(known) = self.labels.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            known.insert(key);
+        }
+
+        Ok(())
+    }
+
+    async fn list_collaborators(&self) -> Result<Option<Vec<String>>> {
+        let page = self
+            .client
+            .repos(&self.owner, &self.repo)
+            .list_collaborators()
+            .per_page(100)
+            .send()
+            .await
+            .context("Failed to list GitHub collaborators")?;
+
+        let collaborators = self
+            .client
+            .all_pages(page)
+            .await
+            .context("Failed to list GitHub collaborators")?;
+
+        Ok(Some(collaborators.into_iter().map(|c| c.author.login).collect()))
+    }
+
+    async fn set_milestone(&self, number: u64, milestone: &str) -> Result<()> {
+        let milestone_number = self.milestone_number(milestone).await?;
+
+        self.client
+            .issues(&self.owner, &self.repo)
+            .update(number)
+            .milestone(milestone_number)
+            .send()
+            .await
+            .with_context(|| format!("Failed to set milestone on GitHub issue #{}", number))?;
+
+        Ok(())
+    }
+
+    async fn verify_repo(&self) -> Result<RepoAccess> {
+        let repo = match self.client.repos(&self.owner, &self.repo).get().await {
+            Ok(repo) => repo,
+            Err(e) if error_kind(&e) == ErrorKind::NotFound => return Ok(RepoAccess::NotFound),
+            Err(e) => return Err(e).context("Failed to get GitHub repository"),
+        };
+
+        // Permissions are only reported to authenticated users
+        Ok(match repo.permissions {
+            Some(p) if p.push || p.maintain || p.admin => RepoAccess::Write,
+            _ => RepoAccess::ReadOnly,
+        })
+    }
+
+    /// `ISSUE_BATCH_SIZE` issues per GraphQL request, falling back to fetching
+    /// each on its own if a request fails
+    async fn get_issues_batch(&self, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+        let mut numbers = numbers.to_vec();
+        numbers.sort_unstable();
+        numbers.dedup();
+
+        let mut issues = HashMap::new();
+        for batch in numbers.chunks(ISSUE_BATCH_SIZE) {
+            match self.get_issues_query(batch).await {
+                Ok(fetched) => issues.extend(fetched),
+                Err(e) => {
+                    eprintln!("Warning: fetching issues one at a time: {:#}", e);
+                    issues.extend(get_issues_one_by_one(self, batch).await?);
+                }
+            }
+        }
+        Ok(issues)
+    }
+
+    fn rate_limit_state(&self) -> Option<Arc<RateLimitState>> {
+        Some(self.rate_limits.clone())
+    }
+
+    async fn rate_limit_status(&self) -> Result<Option<Vec<RateLimit>>> {
+        let status = self
+            .client
+            .ratelimit()
+            .get()
+            .await
+            .context("Failed to get GitHub rate limits")?;
+
+        let resources = &status.resources;
+        let rates = [("core", Some(&resources.core)), ("search", Some(&resources.search)), ("graphql", resources.graphql.as_ref())];
+        Ok(Some(
+            rates
+                .into_iter()
+                .filter_map(|(resource, rate)| {
+                    let rate = rate?;
+                    Some(RateLimit {
+                        resource: resource.to_string(),
+                        limit: rate.limit as u64,
+                        remaining: rate.remaining as u64,
+                        reset: DateTime::from_timestamp(rate.reset as i64, 0)?,
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    /// Requests for an issue moved to another repo are redirected there, so
+    /// the issue that comes back says where it went
+    fn transfer_of(&self, issue: &Issue) -> Option<Transfer> {
+        transferred_to(&issue.url, &format!("{}/{}", self.owner, self.repo))
+    }
+
+    async fn add_sub_issue(&self, parent: u64, child: u64) -> Result<()> {
+        let route = format!("/repos/{}/{}/issues/{}/sub_issues", self.owner, self.repo, parent);
+        let existing: Vec<serde_json::Value> = self
+            .client
+            .get(&route, Some(&[("per_page", "100")]))
+            .await
+            .with_context(|| format!("Failed to list sub-issues of GitHub issue #{}", parent))?;
+        if existing.iter().any(|issue| issue["number"].as_u64() == Some(child)) {
+            return Ok(());
+        }
+
+        // Sub-issues are added by their database id, not their number
+        let child_id = self
+            .client
+            .issues(&self.owner, &self.repo)
+            .get(child)
+            .await
+            .context("Failed to get GitHub issue")?
+            .id
+            .0;
+        let _: serde_json::Value = self
+            .client
+            .post(&route, Some(&json!({ "sub_issue_id": child_id })))
+            .await
+            .with_context(|| format!("Failed to add #{} as a sub-issue of GitHub issue #{}", child, parent))?;
+
+        Ok(())
+    }
+}
+
+/// Where an issue's html URL says it lives, if that's not `repo`
+///
+/// Repo names are compared case-insensitively, as on GitHub.
+pub fn transferred_to(url: &str, repo: &str) -> Option<Transfer> {
+    let path = url.strip_prefix("https://github.com/")?;
+    let (issue_repo, number) = path.split_once("/issues/")?;
+    let number = number.parse().ok()?;
+
+    (!issue_repo.eq_ignore_ascii_case(repo)).then(|| Transfer { repo: issue_repo.to_string(), number, url: url.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphql_issue() {
+        let node = json!({
+            "databaseId": 101, "id": "I_kw", "number": 7, "title": "Fix it", "body": "Text\r\n", "state": "CLOSED",
+            "url": "https://github.com/a/b/issues/7", "closedAt": "2024-05-01T12:00:00Z",
+            "labels": { "nodes": [{ "name": "bug" }] }, "comments": { "totalCount": 2 }, "reactions": { "totalCount": 0 },
+            "milestone": null
+        });
+        let issue = graphql_issue(&node).unwrap();
+        assert_eq!((issue.id, issue.number, issue.state.as_str(), issue.body.as_str()), (101, 7, "closed", "Text"));
+        assert_eq!(issue.labels, vec!["bug"]);
+        assert_eq!(issue.comments, Some(2));
+        assert_eq!(issue.closed_at, DateTime::from_timestamp(1_714_564_800, 0));
+        assert_eq!(issue.milestone, None);
+
+        assert!(graphql_issue(&json!({ "number": 7 })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_base_url_from_backend_config() {
+        let settings: HashMap<String, serde_json::Value> =
+            serde_json::from_str(r#"{"base_url": "https://github.example.com/api/v3", "project_id": 4}"#).unwrap();
+        let options = GitHubOptions::from_backend_config(&settings).unwrap();
+        assert_eq!(options.base_url.as_deref(), Some("https://github.example.com/api/v3"));
+        assert!(GitHubBackend::with_options("token", "a/b", options).is_ok());
+
+        assert_eq!(GitHubOptions::from_backend_config(&HashMap::new()).unwrap(), GitHubOptions::default());
+        let bad = GitHubOptions { base_url: Some("not a url".to_string()) };
+        assert!(GitHubBackend::with_options("token", "a/b", bad).is_err());
+    }
+
+    #[test]
+    fn test_transferred_to() {
+        let transfer = transferred_to("https://github.com/acme/tracker/issues/12", "acme/app").unwrap();
+        assert_eq!(transfer, Transfer { repo: "acme/tracker".to_string(), number: 12, url: "https://github.com/acme/tracker/issues/12".to_string() });
 
-        Ok(page.items.into_iter().map(|i| self.convert_issue(i)).collect())
+        // Only another repo is a transfer, whatever the case
+        assert_eq!(transferred_to("https://github.com/Acme/App/issues/3", "acme/app"), None);
+        assert_eq!(transferred_to("https://github.com/acme/app/pull/3", "acme/tracker"), None);
+        assert_eq!(transferred_to("https://example.com/acme/app/issues/3", "acme/tracker"), None);
     }
 }
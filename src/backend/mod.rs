@@ -1,30 +1,404 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
+use crate::types::{ProjectBoardConfig, ProjectConfig};
+
+pub mod error;
+pub mod fs;
 pub mod github;
 
+/// Backend names accepted in the project.md `backend` field
+pub const SUPPORTED_BACKENDS: &[&str] = &["github", "fs"];
+
+/// Check that a project's configured backend is one we can construct
+pub fn ensure_supported(backend: &str) -> Result<()> {
+    if !SUPPORTED_BACKENDS.contains(&backend) {
+        anyhow::bail!(
+            "Unsupported backend: {}. Supported backends: {}",
+            backend,
+            SUPPORTED_BACKENDS.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Whether a backend needs a GitHub token to be constructed
+pub fn needs_token(backend: &str) -> bool {
+    backend == "github"
+}
+
+/// Create the backend configured for a project
+///
+/// For the `fs` backend, `repo` is a directory relative to `project_root`.
+/// Each backend reads the `backend_config` keys it knows and ignores the rest.
+pub fn create_backend(config: &ProjectConfig, project_root: &Path, token: Option<&str>) -> Result<Box<dyn Backend>> {
+    ensure_supported(&config.backend)?;
+
+    match config.backend.as_str() {
+        "github" => {
+            let token = token
+                .context("GitHub token is required. Set GITHUB_TOKEN env var or use --github-token")?;
+            let options = github::GitHubOptions::from_backend_config(&config.backend_config)?;
+            Ok(Box::new(github::GitHubBackend::with_options(token, &config.repo, options)?))
+        }
+        "fs" => Ok(Box::new(fs::FileSystemBackend::new(&project_root.join(&config.repo)))),
+        _ => unreachable!("checked by ensure_supported"),
+    }
+}
+
 /// Represents an issue in the backend system
 #[derive(Debug, Clone)]
 pub struct Issue {
     pub id: u64,
+    pub node_id: String,
     pub number: u64,
     pub title: String,
+    /// With LF line endings and no trailing whitespace (see `normalize_remote_body`)
     pub body: String,
     pub state: String,
+    pub labels: Vec<String>,
+    pub url: String,
+    /// Number of comments, if the backend reports it
+    pub comments: Option<u64>,
+    /// Total reactions on the issue itself, if the backend reports it
+    pub reactions: Option<u64>,
+    /// When the issue was closed, if it is closed and the backend reports it
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Title of the issue's milestone, if it has one and the backend reports it
+    pub milestone: Option<String>,
+}
+
+/// What the token may do in the configured repo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoAccess {
+    /// Issues can be created and edited
+    Write,
+    /// The repo is visible but the token can't push to it
+    ReadOnly,
+    /// The repo doesn't exist, or is private and hidden from the token
+    NotFound,
+    /// The backend has no notion of access to check
+    Unchecked,
+}
+
+/// Quota left on one of a backend's rate-limited APIs
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    /// Which API the quota is for, e.g. `core` or `search`
+    pub resource: String,
+    pub limit: u64,
+    pub remaining: u64,
+    /// When the quota is next refilled
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    /// The quota reported by GitHub's `x-ratelimit-*` response headers, looked
+    /// up with `header`
+    pub fn from_headers<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Option<Self> {
+        let number = |name: &str| header(name)?.trim().parse::<u64>().ok();
+        Some(Self {
+            resource: header("x-ratelimit-resource").unwrap_or("core").to_string(),
+            limit: number("x-ratelimit-limit")?,
+            remaining: number("x-ratelimit-remaining")?,
+            reset: DateTime::from_timestamp(number("x-ratelimit-reset")? as i64, 0)?,
+        })
+    }
+}
+
+/// The quota left as of a backend's latest response, shared with the sync
+/// engine so it can slow down as the quota runs out
+#[derive(Debug, Default)]
+pub struct RateLimitState {
+    latest: Mutex<Option<RateLimit>>,
+}
+
+impl RateLimitState {
+    pub fn update(&self, limit: RateLimit) {
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(limit);
+    }
+
+    pub fn latest(&self) -> Option<RateLimit> {
+        self.latest.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Where an issue went after being transferred to another repo
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transfer {
+    /// `owner/repo` it now lives in
+    pub repo: String,
+    pub number: u64,
+    pub url: String,
+}
+
+/// Why an issue was closed, as GitHub's `state_reason`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Completed,
+    NotPlanned,
+}
+
+impl CloseReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::Completed => "completed",
+            CloseReason::NotPlanned => "not_planned",
+        }
+    }
+}
+
+/// A comment on an issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+    pub created_at: String,
 }
 
 /// Backend trait for issue management
 #[async_trait]
 pub trait Backend: Send + Sync {
     /// Create a new issue
-    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>) -> Result<Issue>;
+    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue>;
 
     /// Update an existing issue
-    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>) -> Result<Issue>;
+    ///
+    /// Labels are replaced; assignees are only replaced when non-empty so that
+    /// issues assigned by hand on the backend keep their assignees.
+    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue>;
+
+    /// Change just an issue's title, leaving its body, labels and assignees be
+    async fn update_title(&self, number: u64, title: &str) -> Result<Issue> {
+        let issue = self.get_issue(number).await?;
+        self.update_issue(number, title, &issue.body, issue.labels, Vec::new()).await
+    }
+
+    /// Close an issue
+    async fn close_issue(&self, number: u64) -> Result<Issue>;
+
+    /// Close an issue, recording why where the backend can
+    async fn close_issue_with_reason(&self, number: u64, _reason: CloseReason) -> Result<Issue> {
+        self.close_issue(number).await
+    }
 
     /// Get an issue by number
     async fn get_issue(&self, number: u64) -> Result<Issue>;
 
-    /// List all issues
+    /// Get an issue unless it hasn't changed since the response that gave
+    /// `etag`: `None` if it hasn't, otherwise the issue and its new ETag
+    ///
+    /// GitHub doesn't count unchanged responses against the rate limit, so
+    /// polling with this costs only as many requests as issues that changed.
+    /// Backends without ETags fetch the issue every time.
+    async fn get_issue_if_changed(&self, number: u64, _etag: Option<&str>) -> Result<Option<(Issue, Option<String>)>> {
+        Ok(Some((self.get_issue(number).await?, None)))
+    }
+
+    /// List all issues, excluding pull requests
     async fn list_issues(&self) -> Result<Vec<Issue>>;
+
+    /// Find the issue whose body carries the given idempotency key
+    async fn find_issue_by_key(&self, key: &str) -> Result<Option<Issue>>;
+
+    /// List the comments on an issue, oldest first
+    async fn list_comments(&self, number: u64) -> Result<Vec<Comment>>;
+
+    /// Comment on an issue
+    async fn add_comment(&self, _number: u64, _body: &str) -> Result<()> {
+        anyhow::bail!("Comments are not supported by this backend")
+    }
+
+    /// Add an issue to a project board (if needed) and move it to a column
+    async fn set_board_column(&self, _issue: &Issue, _board: &ProjectBoardConfig, _column: &str) -> Result<()> {
+        anyhow::bail!("Project boards are not supported by this backend")
+    }
+
+    /// Create a label with the given color unless it already exists
+    ///
+    /// Backends that create labels as issues use them can leave this a no-op.
+    async fn ensure_label(&self, _name: &str, _color: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Logins that can be assigned issues, or `None` if the backend has no
+    /// such list
+    async fn list_collaborators(&self) -> Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// Put an issue in the milestone with the given title
+    async fn set_milestone(&self, _number: u64, _milestone: &str) -> Result<()> {
+        anyhow::bail!("Milestones are not supported by this backend")
+    }
+
+    /// Issues in the milestone with the given title, open or closed
+    async fn list_issues_by_milestone(&self, milestone: &str) -> Result<Vec<Issue>> {
+        let issues = self.list_issues().await?;
+        Ok(issues.into_iter().filter(|i| i.milestone.as_deref() == Some(milestone)).collect())
+    }
+
+    /// Add a label to an issue, keeping its others
+    async fn add_label(&self, number: u64, label: &str) -> Result<()> {
+        let issue = self.get_issue(number).await?;
+        if issue.labels.iter().any(|l| l == label) {
+            return Ok(());
+        }
+
+        let labels = issue.labels.iter().cloned().chain([label.to_string()]).collect();
+        self.update_issue(number, &issue.title, &issue.body, labels, Vec::new()).await?;
+        Ok(())
+    }
+
+    /// Check that the repo exists and the token can write to it
+    async fn verify_repo(&self) -> Result<RepoAccess> {
+        Ok(RepoAccess::Unchecked)
+    }
+
+    /// Current rate limit quotas, or `None` if the backend has no rate limits
+    async fn rate_limit_status(&self) -> Result<Option<Vec<RateLimit>>> {
+        Ok(None)
+    }
+
+    /// Fetch specific issues by number, leaving out those that don't exist
+    ///
+    /// Backends that can fetch several issues per request should; by default
+    /// each issue is fetched on its own.
+    async fn get_issues_batch(&self, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+        get_issues_one_by_one(self, numbers).await
+    }
+
+    /// The quota left as of the latest response, updated as requests are made,
+    /// or `None` if the backend doesn't track one
+    fn rate_limit_state(&self) -> Option<Arc<RateLimitState>> {
+        None
+    }
+
+    /// Where an issue this backend returned lives, if requests for it were
+    /// redirected to another repo it was transferred to (`None` if they
+    /// weren't, or the backend can't tell)
+    fn transfer_of(&self, _issue: &Issue) -> Option<Transfer> {
+        None
+    }
+
+    /// Make issue `child` a sub-issue of `parent`, doing nothing if it already is
+    async fn add_sub_issue(&self, _parent: u64, _child: u64) -> Result<()> {
+        anyhow::bail!("Sub-issues are not supported by this backend")
+    }
+}
+
+/// Fetch each issue with `get_issue`, leaving out those not found
+pub async fn get_issues_one_by_one<B: Backend + ?Sized>(backend: &B, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+    let mut issues = HashMap::new();
+    for &number in numbers {
+        match backend.get_issue(number).await {
+            Ok(issue) => {
+                issues.insert(number, issue);
+            }
+            Err(e) if error::classify(&e) == error::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(issues)
+}
+
+/// Forward to the boxed backend so `SyncEngine<Box<dyn Backend>>` works
+#[async_trait]
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
+        (**self).create_issue(title, body, labels, assignees).await
+    }
+
+    async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>, assignees: Vec<String>) -> Result<Issue> {
+        (**self).update_issue(number, title, body, labels, assignees).await
+    }
+
+    async fn update_title(&self, number: u64, title: &str) -> Result<Issue> {
+        (**self).update_title(number, title).await
+    }
+
+    async fn close_issue(&self, number: u64) -> Result<Issue> {
+        (**self).close_issue(number).await
+    }
+
+    async fn close_issue_with_reason(&self, number: u64, reason: CloseReason) -> Result<Issue> {
+        (**self).close_issue_with_reason(number, reason).await
+    }
+
+    async fn get_issue(&self, number: u64) -> Result<Issue> {
+        (**self).get_issue(number).await
+    }
+
+    async fn get_issue_if_changed(&self, number: u64, etag: Option<&str>) -> Result<Option<(Issue, Option<String>)>> {
+        (**self).get_issue_if_changed(number, etag).await
+    }
+
+    async fn list_issues(&self) -> Result<Vec<Issue>> {
+        (**self).list_issues().await
+    }
+
+    async fn find_issue_by_key(&self, key: &str) -> Result<Option<Issue>> {
+        (**self).find_issue_by_key(key).await
+    }
+
+    async fn list_comments(&self, number: u64) -> Result<Vec<Comment>> {
+        (**self).list_comments(number).await
+    }
+
+    async fn add_comment(&self, number: u64, body: &str) -> Result<()> {
+        (**self).add_comment(number, body).await
+    }
+
+    async fn set_board_column(&self, issue: &Issue, board: &ProjectBoardConfig, column: &str) -> Result<()> {
+        (**self).set_board_column(issue, board, column).await
+    }
+
+    async fn ensure_label(&self, name: &str, color: &str) -> Result<()> {
+        (**self).ensure_label(name, color).await
+    }
+
+    async fn list_collaborators(&self) -> Result<Option<Vec<String>>> {
+        (**self).list_collaborators().await
+    }
+
+    async fn set_milestone(&self, number: u64, milestone: &str) -> Result<()> {
+        (**self).set_milestone(number, milestone).await
+    }
+
+    async fn list_issues_by_milestone(&self, milestone: &str) -> Result<Vec<Issue>> {
+        (**self).list_issues_by_milestone(milestone).await
+    }
+
+    async fn add_label(&self, number: u64, label: &str) -> Result<()> {
+        (**self).add_label(number, label).await
+    }
+
+    async fn verify_repo(&self) -> Result<RepoAccess> {
+        (**self).verify_repo().await
+    }
+
+    async fn rate_limit_status(&self) -> Result<Option<Vec<RateLimit>>> {
+        (**self).rate_limit_status().await
+    }
+
+    async fn get_issues_batch(&self, numbers: &[u64]) -> Result<HashMap<u64, Issue>> {
+        (**self).get_issues_batch(numbers).await
+    }
+
+    fn rate_limit_state(&self) -> Option<Arc<RateLimitState>> {
+        (**self).rate_limit_state()
+    }
+
+    fn transfer_of(&self, issue: &Issue) -> Option<Transfer> {
+        (**self).transfer_of(issue)
+    }
+
+    async fn add_sub_issue(&self, parent: u64, child: u64) -> Result<()> {
+        (**self).add_sub_issue(parent, child).await
+    }
 }
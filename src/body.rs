@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::newline::to_lf;
+use crate::types::TaskFileConfig;
+
+/// Marker opening the managed footer projectmd appends to issue bodies
+const FOOTER_MARKER: &str = "<!-- projectmd:";
+
+/// Marker opening the metadata block projectmd puts at the top of issue bodies
+const META_MARKER: &str = "<!-- projectmd-meta";
+
+/// Machine-readable task metadata embedded at the top of an issue body
+///
+/// Lets an LLM (or `pull`) reading the issue reconstruct the task's front
+/// matter. Kept inside an HTML comment so GitHub doesn't render it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IssueMeta {
+    pub path: String,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Only known once the issue exists, so absent from freshly created issues
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_id: Option<u64>,
+}
+
+impl IssueMeta {
+    pub fn new(task_path: &Path, config: &TaskFileConfig, issue_id: Option<u64>) -> Self {
+        Self {
+            path: normalize_path(task_path),
+            task_type: config.task_type.clone(),
+            tags: config.tags.clone(),
+            issue_id: issue_id.or(config.issue_id),
+        }
+    }
+
+    /// Whether the block describes the task at `task_path`
+    pub fn is_for(&self, task_path: &Path) -> bool {
+        self.path == normalize_path(task_path)
+    }
+}
+
+/// Deterministic idempotency key for a task, derived from its path
+///
+/// Uses FNV-1a rather than `DefaultHasher` so keys stay stable across Rust
+/// versions and platforms.
+pub fn idempotency_key(task_path: &Path) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in normalize_path(task_path).bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    format!("projectmd-{:016x}", hash)
+}
+
+/// The managed footer identifying which task an issue belongs to
+pub fn managed_footer(task_path: &Path) -> String {
+    format!(
+        "{} key={} path={} -->",
+        FOOTER_MARKER,
+        idempotency_key(task_path),
+        normalize_path(task_path)
+    )
+}
+
+/// Render a task body for the backend, appending the managed footer
+pub fn render_body(task_path: &Path, body: &str) -> String {
+    let body = body.trim_end();
+    let footer = managed_footer(task_path);
+
+    if body.is_empty() {
+        footer
+    } else {
+        format!("{}\n\n{}", body, footer)
+    }
+}
+
+/// Render a task body for the backend with the metadata block and managed footer
+///
+/// Any metadata block already at the top of `body` is replaced, so rendering
+/// a pulled body again doesn't stack blocks.
+pub fn render_issue_body(meta: &IssueMeta, task_path: &Path, body: &str) -> String {
+    let (_, body) = split_meta(body);
+    format!("{}\n\n{}", meta_block(meta), render_body(task_path, body))
+}
+
+/// The metadata block for an issue body
+pub fn meta_block(meta: &IssueMeta) -> String {
+    // Serializing a plain struct of strings can't fail
+    let yaml = serde_yaml::to_string(meta).unwrap_or_default();
+    format!("{}\n{}-->", META_MARKER, yaml)
+}
+
+/// Split the metadata block off the top of an issue body, if it has one
+///
+/// A block that doesn't parse is treated as ordinary content.
+pub fn split_meta(body: &str) -> (Option<IssueMeta>, &str) {
+    let Some(rest) = body.trim_start().strip_prefix(META_MARKER) else {
+        return (None, body);
+    };
+    let Some(end) = rest.find("-->") else {
+        return (None, body);
+    };
+
+    match serde_yaml::from_str(&rest[..end]) {
+        Ok(meta) => (Some(meta), rest[end + 3..].trim_start()),
+        Err(_) => (None, body),
+    }
+}
+
+/// The human-visible part of an issue body, without projectmd's managed blocks
+pub fn strip_managed(body: &str) -> &str {
+    let (_, body) = split_meta(body);
+    split_footer(body).0
+}
+
+/// Split an issue body into its content and managed footer, if any
+pub fn split_footer(body: &str) -> (&str, Option<&str>) {
+    match body.rfind(FOOTER_MARKER) {
+        Some(idx) => (body[..idx].trim_end(), Some(body[idx..].trim_end())),
+        None => (body, None),
+    }
+}
+
+/// Normalize an issue body read from the backend so it compares equal to a
+/// locally rendered one: CRLF line endings become LF and trailing whitespace
+/// at the end is dropped
+///
+/// Trailing spaces inside the body are kept, as markdown uses them for line
+/// breaks.
+pub fn normalize_remote_body(body: &str) -> String {
+    to_lf(body).trim_end().to_string()
+}
+
+/// Check if two issue bodies have the same content, ignoring projectmd's
+/// metadata block and managed footer
+///
+/// Local bodies are compared to rendered remote ones, so without this every
+/// issue would look changed once it has a footer.
+pub fn bodies_match(local: &str, remote: &str) -> bool {
+    let content = |body: &str| strip_managed(&normalize_remote_body(body)).trim().to_string();
+    content(local) == content(remote)
+}
+
+/// Point relative image paths in markdown (`![alt](path)`) at `base_url`
+///
+/// `dir` is the task file's directory relative to the repo root, which paths
+/// starting with `/` are relative to. URLs, anchors, paths leaving the repo and
+/// anything inside fenced code blocks are left alone. Returns the body and the
+/// repo-relative paths of the images rewritten.
+pub fn rewrite_image_paths(body: &str, base_url: &str, dir: &Path) -> (String, Vec<String>) {
+    let mut out = String::with_capacity(body.len());
+    let mut images = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            out.push_str(line);
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = Some(marker);
+            out.push_str(line);
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("![") {
+            let Some((dest_start, dest_end)) = image_destination(&rest[start..]) else {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                continue;
+            };
+            let (dest_start, dest_end) = (start + dest_start, start + dest_end);
+            out.push_str(&rest[..dest_start]);
+
+            let dest = &rest[dest_start..dest_end];
+            match repo_path(dest, dir) {
+                Some(path) => {
+                    out.push_str(&format!("{}/{}", base_url.trim_end_matches('/'), path));
+                    images.push(path);
+                }
+                None => out.push_str(dest),
+            }
+            rest = &rest[dest_end..];
+        }
+        out.push_str(rest);
+    }
+
+    (out, images)
+}
+
+/// Byte range of the link destination in text starting with an image, without
+/// any `<>` around it or title after it
+fn image_destination(image: &str) -> Option<(usize, usize)> {
+    let open = image.find("](")? + 2;
+    let close = open + image[open..].find(')')?;
+    let dest = &image[open..close];
+
+    if let Some(inner) = dest.strip_prefix('<') {
+        let end = inner.find('>')?;
+        return Some((open + 1, open + 1 + end));
+    }
+    let leading = dest.len() - dest.trim_start().len();
+    let end = dest[leading..].find(char::is_whitespace).map_or(dest.len(), |i| leading + i);
+    Some((open + leading, open + end))
+}
+
+/// The repo-relative path an image destination refers to, if it's a local path
+fn repo_path(dest: &str, dir: &Path) -> Option<String> {
+    if dest.is_empty() || dest.contains(':') || dest.starts_with('#') || dest.starts_with("//") {
+        return None;
+    }
+
+    let mut parts: Vec<String> = match dest.strip_prefix('/') {
+        Some(_) => Vec::new(),
+        None => normalize_path(dir).split('/').filter(|p| !p.is_empty()).map(str::to_string).collect(),
+    };
+    for part in dest.trim_start_matches('/').split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part.to_string()),
+        }
+    }
+
+    Some(parts.join("/"))
+}
+
+fn normalize_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idempotency_key_is_stable() {
+        let key = idempotency_key(Path::new("tasks/setup_auth.md"));
+
+        assert_eq!(key, idempotency_key(Path::new("tasks/setup_auth.md")));
+        assert_ne!(key, idempotency_key(Path::new("tasks/other.md")));
+        assert_eq!(key, "projectmd-3e35994203886cdd");
+    }
+
+    #[test]
+    fn test_render_and_split_footer() {
+        let path = Path::new("tasks/setup_auth.md");
+        let rendered = render_body(path, "Some details.\n");
+
+        assert_eq!(
+            rendered,
+            format!("Some details.\n\n<!-- projectmd: key={} path=tasks/setup_auth.md -->", idempotency_key(path))
+        );
+        assert_eq!(split_footer(&rendered), ("Some details.", Some(managed_footer(path).as_str())));
+        assert_eq!(split_footer("No footer"), ("No footer", None));
+    }
+
+    #[test]
+    fn test_meta_block_round_trip() {
+        let path = Path::new("tasks/setup_auth.md");
+        let config = TaskFileConfig {
+            task_type: Some("feature".to_string()),
+            tags: Some(vec!["backend".to_string(), "auth".to_string()]),
+            ..Default::default()
+        };
+        let meta = IssueMeta::new(path, &config, Some(12));
+
+        let rendered = render_issue_body(&meta, path, "Some details.\n");
+        assert!(rendered.starts_with(
+            "<!-- projectmd-meta\npath: tasks/setup_auth.md\ntype: feature\ntags:\n- backend\n- auth\nissue_id: 12\n-->\n\nSome details."
+        ));
+
+        let (parsed, rest) = split_meta(&rendered);
+        assert_eq!(parsed, Some(meta.clone()));
+        assert_eq!(split_footer(rest).0, "Some details.");
+        assert_eq!(strip_managed(&rendered), "Some details.");
+
+        // Rendering an already rendered body replaces the block instead of stacking it
+        assert_eq!(render_issue_body(&meta, path, split_footer(&rendered).0), rendered);
+    }
+
+    #[test]
+    fn test_normalize_remote_body() {
+        let path = Path::new("tasks/setup_auth.md");
+        let meta = IssueMeta::new(path, &TaskFileConfig::default(), Some(12));
+        let rendered = render_issue_body(&meta, path, "Line one  \nLine two\n");
+
+        // GitHub sends back what was pushed with CRLF endings and trailing blanks
+        let remote = format!("{} \r\n\r\n", rendered.replace('\n', "\r\n"));
+        assert_ne!(remote, rendered);
+        assert_eq!(normalize_remote_body(&remote), rendered);
+        assert_eq!(split_meta(&normalize_remote_body(&remote)).0, Some(meta));
+        assert_eq!(normalize_remote_body(""), "");
+    }
+
+    #[test]
+    fn test_bodies_match_ignores_managed_blocks() {
+        let path = Path::new("tasks/setup_auth.md");
+        let meta = IssueMeta::new(path, &TaskFileConfig::default(), Some(12));
+        let remote = render_issue_body(&meta, path, "Some details.\n");
+
+        assert_ne!(remote, "Some details.");
+        assert!(bodies_match("Some details.\n", &remote));
+        assert!(bodies_match("Some details.", &render_body(path, "Some details.")));
+        assert!(bodies_match("Some details.\r\n", &remote.replace('\n', "\r\n")));
+        assert!(!bodies_match("Other details.", &remote));
+    }
+
+    #[test]
+    fn test_rewrite_image_paths() {
+        let base = "https://raw.githubusercontent.com/acme/app/main";
+        let body = "See ![diagram](./diagram.png) and ![](../shared/flow.svg \"Flow\").\n\
+                    ![logo](/docs/logo.png) ![remote](https://example.com/a.png) ![spaced](<my image.png>)\n\
+                    ```\n![in code](code.png)\n```\n\
+                    ![escapes](../../../outside.png) [not an image](notes.md)\n";
+
+        let (rewritten, images) = rewrite_image_paths(body, base, Path::new("planning/tasks"));
+        assert_eq!(
+            rewritten,
+            format!(
+                "See ![diagram]({base}/planning/tasks/diagram.png) and ![]({base}/planning/shared/flow.svg \"Flow\").\n\
+                 ![logo]({base}/docs/logo.png) ![remote](https://example.com/a.png) ![spaced](<{base}/planning/tasks/my image.png>)\n\
+                 ```\n![in code](code.png)\n```\n\
+                 ![escapes](../../../outside.png) [not an image](notes.md)\n"
+            )
+        );
+        assert_eq!(
+            images,
+            vec!["planning/tasks/diagram.png", "planning/shared/flow.svg", "docs/logo.png", "planning/tasks/my image.png"]
+        );
+    }
+
+    #[test]
+    fn test_split_meta_ignores_malformed_block() {
+        let body = "<!-- projectmd-meta\n: [\n-->\nText";
+        assert_eq!(split_meta(body), (None, body));
+        assert_eq!(split_meta("Plain text"), (None, "Plain text"));
+    }
+}
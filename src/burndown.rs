@@ -0,0 +1,113 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Columns of `burndown --format csv`, one row per day
+pub const CSV_HEADER: &str = "date,total,completed,remaining,planned";
+
+/// What a burndown needs to know about a task
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurndownTask {
+    pub estimate: f64,
+    /// Day the task was created, or `None` to count it from the start
+    pub created: Option<NaiveDate>,
+    /// Day the task should be done by
+    pub target: Option<NaiveDate>,
+    /// Day the task's issue was closed, if it is closed
+    pub closed: Option<NaiveDate>,
+}
+
+/// Work at the end of a day, in estimate units
+#[derive(Debug, Clone, PartialEq)]
+pub struct BurndownRow {
+    pub date: NaiveDate,
+    /// Estimates of the tasks created by this day
+    pub total: f64,
+    /// Estimates of those closed by this day
+    pub completed: f64,
+    pub remaining: f64,
+    /// What would remain if every task were closed on its target date
+    pub planned: f64,
+}
+
+/// Parse a `target_date` or `created_at`, as a date or an RFC 3339 timestamp
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().or_else(|| {
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|time| time.with_timezone(&Utc).date_naive())
+    })
+}
+
+/// Daily rows from the earliest creation or close date through `today`
+pub fn burndown(tasks: &[BurndownTask], today: NaiveDate) -> Vec<BurndownRow> {
+    let start = tasks
+        .iter()
+        .flat_map(|task| [task.created, task.closed])
+        .flatten()
+        .min()
+        .map_or(today, |start| start.min(today));
+
+    start
+        .iter_days()
+        .take_while(|date| *date <= today)
+        .map(|date| {
+            let mut row = BurndownRow { date, total: 0.0, completed: 0.0, remaining: 0.0, planned: 0.0 };
+            for task in tasks.iter().filter(|task| task.created.is_none_or(|created| created <= date)) {
+                row.total += task.estimate;
+                if task.closed.is_some_and(|closed| closed <= date) {
+                    row.completed += task.estimate;
+                }
+                if task.target.is_none_or(|target| target > date) {
+                    row.planned += task.estimate;
+                }
+            }
+            row.remaining = row.total - row.completed;
+            row
+        })
+        .collect()
+}
+
+/// Format rows as CSV under `CSV_HEADER`
+pub fn to_csv(rows: &[BurndownRow]) -> String {
+    let mut out = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        out.push_str(&format!(
+            "{},{},{},{},{}\n",
+            row.date, row.total, row.completed, row.remaining, row.planned
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        parse_date(value).unwrap()
+    }
+
+    #[test]
+    fn test_burndown_rows() {
+        let tasks = vec![
+            // Done on the second day, a day late
+            BurndownTask { estimate: 3.0, created: Some(date("2025-03-01")), target: Some(date("2025-03-01")), closed: Some(date("2025-03-02")) },
+            // Added on the second day, still open
+            BurndownTask { estimate: 2.0, created: Some(date("2025-03-02T09:30:00Z")), target: Some(date("2025-03-03")), closed: None },
+            // Never synced, so it counts from the start
+            BurndownTask { estimate: 1.0, created: None, target: None, closed: None },
+        ];
+
+        assert_eq!(
+            to_csv(&burndown(&tasks, date("2025-03-03"))),
+            "date,total,completed,remaining,planned\n\
+             2025-03-01,4,0,4,1\n\
+             2025-03-02,6,3,3,3\n\
+             2025-03-03,6,3,3,1\n"
+        );
+
+        // Nothing dated yet is a single row for today
+        let rows = burndown(&tasks[2..], date("2025-03-03"));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].remaining, 1.0);
+    }
+}
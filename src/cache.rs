@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::encoding::read_utf8_within;
+use crate::parser::parse_task_file_with;
+use crate::types::FrontMatter;
+
+/// Where `status --cached` keeps task details, relative to the project root
+pub const STATUS_CACHE_FILE: &str = ".projectmd/status-cache.json";
+
+/// The task file details `status --verbose` shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSummary {
+    pub title: String,
+    pub task_type: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// A task file's summary, along with the file's mtime and size when read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedSummary {
+    mtime: DateTime<Utc>,
+    len: u64,
+    summary: TaskSummary,
+}
+
+/// Task file summaries from the last status run, keyed by task path
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatusCache {
+    entries: HashMap<PathBuf, CachedSummary>,
+    /// Lookups answered from the cache since it was loaded
+    #[serde(skip)]
+    pub hits: usize,
+}
+
+impl StatusCache {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(STATUS_CACHE_FILE)
+    }
+
+    /// Load the cache; a missing or unreadable cache is just empty
+    pub fn load(project_root: &Path) -> Self {
+        fs::read_to_string(Self::path(project_root))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = Self::path(project_root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create cache directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context("Failed to write status cache")
+    }
+
+    /// Summary of the task file at `path`, re-read only if its mtime or size
+    /// changed since it was cached
+    ///
+    /// Files over `max_bytes` fail without being read.
+    pub fn summary(&mut self, project_root: &Path, path: &Path, front_matter: FrontMatter, max_bytes: Option<u64>) -> Result<TaskSummary> {
+        let task_file_path = project_root.join(path);
+        let metadata = fs::metadata(&task_file_path)
+            .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+        let mtime: DateTime<Utc> = metadata.modified()?.into();
+        let len = metadata.len();
+
+        if let Some(cached) = self.entries.get(path) {
+            if cached.mtime == mtime && cached.len == len {
+                self.hits += 1;
+                return Ok(cached.summary.clone());
+            }
+        }
+
+        let content = read_utf8_within(&task_file_path, max_bytes)
+            .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+        let task_file = parse_task_file_with(&content, front_matter)?;
+        let summary = TaskSummary {
+            title: task_file.title,
+            task_type: task_file.config.task_type,
+            tags: task_file.config.tags,
+        };
+
+        self.entries.insert(path.to_path_buf(), CachedSummary { mtime, len, summary: summary.clone() });
+        Ok(summary)
+    }
+
+    /// Drop entries for files that are no longer tasks
+    pub fn retain(&mut self, paths: &[&Path]) {
+        self.entries.retain(|path, _| paths.contains(&path.as_path()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_cache_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "---\ntype: bug\n---\n# A\n").unwrap();
+        fs::write(root.join("b.md"), "---\ntags: [infra]\n---\n# B\n").unwrap();
+
+        let mut cache = StatusCache::load(root);
+        let a = cache.summary(root, Path::new("a.md"), FrontMatter::Required, None).unwrap();
+        cache.summary(root, Path::new("b.md"), FrontMatter::Required, None).unwrap();
+        assert_eq!(cache.hits, 0);
+        assert_eq!(a.task_type.as_deref(), Some("bug"));
+        cache.save(root).unwrap();
+
+        // Unchanged files come from the cache, changed ones are re-read
+        fs::write(root.join("b.md"), "---\ntags: [infra, docs]\n---\n# B v2\n").unwrap();
+        let mut cache = StatusCache::load(root);
+        assert_eq!(cache.summary(root, Path::new("a.md"), FrontMatter::Required, None).unwrap(), a);
+        let b = cache.summary(root, Path::new("b.md"), FrontMatter::Required, None).unwrap();
+        assert_eq!(cache.hits, 1);
+        assert_eq!(b.title, "B v2");
+
+        cache.retain(&[Path::new("b.md")]);
+        assert!(!cache.entries.contains_key(Path::new("a.md")));
+    }
+}
@@ -1,10 +1,14 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "projectmd")]
 #[command(about = "A plain text LLM-friendly project management system", long_about = None)]
 pub struct Cli {
+    /// Run as if started in this directory, like `git -C`; every other path is relative to it
+    #[arg(short = 'C', long, value_name = "DIR")]
+    pub chdir: Option<PathBuf>,
+
     /// Path to the project.md file
     #[arg(short, long, default_value = "project.md")]
     pub project_file: PathBuf,
@@ -13,6 +17,18 @@ pub struct Cli {
     #[arg(long)]
     pub github_token: Option<String>,
 
+    /// File to read the GitHub token from, taking precedence over the above (can be set via GITHUB_TOKEN_FILE, which --github-token wins over)
+    #[arg(long, value_name = "PATH")]
+    pub github_token_file: Option<PathBuf>,
+
+    /// Backend-specific settings as a JSON object, over project.md's backend_config
+    #[arg(long, global = true, value_name = "JSON")]
+    pub backend_config: Option<String>,
+
+    /// Print where the GitHub token was found (never the token itself)
+    #[arg(long, global = true)]
+    pub print_token_source: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -24,18 +40,294 @@ pub enum Commands {
         /// Dry run - show what would be done without making changes
         #[arg(long)]
         dry_run: bool,
+
+        /// With --dry-run, show the resolved title, labels and assignees per task
+        /// (-vv adds the full body, diffed against the current issue when it can
+        /// be fetched); otherwise report each task as it completes
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+
+        /// With --dry-run, exit non-zero if any task would be created or updated,
+        /// or any issue closed
+        #[arg(long, requires = "dry_run")]
+        dry_run_exit_code: bool,
+
+        /// Truncate titles longer than max_title_len (appending …)
+        #[arg(long)]
+        truncate_titles: bool,
+
+        /// Search for an issue carrying the task's idempotency key before creating it
+        #[arg(long)]
+        idempotent: bool,
+
+        /// Only sync tasks whose files changed relative to this git ref (e.g. main)
+        #[arg(long, value_name = "REF")]
+        changed_since: Option<String>,
+
+        /// Queue creates and updates in .projectmd/queue.json instead of sending them
+        #[arg(long, conflicts_with = "dry_run")]
+        offline: bool,
+
+        /// Refuse to sync if project.md or a task file has uncommitted changes
+        #[arg(long)]
+        require_clean: bool,
+
+        /// Warn about assignees who aren't collaborators on the repo (fetches the collaborator list)
+        #[arg(long)]
+        validate_assignees: bool,
+
+        /// Also assign the owners of each task file in the repo's CODEOWNERS
+        #[arg(long)]
+        assign_from_codeowners: bool,
+
+        /// Check that the repo exists and the token can write to it before syncing
+        #[arg(long)]
+        repo_check: bool,
+
+        /// Only sync tasks whose files have this tag
+        #[arg(long, value_name = "LABEL")]
+        tag: Option<String>,
+
+        /// Record the new repo and number of transferred issues in their task files
+        #[arg(long)]
+        follow_transfers: bool,
+
+        /// Print why each task was created, updated or skipped
+        #[arg(long)]
+        explain: bool,
+
+        /// Write the planned changes to PATH as a markdown report, with diffs for updates (implies --dry-run)
+        #[arg(long, value_name = "PATH")]
+        dump_plan_md: Option<PathBuf>,
+
+        /// Write the title, labels and body each task's issue would get to DIR/<path>.issue.md (implies --dry-run)
+        #[arg(long, value_name = "DIR")]
+        render_to: Option<PathBuf>,
+
+        /// What to print when the sync finishes
+        #[arg(long, value_enum, default_value_t = SyncOutput::Text, conflicts_with_all = ["dry_run", "explain", "dump_plan_md", "render_to"])]
+        output: SyncOutput,
+
+        /// Re-run the whole sync up to N more times while any task fails
+        #[arg(long, value_name = "N", default_value_t = 0, conflicts_with = "dry_run")]
+        retry_run: usize,
+
+        /// YAML or JSON file of tag_labels and label_color_rules to use instead of label_map_file
+        #[arg(long, value_name = "PATH")]
+        map_labels_file: Option<PathBuf>,
+
+        /// Set created_at to now in every task file synced, except those with freeze_created_at
+        #[arg(long)]
+        reset_timestamps: bool,
     },
 
+    /// Send the operations queued by `sync --offline`
+    Flush {
+        /// Search for an issue carrying the task's idempotency key before creating it
+        #[arg(long)]
+        idempotent: bool,
+    },
+
+    /// Update task front matter (type, tags, issue_id) from the issues' metadata blocks
+    Pull {
+        /// Number of issues to fetch at once
+        #[arg(long, default_value_t = projectmd::sync::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+
+    /// Pull from the issues every so often until Ctrl-C, to keep task files
+    /// current with issues edited on the backend
+    WatchRemote {
+        /// Seconds between pulls
+        #[arg(long, value_name = "SECS", default_value_t = 60)]
+        interval: u64,
+
+        /// Number of issues to fetch at once
+        #[arg(long, default_value_t = projectmd::sync::DEFAULT_CONCURRENCY)]
+        concurrency: usize,
+    },
+
+    /// Close every issue carrying a tag (e.g. for sprint cleanup)
+    CloseAll {
+        /// Tag of the tasks (and label of the issues) to close
+        #[arg(long)]
+        tag: String,
+
+        /// Confirm closing the issues
+        #[arg(long)]
+        confirm: bool,
+
+        /// Show which issues would be closed without closing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Label or close every issue in a milestone (e.g. label v2.0 as released)
+    Milestone {
+        /// Title of the milestone
+        milestone: String,
+
+        /// Label to add to each issue
+        #[arg(long, required_unless_present = "close", conflicts_with = "close")]
+        label: Option<String>,
+
+        /// Close each issue instead
+        #[arg(long)]
+        close: bool,
+
+        /// Confirm closing the issues
+        #[arg(long)]
+        confirm: bool,
+
+        /// Show which issues would change without changing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check the project and task files for problems without syncing
+    Validate,
+
+    /// Check that every issue project.md references still exists
+    Verify {
+        /// Turn references to missing issues back into [new] tasks
+        #[arg(long)]
+        fix: bool,
+
+        /// Confirm rewriting project.md with --fix
+        #[arg(long)]
+        confirm: bool,
+    },
+
+    /// List issues project.md references that the backend doesn't have, and
+    /// the backend's issues project.md doesn't reference
+    Gaps,
+
     /// Show the status of all tasks
     Status {
         /// Show detailed information
         #[arg(short, long)]
         verbose: bool,
+
+        /// With --verbose, only re-read task files changed since the last cached run
+        #[arg(long)]
+        cached: bool,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+
+        /// Print at most this many tasks (text and NDJSON output)
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Also show tasks whose files set `archived: true`
+        #[arg(long)]
+        include_archived: bool,
+    },
+
+    /// Add a [new] task to project.md, under the tasks_section heading
+    Add {
+        /// What the task is about
+        description: String,
+
+        /// Task file, for tasks that have one
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+
+    /// Link an existing issue to a task file, creating the file if needed
+    Adopt {
+        /// Issue to adopt, e.g. #12
+        issue: String,
+
+        /// Task file to link it to, relative to project.md
+        path: PathBuf,
+
+        /// Replace the file's title and body with the issue's
+        #[arg(long)]
+        pull: bool,
+    },
+
+    /// Write a table of contents of project.md's headings at its <!-- toc --> marker
+    Toc {
+        /// Only check that the table is current, exiting non-zero if not
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Rewrite project.md in the current format: the current version, `*`
+    /// bullets and canonical markers
+    Upgrade {
+        /// Only check whether an upgrade is needed, exiting non-zero if so
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// List task files that no project.md line references
+    Orphans {
+        /// Directory to look for task files in, relative to project.md
+        #[arg(long, default_value = projectmd::sync::DEFAULT_TASK_DIR)]
+        dir: PathBuf,
+
+        /// Add a [new] line for each, described by its title
+        #[arg(long)]
+        add: bool,
+    },
+
+    /// Generate project.md from a directory of existing task files
+    Generate {
+        /// Directory of task files to list, relative to project.md
+        #[arg(long, value_name = "DIR")]
+        from: PathBuf,
+
+        /// Backend to use (github, fs)
+        #[arg(short, long, default_value = "github")]
+        backend: String,
+
+        /// Repository in owner/repo format
+        #[arg(short, long)]
+        repo: String,
+
+        /// Overwrite an existing project.md
+        #[arg(long)]
+        force: bool,
     },
 
+    /// Print the task list
+    Tasks {
+        /// How to lay the tasks out
+        #[arg(long, value_enum, default_value_t = TaskLayout::Flat)]
+        layout: TaskLayout,
+
+        /// Print at most this many tasks
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Also show tasks whose files set `archived: true`
+        #[arg(long)]
+        include_archived: bool,
+    },
+
+    /// List each distinct tag of the task files with how many tasks use it
+    Tags {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Print remaining work per day, from task estimates and issue close dates
+    Burndown {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = BurndownFormat::Text)]
+        format: BurndownFormat,
+    },
+
+    /// Show the backend's remaining API quota and when it resets
+    Ratelimit,
+
     /// Initialize a new project.md file
     Init {
-        /// Backend to use (github)
+        /// Backend to use (github, fs)
         #[arg(short, long, default_value = "github")]
         backend: String,
 
@@ -43,4 +335,79 @@ pub enum Commands {
         #[arg(short, long)]
         repo: String,
     },
+
+    /// Show everything about a single task
+    Show {
+        /// Task path (tasks/foo.md) or issue number (#12)
+        target: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Open a task's issue in the browser
+    Open {
+        /// Task path (tasks/foo.md) or issue number (#12)
+        target: String,
+
+        /// Print the issue URL instead of opening it
+        #[arg(long)]
+        print_url: bool,
+    },
+
+    /// Debugging helpers for troubleshooting parser issues
+    #[command(hide = true)]
+    Debug {
+        #[command(subcommand)]
+        command: DebugCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DebugCommands {
+    /// Print the parsed structures of a project or task file without syncing
+    Parse {
+        /// Task file to parse (defaults to the project file and its tasks)
+        file: Option<PathBuf>,
+
+        /// Increase detail (-v dumps referenced task files, -vv adds pest rule traces)
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+    /// One JSON object per line; `status` streams a line per task
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum SyncOutput {
+    /// The full summary, listing each task
+    Text,
+    /// One line of counts, like `created=3 updated=2 skipped=5 queued=0 errors=0`
+    SummaryOnly,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum TaskLayout {
+    /// Aligned status, issue, path and title columns
+    Table,
+    /// Tasks nested under their epics
+    Tree,
+    /// One line per task, as in `status`
+    Flat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum BurndownFormat {
+    /// An aligned table
+    Text,
+    /// `date,total,completed,remaining,planned`, for plotting
+    Csv,
 }
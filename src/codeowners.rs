@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+
+/// Where GitHub looks for CODEOWNERS, relative to the repo root, in order
+pub const CODEOWNERS_FILES: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Owners per path pattern from a CODEOWNERS file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodeOwners {
+    /// Patterns and their owners, in file order
+    rules: Vec<(String, Vec<String>)>,
+    /// The project directory, relative to the repo root CODEOWNERS paths start from
+    prefix: PathBuf,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS lines: a pattern followed by owners, with `#` comments
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(|line| line.split_once('#').map_or(line, |(rule, _)| rule))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                Some((pattern.to_string(), fields.map(str::to_string).collect()))
+            })
+            .collect();
+
+        Self { rules, prefix: PathBuf::new() }
+    }
+
+    /// Read the CODEOWNERS file of the git repo containing the project
+    ///
+    /// Outside a git repo the project directory is taken as the repo root.
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let project_root = match project_root.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => project_root,
+        };
+        let project_root = project_root
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve project directory: {:?}", project_root))?;
+        let repo_root = git::repo_root(&project_root).unwrap_or_else(|_| project_root.clone());
+
+        let path = CODEOWNERS_FILES
+            .iter()
+            .map(|file| repo_root.join(file))
+            .find(|path| path.is_file())
+            .with_context(|| format!("No CODEOWNERS file in {:?} (looked in {})", repo_root, CODEOWNERS_FILES.join(", ")))?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {:?}", path))?;
+
+        let mut codeowners = Self::parse(&content);
+        codeowners.prefix = project_root.strip_prefix(&repo_root).unwrap_or(Path::new("")).to_path_buf();
+        Ok(codeowners)
+    }
+
+    /// Owners of a path relative to the project directory, from the last
+    /// matching rule as on GitHub
+    pub fn owners_for(&self, path: &Path) -> &[String] {
+        let path = self.prefix.join(path).to_string_lossy().replace('\\', "/");
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern_matches(pattern, &path))
+            .map_or(&[], |(_, owners)| owners.as_slice())
+    }
+
+    /// Logins of the users owning a path; teams and emails can't be assigned
+    pub fn assignees_for(&self, path: &Path) -> Vec<String> {
+        self.owners_for(path)
+            .iter()
+            .filter_map(|owner| owner.strip_prefix('@'))
+            .filter(|login| !login.contains('/'))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Match a CODEOWNERS (gitignore-style) pattern against a `/`-separated path
+///
+/// Patterns with a `/` before their end are relative to the root, others match
+/// at any depth. A pattern matching a directory matches everything in it; a
+/// trailing `/` matches only directories.
+pub(crate) fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_end_matches('/');
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+
+    let starts = std::iter::once(0).chain(path.match_indices('/').map(|(i, _)| i + 1));
+    let starts: Vec<usize> = if anchored { vec![0] } else { starts.collect() };
+    let ends = path.match_indices('/').map(|(i, _)| (i, true)).chain(std::iter::once((path.len(), false)));
+    let ends: Vec<(usize, bool)> = ends.collect();
+
+    starts.iter().any(|&start| {
+        ends.iter()
+            .filter(|&&(end, _)| end > start)
+            .any(|&(end, is_dir)| (is_dir || !dir_only) && glob(pattern.as_bytes(), &path.as_bytes()[start..end]))
+    })
+}
+
+/// `*` matches within a path segment, `**` across them and `?` one character
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob(rest, text) || (0..text.len()).any(|i| text[i] == b'/' && glob(rest, &text[i + 1..]))
+        }
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|&c| c == b'/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => matches!(text, [c, ..] if *c != b'/') && glob(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeowners_matching() {
+        let codeowners = CodeOwners::parse(
+            "# Default owners\n\
+             *              @lead\n\
+             *.md           @docs-writer @acme/docs\n\
+             /tasks/api/    @alice # API tasks\n\
+             tasks/**/db_*  @bob\n\
+             infra/         @carol\n\
+             ui/?.md        dave@example.com\n",
+        );
+
+        let owners = |path: &str| codeowners.assignees_for(Path::new(path));
+        assert_eq!(owners("Cargo.toml"), vec!["lead"]);
+        assert_eq!(owners("README.md"), vec!["docs-writer"]);
+        assert_eq!(owners("tasks/api/auth.md"), vec!["alice"]);
+        assert_eq!(owners("tasks/api/v2/auth.md"), vec!["alice"]);
+        assert_eq!(owners("tasks/db_migrate.md"), vec!["bob"]);
+        assert_eq!(owners("tasks/api/db_index.md"), vec!["bob"]);
+        // Unanchored directories match at any depth, but not files of that name
+        assert_eq!(owners("deploy/infra/k8s.md"), vec!["carol"]);
+        assert_eq!(owners("notes/infra"), vec!["lead"]);
+        assert_eq!(owners("ui/a.md"), Vec::<String>::new());
+        assert_eq!(codeowners.owners_for(Path::new("ui/a.md")), ["dave@example.com"]);
+        assert_eq!(owners("ui/ab.md"), vec!["docs-writer"]);
+
+        let nested = CodeOwners { prefix: PathBuf::from("planning"), ..CodeOwners::parse("/planning/tasks/ @alice\n") };
+        assert_eq!(nested.assignees_for(Path::new("tasks/a.md")), vec!["alice"]);
+    }
+}
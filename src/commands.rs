@@ -1,141 +1,1700 @@
 use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::backend::{Backend, github::GitHubBackend};
-use crate::parser::parse_project_file;
-use crate::sync::SyncEngine;
-use crate::types::TaskStatus;
+use crate::cli::{BurndownFormat, OutputFormat, TaskLayout};
+use projectmd::backend::{self, Backend, Comment, Issue};
+use projectmd::cache::{StatusCache, TaskSummary};
+use projectmd::codeowners::CodeOwners;
+use projectmd::encoding::{read_utf8, read_utf8_within};
+use projectmd::frontmatter;
+use projectmd::body::{bodies_match, strip_managed};
+use projectmd::burndown::{parse_date, to_csv, BurndownTask};
+use projectmd::git;
+use projectmd::layout::{format_table, task_tree};
+use projectmd::migrate::{upgrade_project, FORMAT_VERSION};
+use projectmd::newline::{to_lf, NewlineStyle};
+use projectmd::queue::{OfflineBackend, Queue};
+use projectmd::report::{count_tags, LiveReport, StatusReport, TaskReport, TrackedIssue};
+use projectmd::parser::{parse_project_file, parse_task_file, parse_task_file_with, parse_tree};
+use projectmd::plan::{line_diff, render_issue_file, render_plan_md, PlanAction, PlanClose, PlanEntry};
+use projectmd::preprocess::preprocessor_for;
+use projectmd::toc::update_toc;
+use projectmd::sync::{
+    append_task_line, check_clean, check_write_permissions, clear_wip_markers, close_candidates, close_issues, description_task_file, find_gaps, find_orphans, generate_project, is_archived, is_pending, is_tagged, load_label_map, prepare_issue,
+    rewrite_images, set_issue_number, set_status, title_or_stem, SyncEngine, SyncEvent, SyncOptions,
+};
+use projectmd::validate::{check_duplicate_issue_ids, check_task_errors, validate_project};
+use projectmd::types::{format_marker, EpicRef, FrontMatter, ProjectConfig, ProjectMd, TaskFile, TaskFileConfig, TaskItem, TaskStatus};
+
+/// Where the GitHub token was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSource {
+    FileFlag,
+    FileEnv,
+    Flag,
+    Env,
+}
+
+impl TokenSource {
+    fn describe(&self) -> &'static str {
+        match self {
+            TokenSource::FileFlag => "the file given by --github-token-file",
+            TokenSource::FileEnv => "the file named by the GITHUB_TOKEN_FILE environment variable",
+            TokenSource::Flag => "the --github-token flag",
+            TokenSource::Env => "the GITHUB_TOKEN environment variable",
+        }
+    }
+}
+
+/// How to reach the backend, from the global options
+#[derive(Clone, Copy)]
+pub struct Remote<'a> {
+    pub token: Option<&'a str>,
+    /// `--backend-config` settings, over project.md's `backend_config`
+    pub backend_config: &'a HashMap<String, serde_json::Value>,
+}
+
+/// Resolve the GitHub token from the CLI flags or the environment
+///
+/// Flags win over environment variables, and within each a token file wins
+/// over the token itself: `--github-token-file`, `--github-token`,
+/// GITHUB_TOKEN_FILE, then GITHUB_TOKEN. A file keeps the token out of the
+/// environment and process list. With `print_source`, reports on stderr where
+/// the token came from. The token itself is never printed.
+pub fn resolve_token(github_token: Option<&str>, github_token_file: Option<&Path>, print_source: bool) -> Result<Option<String>> {
+    let resolved = match (github_token_file, github_token, std::env::var_os("GITHUB_TOKEN_FILE")) {
+        (Some(path), _, _) => Some((read_token_file(path)?, TokenSource::FileFlag)),
+        (None, Some(token), _) => Some((token.to_string(), TokenSource::Flag)),
+        (None, None, Some(path)) => Some((read_token_file(Path::new(&path))?, TokenSource::FileEnv)),
+        (None, None, None) => std::env::var("GITHUB_TOKEN").ok().map(|token| (token, TokenSource::Env)),
+    };
+
+    if print_source {
+        match &resolved {
+            Some((_, source)) => eprintln!("Using GitHub token from {}", source.describe()),
+            None => eprintln!("No GitHub token found (checked --github-token-file, --github-token, GITHUB_TOKEN_FILE and GITHUB_TOKEN)"),
+        }
+    }
+
+    Ok(resolved.map(|(token, _)| token))
+}
+
+/// Read a token from a file, e.g. a mounted Docker or Kubernetes secret
+fn read_token_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read GitHub token file {:?}", path))?;
+    let token = content.trim();
+    if token.is_empty() {
+        anyhow::bail!("GitHub token file {:?} is empty", path);
+    }
+    Ok(token.to_string())
+}
 
 /// Execute the sync command
-pub async fn sync(project_file: &Path, github_token: &str, dry_run: bool) -> Result<()> {
+///
+/// With `fail_on_pending`, a dry run returns an error if anything would change.
+pub async fn sync(
+    project_file: &Path,
+    remote: Remote<'_>,
+    dry_run: bool,
+    verbose: u8,
+    fail_on_pending: bool,
+    assign_from_codeowners: bool,
+    mut options: SyncOptions,
+) -> Result<()> {
+    let project_root = project_file.parent()
+        .context("Failed to get project root directory")?
+        .to_path_buf();
+
+    if assign_from_codeowners {
+        options.codeowners = Some(CodeOwners::load(&project_root)?);
+    }
+
+    // Read and parse project file
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let mut project = parse_project_file(&content)?;
+    load_label_map(&mut project.config, &project_root, options.label_map_file.as_deref())?;
+
+    // Validate backend
+    backend::ensure_supported(&project.config.backend)?;
+
+    if dry_run {
+        // A real sync checks this in the engine; surface it in dry runs too
+        check_task_errors(&project)?;
+        check_write_permissions(project_file, &project_root, &project)?;
+        check_duplicate_issue_ids(&project_root, &project)?;
+        if options.require_clean {
+            check_clean(project_file, &project_root, &project)?;
+        }
+
+        // Closes, plans and -vv diffs compare against the current issues
+        let backend = live_backend(&project.config, &project_root, remote)?;
+        let tasks = dry_run_tasks(project_file, &project_root, &project, &options)?;
+        let closes = dry_run_closes(&project_root, &tasks, &project.config, backend.as_deref()).await;
+        let pending = print_dry_run(&project_root, &project, &tasks, &closes, &options, verbose, backend.as_deref()).await?;
+        if let Some(path) = &options.plan_md {
+            write_plan_md(path, &project_root, &project, &tasks, &closes, &options, backend.as_deref()).await?;
+        }
+        if let Some(dir) = &options.render_to {
+            render_issues_to(dir, project_file, &project_root, &project, &options)?;
+        }
+        if fail_on_pending && pending > 0 {
+            anyhow::bail!("{} pending changes; run sync to apply them", pending);
+        }
+        return Ok(());
+    }
+
+    // Offline syncs only queue operations, so they need no backend
+    let backend: Box<dyn Backend> = match options.offline {
+        true => Box::new(OfflineBackend),
+        false => create_backend(&project.config, &project_root, remote)?,
+    };
+
+    // Create sync engine and run sync
+    let (explain, summary_only) = (options.explain, options.summary_only);
+    let mut engine = SyncEngine::new(backend, project_root).with_options(options);
+    if verbose > 0 {
+        engine = engine.with_events(print_progress);
+    }
+    let result = engine.sync(project_file).await?;
+
+    // Print summary
+    if summary_only {
+        println!("{}", result.summary_line());
+    } else {
+        if explain {
+            result.print_explanation();
+        }
+        result.print_summary();
+    }
+
+    if !result.errors.is_empty() {
+        anyhow::bail!("Sync completed with errors");
+    }
+
+    Ok(())
+}
+
+/// Report a task's outcome on stderr as it completes, for `sync --verbose`
+fn print_progress(event: SyncEvent) {
+    match event {
+        SyncEvent::Created(task, num) => eprintln!("Created #{}: {}", num, task.name()),
+        SyncEvent::Updated(task, num) => eprintln!("Updated #{}: {}", num, task.name()),
+        SyncEvent::Skipped(task) => eprintln!("Skipped: {}", task.name()),
+        SyncEvent::Queued(task) => eprintln!("Queued: {}", task.name()),
+        SyncEvent::Failed(task, _) => eprintln!("Failed: {}", task.name()),
+    }
+}
+
+/// Execute the flush command: send the operations queued by offline syncs
+pub async fn flush(project_file: &Path, remote: Remote<'_>, options: SyncOptions) -> Result<()> {
+    let project_root = project_file.parent()
+        .context("Failed to get project root directory")?
+        .to_path_buf();
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+
+    if Queue::load(&project_root)?.ops.is_empty() {
+        println!("Nothing queued");
+        return Ok(());
+    }
+
+    let backend = create_backend(&project.config, &project_root, remote)?;
+    let engine = SyncEngine::new(backend, project_root).with_options(options);
+    let result = engine.flush(project_file).await?;
+
+    result.print_summary();
+
+    if !result.errors.is_empty() {
+        anyhow::bail!("Flush completed with errors; failed operations are still queued");
+    }
+
+    Ok(())
+}
+
+/// Execute the pull command
+pub async fn pull(project_file: &Path, remote: Remote<'_>, options: SyncOptions) -> Result<()> {
     let project_root = project_file.parent()
         .context("Failed to get project root directory")?
         .to_path_buf();
 
-    // Read and parse project file
-    let content = fs::read_to_string(project_file)
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+
+    let backend = create_backend(&project.config, &project_root, remote)?;
+    let engine = SyncEngine::new(backend, project_root).with_options(options);
+    let result = engine.pull(project_file).await?;
+
+    result.print_summary();
+
+    if !result.errors.is_empty() {
+        anyhow::bail!("Pull completed with errors");
+    }
+
+    Ok(())
+}
+
+/// Execute the watch-remote command: pull every `interval` seconds until Ctrl-C
+pub async fn watch_remote(project_file: &Path, remote: Remote<'_>, interval: u64, options: SyncOptions) -> Result<()> {
+    let project_root = project_file.parent()
+        .context("Failed to get project root directory")?
+        .to_path_buf();
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+
+    let backend = create_backend(&project.config, &project_root, remote)?;
+    let engine = SyncEngine::new(backend, project_root).with_options(options);
+
+    eprintln!("Pulling from {} every {}s; press Ctrl-C to stop", project.config.repo, interval);
+    let stop = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("Warning: can't listen for Ctrl-C: {:#}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    engine.watch_remote(project_file, Duration::from_secs(interval.max(1)), stop, |result| match result {
+        Ok(result) => {
+            let time = Utc::now().format("%H:%M:%S");
+            for (task, num) in &result.updated {
+                println!("[{}] Pulled #{}: {}", time, num, task.name());
+            }
+            for (task, error) in &result.errors {
+                eprintln!("Warning: failed to pull {}: {}", task.name(), error);
+            }
+        }
+        Err(e) => eprintln!("Warning: pull failed: {:#}", e),
+    }).await;
+
+    Ok(())
+}
+
+/// Print what a sync would do, without changing anything on the backend
+///
+/// Returns the number of tasks sync would create or update plus the number
+/// of issues in `closes`.
+///
+/// From `verbose` 1, each task file is read and the resolved title, labels
+/// and assignees are shown exactly as sync would send them. From 2 the body
+/// is shown too, as a diff against the current issue of updates when
+/// `backend` can fetch it.
+async fn print_dry_run(
+    project_root: &Path,
+    project: &ProjectMd,
+    tasks: &[&TaskItem],
+    closes: &[PlanClose],
+    options: &SyncOptions,
+    verbose: u8,
+    backend: Option<&dyn Backend>,
+) -> Result<usize> {
+    println!("DRY RUN: No changes will be made\n");
+
+    println!("Would sync {} tasks to {}/{}\n",
+        tasks.len(),
+        project.config.backend,
+        project.config.repo);
+
+    let mut pending = 0;
+    for &task in tasks {
+        // A task that can't be read would fail to sync, so it isn't in sync either
+        let task_pending = is_pending(project_root, task, &project.config).unwrap_or(true);
+        if task_pending {
+            pending += 1;
+        }
+
+        match &task.status {
+            _ if !task_pending => {
+                println!("  [SKIP] {}", task_summary(project_root, task, &project.config));
+            }
+            TaskStatus::New => {
+                println!("  [CREATE] {}", task_summary(project_root, task, &project.config));
+            }
+            TaskStatus::Existing(num) => {
+                println!("  [UPDATE] #{} {}", num, task_summary(project_root, task, &project.config));
+            }
+            TaskStatus::InProgress(num) => {
+                println!("  [UPDATE] #{} [WIP] {}", num, task_summary(project_root, task, &project.config));
+            }
+        }
+
+        if verbose > 0 {
+            let task_file = match verbose {
+                1 => load_task_file(project_root, task, &project.config),
+                _ => load_rendered_task_file(project_root, task, &project.config),
+            };
+            match task_file {
+                Ok(task_file) => {
+                    let fields = prepare_issue(task, &task_file, &project.config, options);
+                    println!("       Title: {}", fields.title);
+                    println!("       Labels: {}", display_list(&fields.labels));
+                    println!("       Assignees: {}", display_list(&fields.assignees));
+                    if let Some(milestone) = &fields.milestone {
+                        println!("       Milestone: {}", milestone);
+                    }
+                    if verbose >= 2 && task_pending {
+                        let current = match (task.status.issue_id(), backend) {
+                            (Some(num), Some(backend)) => backend.get_issue(num).await.ok(),
+                            _ => None,
+                        };
+                        let new = format!("# {}\n\n{}", fields.title, fields.body.trim_end());
+                        let (heading, text) = match current {
+                            Some(issue) => ("Diff", line_diff(&format!("# {}\n\n{}", issue.title, issue.body.trim_end()), &new)),
+                            None => ("Body", format!("{}\n", fields.body.trim_end())),
+                        };
+                        println!("       {}:", heading);
+                        for line in text.lines() {
+                            println!("         {}", line);
+                        }
+                    }
+                }
+                Err(e) => println!("       Error: {:#}", e),
+            }
+            println!();
+        }
+    }
+
+    if !closes.is_empty() {
+        println!();
+        for close in closes {
+            println!("  [CLOSE] {}", close.summary());
+        }
+        pending += closes.len();
+    }
+
+    println!("\n{} pending changes", pending);
+
+    Ok(pending)
+}
+
+/// The issues a sync of `tasks` would close after syncing them, leaving out
+/// ones `backend` reports as closed already
+///
+/// Without a backend to ask, every candidate counts, since sync closes any
+/// that are still open.
+async fn dry_run_closes(project_root: &Path, tasks: &[&TaskItem], config: &ProjectConfig, backend: Option<&dyn Backend>) -> Vec<PlanClose> {
+    let (completed, duplicates) = close_candidates(project_root, tasks.iter().copied(), config);
+    let mut closes: Vec<PlanClose> = duplicates
+        .into_iter()
+        .map(|(task, original)| PlanClose { task: task.clone(), number: task.status.issue_id(), duplicate_of: Some(original) })
+        .collect();
+    for task in completed {
+        if !closes.iter().any(|close| close.task == *task) {
+            closes.push(PlanClose { task: task.clone(), number: task.status.issue_id(), duplicate_of: None });
+        }
+    }
+
+    let Some(backend) = backend else { return closes };
+    let numbers: Vec<u64> = closes.iter().filter_map(|close| close.number).collect();
+    if numbers.is_empty() {
+        return closes;
+    }
+    match backend.get_issues_batch(&numbers).await {
+        Ok(issues) => closes.retain(|close| close.number.is_none_or(|n| issues.get(&n).is_none_or(|issue| issue.state != "closed"))),
+        Err(e) => eprintln!("Warning: failed to check which issues are still open: {:#}", e),
+    }
+    closes
+}
+
+/// The tasks a dry run looks at, after `--changed-since` and `--tag`
+fn dry_run_tasks<'a>(project_file: &Path, project_root: &Path, project: &'a ProjectMd, options: &SyncOptions) -> Result<Vec<&'a TaskItem>> {
+    let changed = match &options.changed_since {
+        Some(git_ref) => Some(git::changed_files(project_root, git_ref)?),
+        None => None,
+    };
+    Ok(project.tasks
+        .iter()
+        .filter(|t| {
+            // Description-only tasks live in project.md itself
+            let task_file = t.path.as_ref().map_or(project_file.to_path_buf(), |p| project_root.join(p));
+            changed.as_ref().is_none_or(|c| git::is_changed(c, &task_file))
+        })
+        .filter(|t| match (&options.tag, &t.path) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(tag), Some(path)) => read_utf8_within(project_root.join(path), project.config.max_task_file_bytes)
+                .map_or(true, |content| is_tagged(&content, tag, project.config.front_matter)),
+        })
+        .collect())
+}
+
+/// Write what a sync would do to `path` as a markdown report
+///
+/// With a backend, updates are shown as diffs against their current issues.
+async fn write_plan_md(
+    path: &Path,
+    project_root: &Path,
+    project: &ProjectMd,
+    tasks: &[&TaskItem],
+    closes: &[PlanClose],
+    options: &SyncOptions,
+    backend: Option<&dyn Backend>,
+) -> Result<()> {
+    let mut entries = Vec::new();
+    for &task in tasks {
+        let action = match task.status.issue_id() {
+            _ if !is_pending(project_root, task, &project.config).unwrap_or(true) => PlanAction::Unchanged,
+            Some(num) => PlanAction::Update(num),
+            None => PlanAction::Create,
+        };
+        let current = match (&action, backend) {
+            (PlanAction::Update(num), Some(backend)) => backend.get_issue(*num).await.ok(),
+            _ => None,
+        };
+        let fields = load_task_file(project_root, task, &project.config)
+            .map(|task_file| prepare_issue(task, &task_file, &project.config, options))
+            .map_err(|e| format!("{:#}", e));
+        entries.push(PlanEntry { task: task.clone(), action, fields, current });
+    }
+
+    fs::write(path, render_plan_md(&project.config.repo, &entries, closes))
+        .with_context(|| format!("Failed to write plan: {:?}", path))?;
+    eprintln!("Wrote the sync plan to {}", path.display());
+    Ok(())
+}
+
+/// Write the issue each task would be synced to under `dir`, as
+/// `<path>.issue.md` or, for description-only tasks, `description-<n>.issue.md`
+///
+/// Bodies go through the preprocessor and image rewriting as in a real sync.
+fn render_issues_to(dir: &Path, project_file: &Path, project_root: &Path, project: &ProjectMd, options: &SyncOptions) -> Result<()> {
+    let mut descriptions = 0;
+    let mut written = 0;
+    for task in dry_run_tasks(project_file, project_root, project, options)? {
+        let file = match &task.path {
+            Some(path) if !stays_inside(path) => {
+                eprintln!("Warning: {}: not rendered: the path leads outside {}", task.name(), dir.display());
+                continue;
+            }
+            Some(path) => format!("{}.issue.md", path.display()),
+            None => {
+                descriptions += 1;
+                format!("description-{}.issue.md", descriptions)
+            }
+        };
+
+        let rendered = load_rendered_task_file(project_root, task, &project.config)
+            .and_then(|task_file| render_issue_file(&prepare_issue(task, &task_file, &project.config, options)));
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                eprintln!("Warning: {}: not rendered: {:#}", task.name(), e);
+                continue;
+            }
+        };
+
+        let path = dir.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create directory: {:?}", parent))?;
+        }
+        fs::write(&path, rendered).with_context(|| format!("Failed to write rendered issue: {:?}", path))?;
+        written += 1;
+    }
+
+    eprintln!("Wrote {} rendered issues to {}", written, dir.display());
+    Ok(())
+}
+
+/// Whether `path`, joined to a directory, stays inside it: it is relative and
+/// has no `..`
+fn stays_inside(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir))
+}
+
+/// The backend to fetch live data from, if one can be created
+///
+/// Live data is optional, so a missing token or unsupported backend just
+/// means there is none.
+fn live_backend(config: &ProjectConfig, project_root: &Path, remote: Remote<'_>) -> Result<Option<Box<dyn Backend>>> {
+    if backend::ensure_supported(&config.backend).is_err() || (remote.token.is_none() && backend::needs_token(&config.backend)) {
+        return Ok(None);
+    }
+
+    create_backend(config, project_root, remote).map(Some)
+}
+
+/// Create the project's backend, with `--backend-config` settings over its
+/// `backend_config`
+fn create_backend(config: &ProjectConfig, project_root: &Path, remote: Remote<'_>) -> Result<Box<dyn Backend>> {
+    let mut config = config.clone();
+    config.backend_config.extend(remote.backend_config.clone());
+    backend::create_backend(&config, project_root, remote.token)
+}
+
+/// A task's path and description, or just the description if it has no file
+fn task_summary(project_root: &Path, task: &TaskItem, config: &ProjectConfig) -> String {
+    let description = task_description(project_root, task, config);
+    match &task.path {
+        Some(path) if description.is_empty() => path.display().to_string(),
+        Some(path) => format!("{} - {}", path.display(), description),
+        None => description,
+    }
+}
+
+/// A task's description, or for lines without one its file's title (empty if
+/// the file can't be read)
+fn task_description(project_root: &Path, task: &TaskItem, config: &ProjectConfig) -> String {
+    match &task.description {
+        Some(description) => description.clone(),
+        None => load_task_file(project_root, task, config).map(|f| f.title).unwrap_or_default(),
+    }
+}
+
+/// How `status` and `tasks` show a task's marker
+fn status_label(status: &TaskStatus) -> String {
+    match status {
+        TaskStatus::New => "[NEW]".to_string(),
+        TaskStatus::Existing(num) => format!("[#{}]", num),
+        TaskStatus::InProgress(num) => format!("[WIP #{}]", num),
+    }
+}
+
+/// Read a task's file, or stand one in for a description-only task
+fn load_task_file(project_root: &Path, task: &TaskItem, config: &ProjectConfig) -> Result<TaskFile> {
+    let Some(path) = &task.path else {
+        return Ok(description_task_file(task));
+    };
+
+    let task_file_path = project_root.join(path);
+    let task_content = read_utf8_within(&task_file_path, config.max_task_file_bytes)
+        .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+    parse_task_file_with(&task_content, config.front_matter)
+}
+
+/// A task file with its body as sync pushes it, after the preprocessor and
+/// image rewriting
+fn load_rendered_task_file(project_root: &Path, task: &TaskItem, config: &ProjectConfig) -> Result<TaskFile> {
+    let mut task_file = load_task_file(project_root, task, config)?;
+    task_file.body = preprocessor_for(config, project_root).process(task.path.as_deref(), &task_file.body)?;
+    if let Some(path) = &task.path {
+        task_file.body = rewrite_images(config, project_root, path, task_file.body);
+    }
+    Ok(task_file)
+}
+
+/// Comment and reaction counts of an issue, for counts the backend reported
+fn engagement(comments: Option<u64>, reactions: Option<u64>) -> String {
+    let count = |n: Option<u64>| n.map_or("?".to_string(), |n| n.to_string());
+    format!("{} comments, {} reactions", count(comments), count(reactions))
+}
+
+fn display_list(items: &[String]) -> String {
+    if items.is_empty() {
+        "(none)".to_string()
+    } else {
+        items.join(", ")
+    }
+}
+
+/// Execute the close-all command
+///
+/// Closes the issues of tasks tagged `tag`, plus any open issue on the backend
+/// carrying it as a label.
+pub async fn close_all(project_file: &Path, remote: Remote<'_>, tag: &str, confirm: bool, dry_run: bool) -> Result<()> {
+    if !dry_run && !confirm {
+        anyhow::bail!("close-all closes issues in bulk; re-run with --confirm (or --dry-run to preview)");
+    }
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    backend::ensure_supported(&project.config.backend)?;
+
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    // Issue number -> what to call it, for every tracked task carrying the tag
+    let mut targets: BTreeMap<u64, String> = BTreeMap::new();
+    for task in &project.tasks {
+        let Some(num) = task.status.issue_id() else { continue };
+
+        match load_task_file(project_root, task, &project.config) {
+            Ok(task_file) if task_file.config.tags.iter().flatten().any(|t| t == tag) => {
+                targets.insert(num, task_summary(project_root, task, &project.config));
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Warning: skipping {}: {:#}", task.name(), e),
+        }
+    }
+
+    // A dry run can preview from the task files alone, without a token
+    let backend = match dry_run {
+        true => live_backend(&project.config, project_root, remote)?,
+        false => Some(create_backend(&project.config, project_root, remote)?),
+    };
+    match &backend {
+        Some(backend) => {
+            let issues = backend.list_issues().await?;
+            for issue in issues.iter().filter(|i| i.state == "open" && i.labels.iter().any(|l| l == tag)) {
+                targets.entry(issue.number).or_insert_with(|| issue.title.clone());
+            }
+
+            // Already closed issues need no request
+            let already_closed: HashSet<u64> = issues
+                .iter()
+                .filter(|i| i.state == "closed")
+                .map(|i| i.number)
+                .collect();
+            targets.retain(|num, _| !already_closed.contains(num));
+        }
+        None => eprintln!(
+            "Note: without a GitHub token, only tasks tagged '{}' in their files are listed, including any whose issues are closed",
+            tag
+        ),
+    }
+
+    if targets.is_empty() {
+        println!("No open issues tagged '{}'", tag);
+        return Ok(());
+    }
+
+    let Some(backend) = backend.filter(|_| !dry_run) else {
+        println!("DRY RUN: No changes will be made\n");
+        println!("Would close {} issues tagged '{}':\n", targets.len(), tag);
+        for (num, name) in &targets {
+            println!("  [CLOSE] #{} {}", num, name);
+        }
+        return Ok(());
+    };
+
+    let numbers: Vec<u64> = targets.keys().copied().collect();
+    let mut closed = Vec::new();
+    let mut failed = 0;
+
+    for (num, result) in close_issues(&backend, &numbers).await {
+        match result {
+            Ok(_) => {
+                println!("  [CLOSED] #{} {}", num, targets[&num]);
+                closed.push(num);
+            }
+            Err(e) => {
+                println!("  [ERROR] #{}: {:#}", num, e);
+                failed += 1;
+            }
+        }
+    }
+
+    let style = NewlineStyle::detect(&content).with_line_ending(project.config.line_ending);
+    let updated_content = style.apply(&clear_wip_markers(&to_lf(&content), &closed, &project.config));
+    if updated_content != content {
+        fs::write(project_file, updated_content)
+            .context("Failed to write updated project file")?;
+    }
+
+    println!("\nClosed {} of {} issues", closed.len(), targets.len());
+
+    if failed > 0 {
+        anyhow::bail!("Failed to close {} issues", failed);
+    }
+
+    Ok(())
+}
+
+/// What `milestone` does to each issue in the milestone
+#[derive(Debug, Clone, PartialEq)]
+pub enum MilestoneAction {
+    Label(String),
+    Close,
+}
+
+/// Execute the milestone command: label or close every issue in a milestone
+///
+/// Closing is destructive, so it needs `confirm` unless it is a dry run.
+pub async fn milestone(project_file: &Path, remote: Remote<'_>, milestone: &str, action: MilestoneAction, confirm: bool, dry_run: bool) -> Result<()> {
+    if action == MilestoneAction::Close && !dry_run && !confirm {
+        anyhow::bail!("milestone --close closes issues in bulk; re-run with --confirm (or --dry-run to preview)");
+    }
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    backend::ensure_supported(&project.config.backend)?;
+
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    let backend = create_backend(&project.config, project_root, remote)?;
+
+    // Issues that already have the label, or are already closed, need no request
+    let mut issues = backend.list_issues_by_milestone(milestone).await?;
+    issues.retain(|issue| match &action {
+        MilestoneAction::Label(label) => !issue.labels.contains(label),
+        MilestoneAction::Close => issue.state != "closed",
+    });
+    let verb = match &action {
+        MilestoneAction::Label(label) => format!("label '{}'", label),
+        MilestoneAction::Close => "close".to_string(),
+    };
+
+    if issues.is_empty() {
+        println!("Nothing to {} in milestone '{}'", verb, milestone);
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("DRY RUN: No changes will be made\n");
+        println!("Would {} {} issues in milestone '{}':\n", verb, issues.len(), milestone);
+        for issue in &issues {
+            println!("  #{} {}", issue.number, issue.title);
+        }
+        return Ok(());
+    }
+
+    let numbers: Vec<u64> = issues.iter().map(|issue| issue.number).collect();
+    let (results, marker): (Vec<(u64, Result<()>)>, _) = match &action {
+        MilestoneAction::Label(label) => {
+            let mut results = Vec::with_capacity(numbers.len());
+            for num in &numbers {
+                results.push((*num, backend.add_label(*num, label).await));
+            }
+            (results, "LABELLED")
+        }
+        MilestoneAction::Close => {
+            let closed = close_issues(&backend, &numbers).await;
+            (closed.into_iter().map(|(num, result)| (num, result.map(|_| ()))).collect(), "CLOSED")
+        }
+    };
+
+    let mut done = Vec::new();
+    let mut failed = 0;
+    for (num, result) in results {
+        match result {
+            Ok(()) => {
+                println!("  [{}] #{}", marker, num);
+                done.push(num);
+            }
+            Err(e) => {
+                println!("  [ERROR] #{}: {:#}", num, e);
+                failed += 1;
+            }
+        }
+    }
+
+    if action == MilestoneAction::Close {
+        let style = NewlineStyle::detect(&content).with_line_ending(project.config.line_ending);
+        let updated_content = style.apply(&clear_wip_markers(&to_lf(&content), &done, &project.config));
+        if updated_content != content {
+            fs::write(project_file, updated_content)
+                .context("Failed to write updated project file")?;
+        }
+    }
+
+    println!("\nDone: {} of {} issues", done.len(), issues.len());
+
+    if failed > 0 {
+        anyhow::bail!("Failed to {} {} issues", verb, failed);
+    }
+
+    Ok(())
+}
+
+/// Execute the status command
+pub async fn status(
+    project_file: &Path,
+    remote: Remote<'_>,
+    verbose: bool,
+    cached: bool,
+    format: OutputFormat,
+    limit: Option<usize>,
+    include_archived: bool,
+) -> Result<()> {
+    // Read and parse project file
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let mut project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    if !include_archived {
+        project.tasks.retain(|task| !is_archived(project_root, task, &project.config));
+    }
+
+    let mut cache = (verbose && cached).then(|| StatusCache::load(project_root));
+
+    // NDJSON lines are printed as each task is read, so tasks past the limit
+    // aren't read at all
+    let ndjson = format == OutputFormat::Ndjson;
+    let shown = match (ndjson, limit) {
+        (true, Some(limit)) => limit,
+        _ => usize::MAX,
+    };
+    let mut tasks = Vec::with_capacity(project.tasks.len());
+    for task in project.tasks.iter().take(shown) {
+        // Try to read the task file for more details
+        let details = match (verbose, &mut cache, &task.path) {
+            (false, _, _) => None,
+            (true, Some(cache), Some(path)) => cache.summary(project_root, path, project.config.front_matter, project.config.max_task_file_bytes).ok(),
+            (true, _, _) => load_task_file(project_root, task, &project.config).ok().map(|task_file| TaskSummary {
+                title: task_file.title,
+                task_type: task_file.config.task_type,
+                tags: task_file.config.tags,
+            }),
+        };
+        let details = details.map(|mut details| {
+            if details.task_type.is_none() && project.config.tag_as_type {
+                details.task_type = details.tags.as_ref().and_then(|tags| tags.first().cloned());
+            }
+            details
+        });
+
+        let report = TaskReport {
+            marker: format_marker(&task.status, &project.config),
+            path: task.path.clone(),
+            description: match (&task.description, &details) {
+                (None, Some(details)) => details.title.clone(),
+                _ => task_description(project_root, task, &project.config),
+            },
+            issue_number: task.status.issue_id(),
+            details,
+        };
+        match ndjson {
+            true => println!("{}", serde_json::to_string(&report)?),
+            false => tasks.push(report),
+        }
+    }
+
+    if let Some(cache) = &mut cache {
+        let paths: Vec<&Path> = project.tasks.iter().filter_map(|t| t.path.as_deref()).collect();
+        cache.retain(&paths);
+        if let Err(e) = cache.save(project_root) {
+            eprintln!("Warning: failed to save status cache: {:#}", e);
+        }
+    }
+
+    match format {
+        OutputFormat::Ndjson => {}
+        OutputFormat::Text => print_status(&status_report(project_file, &project, tasks, remote).await?, verbose, limit),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&status_report(project_file, &project, tasks, remote).await?)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&status_report(project_file, &project, tasks, remote).await?)?),
+    }
+
+    Ok(())
+}
+
+/// The status of `tasks`, with the live status of their issues if the backend
+/// can be reached
+async fn status_report(project_file: &Path, project: &ProjectMd, tasks: Vec<TaskReport>, remote: Remote<'_>) -> Result<StatusReport> {
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    // With a token (or a backend that needs none), fetch live status
+    let mut live = None;
+    if let Some(backend) = live_backend(&project.config, project_root, remote)? {
+        eprintln!("Fetching live status from {}...", project.config.backend);
+
+        let issues = backend.list_issues().await?;
+        let by_number: HashMap<u64, &Issue> = issues.iter().map(|i| (i.number, i)).collect();
+
+        let tracked = project.tasks
+            .iter()
+            .filter_map(|t| Some((t, t.status.issue_id()?)))
+            .map(|(task, number)| {
+                let issue = by_number.get(&number);
+                TrackedIssue {
+                    number,
+                    task: task.name(),
+                    state: issue.map(|i| i.state.clone()),
+                    comments: issue.and_then(|i| i.comments),
+                    reactions: issue.and_then(|i| i.reactions),
+                }
+            })
+            .collect();
+
+        live = Some(LiveReport::new(&issues, tracked));
+    }
+
+    Ok(StatusReport {
+        project: project_file.to_path_buf(),
+        backend: project.config.backend.clone(),
+        repo: project.config.repo.clone(),
+        tasks,
+        live,
+    })
+}
+
+/// Print a status report as text, listing at most `limit` tasks and tracked issues
+fn print_status(report: &StatusReport, verbose: bool, limit: Option<usize>) {
+    println!("Project: {}", report.project.display());
+    println!("Backend: {}", report.backend);
+    println!("Repo: {}", report.repo);
+    println!("\nTasks ({}):\n", report.tasks.len());
+
+    for task in report.tasks.iter().take(limit.unwrap_or(usize::MAX)) {
+        let label = match (task.marker.starts_with("[wip:"), task.issue_number) {
+            (true, Some(num)) => format!("[WIP #{}]", num),
+            (false, Some(num)) => format!("[#{}]", num),
+            (_, None) => "[NEW]".to_string(),
+        };
+        match &task.path {
+            Some(path) if task.description.is_empty() => println!("  {} {}", label, path.display()),
+            Some(path) => println!("  {} {} - {}", label, path.display(), task.description),
+            None => println!("  {} {}", label, task.description),
+        }
+
+        if verbose {
+            if let Some(details) = &task.details {
+                println!("       Title: {}", details.title);
+                if let Some(task_type) = &details.task_type {
+                    println!("       Type: {}", task_type);
+                }
+                if let Some(tags) = &details.tags {
+                    println!("       Tags: {}", tags.join(", "));
+                }
+            }
+            println!();
+        }
+    }
+    print_more(report.tasks.len(), limit);
+
+    // The counts cover every issue, whatever the limit
+    let Some(live) = &report.live else { return };
+
+    println!("\nTotal issues in repository: {}", live.total);
+    println!("  Open: {}", live.open);
+    println!("  Closed: {}", live.closed);
+    if live.other > 0 {
+        println!("  Other: {}", live.other);
+    }
+
+    if !live.tracked.is_empty() {
+        println!("\nTracked issues:\n");
+    }
+    for tracked in live.tracked.iter().take(limit.unwrap_or(usize::MAX)) {
+        match &tracked.state {
+            Some(state) => println!(
+                "  #{} {}: {}, {}",
+                tracked.number, tracked.task, state, engagement(tracked.comments, tracked.reactions)
+            ),
+            None => println!("  #{} {}: not in fetched issues", tracked.number, tracked.task),
+        }
+    }
+    print_more(live.tracked.len(), limit);
+}
+
+/// Note how many of `total` items a `--limit` left out
+fn print_more(total: usize, limit: Option<usize>) {
+    match limit {
+        Some(limit) if total > limit => println!("  ... and {} more", total - limit),
+        _ => {}
+    }
+}
+
+/// Execute the add command
+pub fn add(project_file: &Path, description: &str, path: Option<PathBuf>) -> Result<()> {
+    let content = read_utf8(project_file)
         .context("Failed to read project file")?;
 
     let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
 
-    // Validate backend
-    if project.config.backend != "github" {
-        anyhow::bail!("Unsupported backend: {}. Only 'github' is currently supported.",
-            project.config.backend);
+    if description.contains('\n') {
+        anyhow::bail!("Task descriptions must fit on one line");
+    }
+    if path.is_none() && description.contains(" - ") {
+        anyhow::bail!("A description containing ' - ' would be read as a task path; pass --path or reword it");
     }
 
-    if dry_run {
-        println!("DRY RUN: No changes will be made\n");
-        println!("Would sync {} tasks to {}/{}\n",
-            project.tasks.len(),
-            project.config.backend,
-            project.config.repo);
+    let task = TaskItem::new(TaskStatus::New, path, Some(description.to_string()));
+    let updated = append_task_line(&content, project.config.tasks_section(), &task.line());
 
-        for task in &project.tasks {
-            match &task.status {
-                TaskStatus::New => {
-                    println!("  [CREATE] {} - {}", task.path.display(), task.description);
-                }
-                TaskStatus::Existing(num) => {
-                    println!("  [UPDATE] #{} {} - {}", num, task.path.display(), task.description);
-                }
+    // Make sure the line reads back as the task it was meant to be
+    if !parse_project_file(&updated)?.tasks.contains(&task) {
+        anyhow::bail!("'{}' would not be parsed back as a task", task.line());
+    }
+
+    fs::write(project_file, updated)
+        .context("Failed to write project file")?;
+
+    println!("Added: {}", task.line());
+    if let Some(path) = &task.path {
+        if !project_root.join(path).exists() {
+            println!("Note: {} doesn't exist yet; create it before syncing", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute the adopt command
+pub async fn adopt(project_file: &Path, issue: &str, path: &Path, pull: bool, remote: Remote<'_>) -> Result<()> {
+    let num: u64 = issue
+        .trim_start_matches('#')
+        .parse()
+        .with_context(|| format!("Invalid issue number '{}'; expected e.g. #12", issue))?;
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    if let Some(task) = project.tasks.iter().find(|t| t.status.issue_id() == Some(num) && t.path.as_deref() != Some(path)) {
+        anyhow::bail!("#{} is already linked to '{}'", num, task.line_in(&project.config));
+    }
+    let existing = project.tasks.iter().find(|t| t.path.as_deref() == Some(path));
+    if let Some(linked) = existing.and_then(|t| t.status.issue_id()).filter(|linked| *linked != num) {
+        anyhow::bail!("{} is already linked to #{}", path.display(), linked);
+    }
+
+    // Without a token the issue can't be checked, only linked
+    let remote = match live_backend(&project.config, project_root, remote)? {
+        Some(backend) => Some(backend.get_issue(num).await.with_context(|| format!("Can't adopt #{}", num))?),
+        None if pull => anyhow::bail!("--pull needs a backend to fetch issue #{} from; set a GitHub token", num),
+        None => None,
+    };
+
+    // Link the task file
+    let task_file_path = project_root.join(path);
+    let task_content = match task_file_path.exists() {
+        true => {
+            let task_content = read_utf8_within(&task_file_path, project.config.max_task_file_bytes)
+                .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+            let linked = parse_task_file_with(&task_content, FrontMatter::Optional)?.config.issue_id;
+            if let Some(linked) = linked.filter(|linked| *linked != num) {
+                anyhow::bail!("{} already has issue_id: {}", path.display(), linked);
             }
+            task_content
+        }
+        false => {
+            let title = remote.as_ref().map_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string(), |issue| issue.title.clone());
+            format!("# {}\n", title)
+        }
+    };
+    let mut task_content = frontmatter::update_keys(&task_content, &[("issue_id", num.to_string())])?;
+    if let Some(issue) = remote.as_ref().filter(|_| pull) {
+        let body = strip_managed(&issue.body).trim();
+        task_content = frontmatter::replace_markdown(&task_content, &format!("# {}\n\n{}\n", issue.title, body))?;
+    }
+    let task_file = parse_task_file_with(&task_content, project.config.front_matter)?;
+
+    if let Some(parent) = task_file_path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create {:?}", parent))?;
+    }
+    fs::write(&task_file_path, &task_content)
+        .with_context(|| format!("Failed to write task file: {:?}", task_file_path))?;
+
+    // Link the project.md line
+    let updated = match existing {
+        Some(task) if task.status.issue_id() == Some(num) => None,
+        Some(task) => {
+            let style = NewlineStyle::detect(&content);
+            Some(style.apply(&set_issue_number(&to_lf(&content), task, num, &project.config)))
+        }
+        None => {
+            let description = remote.as_ref().map_or_else(|| title_or_stem(&task_file, path), |issue| issue.title.clone());
+            let task = TaskItem::new(TaskStatus::Existing(num), Some(path.to_path_buf()), Some(description));
+            Some(append_task_line(&content, project.config.tasks_section(), &task.line_in(&project.config)))
         }
+    };
+    if let Some(updated) = updated {
+        fs::write(project_file, updated)
+            .context("Failed to write project file")?;
+    }
+
+    println!("Adopted #{} as {}", num, path.display());
+    Ok(())
+}
+
+/// Execute the toc command
+pub fn toc(project_file: &Path, check: bool) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
 
+    let updated = update_toc(&content)?;
+    if updated == content {
+        println!("Table of contents is up to date");
         return Ok(());
     }
 
-    // Create backend
-    let backend = GitHubBackend::new(github_token, &project.config.repo)?;
+    if check {
+        anyhow::bail!("Table of contents in {} is out of date; run projectmd toc", project_file.display());
+    }
 
-    // Create sync engine and run sync
-    let engine = SyncEngine::new(backend, project_root);
-    let result = engine.sync(project_file).await?;
+    fs::write(project_file, updated)
+        .context("Failed to write project file")?;
+    println!("Updated the table of contents in {}", project_file.display());
+    Ok(())
+}
 
-    // Print summary
-    result.print_summary();
+/// Execute the upgrade command
+pub fn upgrade(project_file: &Path, check: bool) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
 
-    if !result.errors.is_empty() {
-        anyhow::bail!("Sync completed with errors");
+    // Lines that don't parse can't be rewritten, so point them out
+    for (line, error) in parse_project_file(&content)?.task_errors {
+        eprintln!("Warning: line {} left as it is: {}", line, error);
+    }
+
+    let upgraded = upgrade_project(&content)?;
+    if upgraded == content {
+        println!("{} is in the current format", project_file.display());
+        return Ok(());
     }
 
+    if check {
+        anyhow::bail!("{} is in an older format; run projectmd upgrade", project_file.display());
+    }
+
+    fs::write(project_file, upgraded)
+        .context("Failed to write project file")?;
+    println!("Upgraded {} to the current format", project_file.display());
     Ok(())
 }
 
-/// Execute the status command
-pub async fn status(project_file: &Path, github_token: Option<&str>, verbose: bool) -> Result<()> {
-    // Read and parse project file
-    let content = fs::read_to_string(project_file)
+/// Execute the orphans command
+pub fn orphans(project_file: &Path, dir: &Path, add: bool) -> Result<()> {
+    let content = read_utf8(project_file)
         .context("Failed to read project file")?;
 
     let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
 
-    println!("Project: {}", project_file.display());
-    println!("Backend: {}", project.config.backend);
-    println!("Repo: {}", project.config.repo);
-    println!("\nTasks ({}):\n", project.tasks.len());
+    let orphans = find_orphans(project_root, dir, &project)?;
+    if orphans.is_empty() {
+        println!("Every task file in {} is in project.md", dir.display());
+        return Ok(());
+    }
 
-    for task in &project.tasks {
-        match &task.status {
-            TaskStatus::New => {
-                println!("  [NEW] {} - {}", task.path.display(), task.description);
+    if !add {
+        println!("Task files not in project.md ({}):", orphans.len());
+        for path in &orphans {
+            println!("  {}", path.display());
+        }
+        println!("\nRun with --add to add them as [new] tasks");
+        return Ok(());
+    }
+
+    let mut updated = content.clone();
+    for path in orphans {
+        let task_file = read_utf8_within(project_root.join(&path), project.config.max_task_file_bytes)
+            .with_context(|| format!("Failed to read task file: {:?}", project_root.join(&path)))
+            .and_then(|content| parse_task_file_with(&content, project.config.front_matter));
+        let description = match task_file {
+            Ok(task_file) => title_or_stem(&task_file, &path),
+            Err(e) => {
+                eprintln!("Warning: skipping {}: {:#}", path.display(), e);
+                continue;
             }
-            TaskStatus::Existing(num) => {
-                println!("  [#{}] {} - {}", num, task.path.display(), task.description);
+        };
+
+        let task = TaskItem::new(TaskStatus::New, Some(path), Some(description));
+        updated = append_task_line(&updated, project.config.tasks_section(), &task.line());
+        println!("Added: {}", task.line());
+    }
+
+    fs::write(project_file, updated)
+        .context("Failed to write project file")
+}
+
+/// Execute the generate command
+pub fn generate(project_file: &Path, from: &Path, backend: &str, repo: &str, force: bool) -> Result<()> {
+    if project_file.exists() && !force {
+        anyhow::bail!("{} already exists; use --force to overwrite it", project_file.display());
+    }
+    backend::ensure_supported(backend)?;
+
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    let content = generate_project(project_root, from, backend, repo)?;
+    let count = parse_project_file(&content)?.tasks.len();
+
+    fs::write(project_file, content)
+        .context("Failed to write project file")?;
+    println!("Generated {} with {} tasks from {}", project_file.display(), count, from.display());
+
+    Ok(())
+}
+
+/// Execute the tasks command
+pub fn tasks(project_file: &Path, layout: TaskLayout, limit: Option<usize>, include_archived: bool) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let mut project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    if !include_archived {
+        project.tasks.retain(|task| !is_archived(project_root, task, &project.config));
+    }
+    let shown = limit.unwrap_or(usize::MAX);
+
+    match layout {
+        TaskLayout::Flat => {
+            for task in project.tasks.iter().take(shown) {
+                println!("{} {}", status_label(&task.status), task_summary(project_root, task, &project.config));
             }
         }
+        TaskLayout::Table => {
+            let rows: Vec<Vec<String>> = project.tasks
+                .iter()
+                .take(shown)
+                .map(|task| {
+                    let status = match task.status {
+                        TaskStatus::New => "new",
+                        TaskStatus::Existing(_) => "open",
+                        TaskStatus::InProgress(_) => "wip",
+                    };
+                    let issue = task.status.issue_id().map_or("-".to_string(), |n| format!("#{}", n));
+                    let path = task.path.as_ref().map_or("-".to_string(), |p| p.display().to_string());
+                    // Tasks whose file can't be read fall back to their description
+                    let title = load_task_file(project_root, task, &project.config)
+                        .map_or_else(|_| task.description.clone().unwrap_or_default(), |f| f.title);
+                    vec![status.to_string(), issue, path, title]
+                })
+                .collect();
 
-        if verbose {
-            // Try to read the task file for more details
-            let project_root = project_file.parent().unwrap_or(Path::new("."));
-            let task_file_path = project_root.join(&task.path);
-
-            if let Ok(task_content) = fs::read_to_string(&task_file_path) {
-                if let Ok(task_file) = crate::parser::parse_task_file(&task_content) {
-                    println!("       Title: {}", task_file.title);
-                    if let Some(task_type) = &task_file.config.task_type {
-                        println!("       Type: {}", task_type);
-                    }
-                    if let Some(tags) = &task_file.config.tags {
-                        println!("       Tags: {}", tags.join(", "));
+            print!("{}", format_table(&["STATUS", "ISSUE", "PATH", "TITLE"], &rows));
+        }
+        TaskLayout::Tree => {
+            let epics: HashMap<PathBuf, EpicRef> = project.tasks
+                .iter()
+                .filter_map(|task| {
+                    let path = task.path.clone()?;
+                    let epic = load_task_file(project_root, task, &project.config).ok()?.config.epic?;
+                    Some((path, EpicRef::parse(&epic)))
+                })
+                .collect();
+
+            for (depth, task) in task_tree(&project.tasks, &epics).into_iter().take(shown) {
+                println!("{}{} {}", "  ".repeat(depth), status_label(&task.status), task_summary(project_root, task, &project.config));
+            }
+        }
+    }
+
+    print_more(project.tasks.len(), limit);
+
+    Ok(())
+}
+
+/// Execute the tags command
+pub fn tags(project_file: &Path, format: OutputFormat) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    // Task files are read through the status cache, so unchanged ones aren't re-parsed
+    let mut cache = StatusCache::load(project_root);
+    let paths: Vec<&Path> = project.tasks.iter().filter_map(|t| t.path.as_deref()).collect();
+    let mut summaries = Vec::with_capacity(paths.len());
+    for path in &paths {
+        match cache.summary(project_root, path, project.config.front_matter, project.config.max_task_file_bytes) {
+            Ok(summary) => summaries.push(summary),
+            Err(e) => eprintln!("Warning: skipping {}: {:#}", path.display(), e),
+        }
+    }
+    if let Err(e) = cache.save(project_root) {
+        eprintln!("Warning: failed to save status cache: {:#}", e);
+    }
+
+    let counts = count_tags(&summaries);
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&counts)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&counts)?),
+        OutputFormat::Ndjson => {
+            for count in &counts {
+                println!("{}", serde_json::to_string(count)?);
+            }
+        }
+        OutputFormat::Text if counts.is_empty() => println!("No task uses any tags"),
+        OutputFormat::Text => {
+            let width = counts[0].tasks.to_string().len();
+            for count in &counts {
+                println!("{:>width$}  {}", count.tasks, count.tag, width = width);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute the validate command
+pub fn validate(project_file: &Path) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    println!("Validating {} tasks in {}\n", project.tasks.len(), project_file.display());
+
+    let report = validate_project(project_root, &project);
+    report.print();
+
+    if report.has_errors() {
+        anyhow::bail!("Validation failed");
+    }
+
+    Ok(())
+}
+
+/// Execute the verify command: check every `[#N]` still exists on the backend
+///
+/// Turning missing issues back into `[new]` tasks rewrites project.md, so
+/// `fix` needs `confirm`.
+pub async fn verify(project_file: &Path, remote: Remote<'_>, fix: bool, confirm: bool) -> Result<()> {
+    if fix && !confirm {
+        anyhow::bail!("verify --fix turns missing issues back into [new] tasks; re-run with --confirm");
+    }
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    let Some(backend) = live_backend(&project.config, project_root, remote)? else {
+        anyhow::bail!("verify needs a backend to check issues against; set a GitHub token");
+    };
+
+    let tracked: Vec<(&TaskItem, u64)> = project.tasks.iter().filter_map(|t| Some((t, t.status.issue_id()?))).collect();
+    let numbers: Vec<u64> = tracked.iter().map(|(_, num)| *num).collect();
+    let by_number = backend.get_issues_batch(&numbers).await?;
+    println!("Checking {} issues against {}\n", tracked.len(), project.config.backend);
+
+    let mut missing = Vec::new();
+    let mut closed = 0;
+    for (task, num) in tracked.iter().copied() {
+        match by_number.get(&num) {
+            Some(issue) if issue.state == "closed" => {
+                println!("  [CLOSED] #{} {}", num, task.name());
+                closed += 1;
+            }
+            Some(issue) => {
+                if let Some(transfer) = backend.transfer_of(issue) {
+                    println!("  [MISSING] #{} {} (transferred to {}#{})", num, task.name(), transfer.repo, transfer.number);
+                    missing.push(task);
+                }
+            }
+            None => {
+                // Fetching a transferred issue on its own is redirected to where it went
+                let moved = match backend.get_issue(num).await {
+                    Ok(issue) => backend.transfer_of(&issue).map(|t| format!(" (transferred to {}#{})", t.repo, t.number)),
+                    Err(e) if backend::error::classify(&e) == backend::error::ErrorKind::NotFound => None,
+                    Err(e) => {
+                        eprintln!("Warning: failed to check whether #{} was transferred: {:#}", num, e);
+                        None
                     }
+                };
+                println!("  [MISSING] #{} {}{}", num, task.name(), moved.unwrap_or_default());
+                missing.push(task);
+            }
+        }
+    }
+
+    println!("\n{} of {} issues missing, {} closed", missing.len(), tracked.len(), closed);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    if !fix {
+        println!("Run with --fix --confirm to turn missing issues back into [new] tasks");
+        anyhow::bail!("{} issues no longer exist", missing.len());
+    }
+
+    let mut updated = to_lf(&content);
+    for task in &missing {
+        updated = set_status(&updated, task, TaskStatus::New, &project.config);
+    }
+    let style = NewlineStyle::detect(&content).with_line_ending(project.config.line_ending);
+    fs::write(project_file, style.apply(&updated))
+        .context("Failed to write project file")?;
+
+    println!("Reset {} tasks to [new]; the next sync creates their issues again", missing.len());
+    Ok(())
+}
+
+pub async fn gaps(project_file: &Path, remote: Remote<'_>) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    let Some(backend) = live_backend(&project.config, project_root, remote)? else {
+        anyhow::bail!("gaps needs a backend to compare issues with; set a GitHub token");
+    };
+
+    let issues = backend.list_issues().await?;
+    let tracked: Vec<u64> = project.tasks.iter().filter_map(|t| t.status.issue_id()).collect();
+    let remote: Vec<u64> = issues.iter().map(|i| i.number).collect();
+    let gaps = find_gaps(&tracked, &remote);
+
+    println!("Tracked but missing remotely ({}):", gaps.missing_remotely.len());
+    for num in &gaps.missing_remotely {
+        let task = project.tasks.iter().find(|t| t.status.issue_id() == Some(*num));
+        println!("  - #{} {}", num, task.map(TaskItem::name).unwrap_or_default());
+    }
+
+    println!("\nRemote but untracked ({}):", gaps.untracked.len());
+    for num in &gaps.untracked {
+        let issue = issues.iter().find(|i| i.number == *num);
+        println!("  - #{} {}", num, issue.map_or("", |i| i.title.as_str()));
+    }
+    Ok(())
+}
+
+/// Everything known about a single task, as printed by `show`
+#[derive(Serialize)]
+struct TaskDetails {
+    line: String,
+    path: Option<PathBuf>,
+    description: String,
+    issue_number: Option<u64>,
+    front_matter: TaskFileConfig,
+    title: String,
+    body: String,
+    live: Option<LiveIssue>,
+}
+
+/// Live issue data fetched from the backend
+#[derive(Serialize)]
+struct LiveIssue {
+    state: String,
+    url: String,
+    /// Whether the issue body matches the task's, ignoring managed blocks
+    body_in_sync: bool,
+    last_comment: Option<Comment>,
+}
+
+/// Find a task by path or by `#N` issue number
+fn find_task<'a>(project: &'a ProjectMd, target: &str) -> Result<&'a TaskItem> {
+    let issue_number = target.trim_start_matches('#').parse::<u64>().ok();
+
+    project
+        .tasks
+        .iter()
+        .find(|task| match issue_number {
+            Some(num) => task.status.issue_id() == Some(num),
+            None => task.path.as_deref() == Some(Path::new(target)),
+        })
+        .with_context(|| format!("No task matching '{}' in project file", target))
+}
+
+/// Execute the show command
+pub async fn show(project_file: &Path, target: &str, remote: Remote<'_>, format: OutputFormat) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let task = find_task(&project, target)?;
+
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    let task_file = load_task_file(project_root, task, &project.config)?;
+
+    let mut live = None;
+    if let Some(num) = task.status.issue_id() {
+        if let Some(backend) = live_backend(&project.config, project_root, remote)? {
+            let issue = backend.get_issue(num).await?;
+            let last_comment = backend.list_comments(num).await?.pop();
+
+            // Compare against the body as sync would push it
+            let mut local = preprocessor_for(&project.config, project_root).process(task.path.as_deref(), &task_file.body)?;
+            if let Some(path) = &task.path {
+                local = rewrite_images(&project.config, project_root, path, local);
+            }
+
+            live = Some(LiveIssue {
+                state: issue.state,
+                url: issue.url,
+                body_in_sync: bodies_match(&local, &issue.body),
+                last_comment,
+            });
+        }
+    }
+
+    let details = TaskDetails {
+        line: task.line_in(&project.config),
+        path: task.path.clone(),
+        description: task.description_or(&task_file.title).to_string(),
+        issue_number: task.status.issue_id(),
+        front_matter: task_file.config,
+        title: task_file.title,
+        body: task_file.body,
+        live,
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&details)?),
+        OutputFormat::Ndjson => println!("{}", serde_json::to_string(&details)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&details)?),
+        OutputFormat::Text => print_task_details(&details)?,
+    }
+
+    Ok(())
+}
+
+fn print_task_details(details: &TaskDetails) -> Result<()> {
+    println!("Line: {}", details.line);
+    match &details.path {
+        Some(path) => println!("Path: {}", path.display()),
+        None => println!("Path: (none, description-only task)"),
+    }
+    match details.issue_number {
+        Some(num) => println!("Issue: #{}", num),
+        None => println!("Issue: (not yet created)"),
+    }
+
+    println!("\nFront matter:");
+    for line in serde_yaml::to_string(&details.front_matter)?.lines() {
+        println!("  {}", line);
+    }
+
+    println!("\nTitle: {}", details.title);
+    println!("\nBody:\n{}", details.body);
+
+    if let Some(live) = &details.live {
+        println!("\nLive issue:");
+        println!("  State: {}", live.state);
+        println!("  URL: {}", live.url);
+        println!("  Body: {}", if live.body_in_sync { "in sync" } else { "differs from the task file" });
+        match &live.last_comment {
+            Some(comment) => {
+                println!("  Last comment by {} at {}:", comment.author, comment.created_at);
+                for line in comment.body.lines() {
+                    println!("    {}", line);
                 }
             }
-            println!();
+            None => println!("  No comments"),
         }
     }
 
-    // If we have a token, we can fetch live status from backend
-    let token_string;
-    let token = match github_token {
-        Some(t) => Some(t),
-        None => {
-            token_string = std::env::var("GITHUB_TOKEN").ok();
-            token_string.as_deref()
+    Ok(())
+}
+
+/// Execute the open command
+///
+/// The URL comes from the backend when it can be reached, and is otherwise
+/// built from the repo for GitHub projects.
+pub async fn open(project_file: &Path, target: &str, remote: Remote<'_>, print_url: bool) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let task = find_task(&project, target)?;
+    let num = task.status.issue_id()
+        .with_context(|| format!("{} has no issue yet; run sync to create it", task.name()))?;
+
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+    let url = match live_backend(&project.config, project_root, remote)? {
+        Some(backend) => backend.get_issue(num).await?.url,
+        None if project.config.backend == "github" => {
+            format!("https://github.com/{}/issues/{}", project.config.repo, num)
         }
+        None => anyhow::bail!("Cannot find the URL of issue #{} without a {} backend", num, project.config.backend),
     };
 
-    if let Some(token) = token {
-        if project.config.backend == "github" {
-            println!("\nFetching live status from GitHub...\n");
+    if print_url {
+        println!("{}", url);
+        return Ok(());
+    }
 
-            let backend = GitHubBackend::new(token, &project.config.repo)?;
-            let issues = backend.list_issues().await?;
+    ::open::that(&url).with_context(|| format!("Failed to open {} in a browser", url))
+}
+
+/// Execute the burndown command
+pub async fn burndown(project_file: &Path, remote: Remote<'_>, format: BurndownFormat) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    let backend = live_backend(&project.config, project_root, remote)?
+        .context("Burndown needs issue close dates from the backend; set GITHUB_TOKEN or pass --github-token")?;
+    let issues = backend.list_issues().await?;
+    let by_number: HashMap<u64, &Issue> = issues.iter().map(|i| (i.number, i)).collect();
+
+    let today = Utc::now().date_naive();
+    let mut tasks = Vec::with_capacity(project.tasks.len());
+    for task in &project.tasks {
+        let config = match load_task_file(project_root, task, &project.config) {
+            Ok(task_file) => task_file.config,
+            Err(e) => {
+                eprintln!("Warning: counting {} with the default estimate: {:#}", task.name(), e);
+                TaskFileConfig::default()
+            }
+        };
 
-            println!("Total issues in repository: {}", issues.len());
+        let issue = task.status.issue_id().and_then(|num| by_number.get(&num));
+        let closed = issue
+            .filter(|issue| issue.state == "closed")
+            .map(|issue| issue.closed_at.map_or(today, |closed_at| closed_at.date_naive()));
 
-            let open_count = issues.iter().filter(|i| i.state == "open").count();
-            let closed_count = issues.iter().filter(|i| i.state == "closed").count();
+        tasks.push(BurndownTask {
+            estimate: config.estimate.unwrap_or(1.0),
+            created: config.created_at.as_deref().and_then(parse_date),
+            target: config.target_date.as_deref().and_then(parse_date),
+            closed,
+        });
+    }
 
-            println!("  Open: {}", open_count);
-            println!("  Closed: {}", closed_count);
+    let rows = projectmd::burndown::burndown(&tasks, today);
+    match format {
+        BurndownFormat::Csv => print!("{}", to_csv(&rows)),
+        BurndownFormat::Text => {
+            let cells: Vec<Vec<String>> = rows
+                .iter()
+                .map(|row| {
+                    vec![
+                        row.date.to_string(),
+                        row.total.to_string(),
+                        row.completed.to_string(),
+                        row.remaining.to_string(),
+                        row.planned.to_string(),
+                    ]
+                })
+                .collect();
+            print!("{}", format_table(&["DATE", "TOTAL", "COMPLETED", "REMAINING", "PLANNED"], &cells));
         }
     }
 
     Ok(())
 }
 
+/// Execute the ratelimit command
+pub async fn ratelimit(project_file: &Path, remote: Remote<'_>) -> Result<()> {
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    let project = parse_project_file(&content)?;
+    let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+    let backend = live_backend(&project.config, project_root, remote)?
+        .context("Checking rate limits needs a GitHub token; set GITHUB_TOKEN or pass --github-token")?;
+    let Some(limits) = backend.rate_limit_status().await? else {
+        println!("The {} backend has no rate limits", project.config.backend);
+        return Ok(());
+    };
+
+    let now = Utc::now();
+    let cells: Vec<Vec<String>> = limits
+        .iter()
+        .map(|limit| {
+            let minutes = (limit.reset - now).num_minutes().max(0);
+            vec![
+                limit.resource.clone(),
+                limit.remaining.to_string(),
+                limit.limit.to_string(),
+                format!("{} (in {}m)", limit.reset.format("%Y-%m-%d %H:%M:%S UTC"), minutes),
+            ]
+        })
+        .collect();
+    print!("{}", format_table(&["API", "REMAINING", "LIMIT", "RESETS"], &cells));
+
+    Ok(())
+}
+
 /// Execute the init command
 pub async fn init(backend: &str, repo: &str) -> Result<()> {
     let project_file = Path::new("project.md");
@@ -145,7 +1704,8 @@ pub async fn init(backend: &str, repo: &str) -> Result<()> {
     }
 
     let template = format!(
-        r#"backend: {}
+        r#"version: {}
+backend: {}
 repo: {}
 ---
 
@@ -158,7 +1718,7 @@ Project description goes here.
 * [new] - tasks/example.md - Example task
 
 "#,
-        backend, repo
+        FORMAT_VERSION, backend, repo
     );
 
     fs::write(project_file, template)
@@ -202,3 +1762,40 @@ When you run `projectmd sync`, this will be created as an issue in your backend.
 
     Ok(())
 }
+
+/// Execute `debug parse`: print parsed structures without syncing
+pub fn debug_parse(project_file: &Path, task_file: Option<&Path>, verbose: u8) -> Result<()> {
+    if let Some(path) = task_file {
+        let content = read_utf8(path)
+            .with_context(|| format!("Failed to read task file: {:?}", path))?;
+        println!("{:#?}", parse_task_file(&content)?);
+        return Ok(());
+    }
+
+    let content = read_utf8(project_file)
+        .context("Failed to read project file")?;
+
+    if verbose >= 2 {
+        println!("=== Parse tree: {} ===\n", project_file.display());
+        println!("{}", parse_tree(&content)?);
+    }
+
+    let project = parse_project_file(&content)?;
+    println!("=== {} ===\n", project_file.display());
+    println!("{:#?}", project);
+
+    if verbose >= 1 {
+        let project_root = project_file.parent().unwrap_or(Path::new("."));
+
+        for task in &project.tasks {
+            println!("\n=== {} ===\n", task.name());
+
+            match load_task_file(project_root, task, &project.config) {
+                Ok(task_file) => println!("{:#?}", task_file),
+                Err(e) => println!("Error: {:?}", e),
+            }
+        }
+    }
+
+    Ok(())
+}
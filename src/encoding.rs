@@ -0,0 +1,64 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Read a text file, failing on invalid UTF-8 with the file and the byte
+/// offset of the first invalid sequence
+///
+/// `fs::read_to_string` only says the stream "did not contain valid UTF-8".
+/// The error keeps the `InvalidData` kind, so callers can still tell it
+/// apart from a missing file.
+pub fn read_utf8(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    String::from_utf8(fs::read(path)?).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} is not valid UTF-8 (invalid byte sequence at byte offset {}); re-save it as UTF-8",
+                path.display(),
+                e.utf8_error().valid_up_to()
+            ),
+        )
+    })
+}
+
+/// `read_utf8`, but failing without reading files over `max_bytes`
+///
+/// Guards against huge files, like a binary renamed to `.md`, being read
+/// into memory and pushed. The error has the `InvalidData` kind.
+pub fn read_utf8_within(path: impl AsRef<Path>, max_bytes: Option<u64>) -> io::Result<String> {
+    let path = path.as_ref();
+    if let Some(max_bytes) = max_bytes {
+        let len = fs::metadata(path)?.len();
+        if len > max_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is {} bytes, over max_task_file_bytes ({}); skipped", path.display(), len, max_bytes),
+            ));
+        }
+    }
+    read_utf8(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.md");
+
+        fs::write(&path, "---\n---\n# Café\n").unwrap();
+        assert_eq!(read_utf8(&path).unwrap(), "---\n---\n# Café\n");
+
+        // "# Caf\xe9" as saved in Latin-1
+        fs::write(&path, b"---\n---\n# Caf\xe9\n").unwrap();
+        let error = read_utf8(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("task.md is not valid UTF-8"), "{}", error);
+        assert!(error.to_string().contains("byte offset 13"), "{}", error);
+
+        assert_eq!(read_utf8(dir.path().join("missing.md")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+
+use crate::newline::{to_lf, NewlineStyle};
+use crate::parser::has_front_matter;
+
+/// Set top-level keys in a task file's YAML front matter, editing the raw text
+///
+/// Unlike round-tripping through `TaskFileConfig`, this keeps comments, key
+/// order and formatting intact. Existing keys have their value replaced (an
+/// inline `# comment` after the value is kept); missing keys are appended.
+/// The file's line endings and trailing newline are preserved. A file without
+/// front matter gets a block holding just the updated keys.
+pub fn update_keys(content: &str, updates: &[(&str, String)]) -> Result<String> {
+    let style = NewlineStyle::detect(content);
+    let content = to_lf(content);
+
+    if !has_front_matter(&content) {
+        let yaml = updates.iter().fold("\n".to_string(), |yaml, (key, value)| set_key(&yaml, key, value));
+        return Ok(style.apply(&format!("---{}---\n{}", yaml, content)));
+    }
+
+    let parts: Vec<&str> = content.splitn(3, "---").collect();
+    if parts.len() < 3 {
+        anyhow::bail!("Invalid task file format");
+    }
+
+    let mut yaml = parts[1].to_string();
+    for (key, value) in updates {
+        yaml = set_key(&yaml, key, value);
+    }
+
+    Ok(style.apply(&format!("{}---{}---{}", parts[0], yaml, parts[2])))
+}
+
+/// Replace the markdown after a task file's front matter, keeping the front
+/// matter's text, the file's line endings and the blank lines (if any) between
+/// the two
+pub fn replace_markdown(content: &str, markdown: &str) -> Result<String> {
+    let style = NewlineStyle::detect(content);
+    let content = to_lf(content);
+
+    if !has_front_matter(&content) {
+        return Ok(style.apply(markdown));
+    }
+
+    let parts: Vec<&str> = content.splitn(3, "---").collect();
+    if parts.len() < 3 {
+        anyhow::bail!("Invalid task file format");
+    }
+
+    let spacing = &parts[2][..parts[2].len() - parts[2].trim_start_matches('\n').len()];
+    let spacing = if spacing.is_empty() { "\n" } else { spacing };
+    Ok(style.apply(&format!("{}---{}---{}{}", parts[0], parts[1], spacing, markdown)))
+}
+
+/// Set a single top-level key in a block of YAML text
+///
+/// The old value's indented continuation lines (a block-style list or map)
+/// are replaced along with the key's own line.
+pub fn set_key(yaml: &str, key: &str, value: &str) -> String {
+    let prefix = format!("{}:", key);
+    let mut found = false;
+    let mut in_old_value = false;
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in yaml.split('\n') {
+        if in_old_value {
+            if is_continuation(line) {
+                continue;
+            }
+            in_old_value = false;
+        }
+        if found || !line.starts_with(&prefix) {
+            lines.push(line.to_string());
+            continue;
+        }
+        found = true;
+        in_old_value = true;
+
+        lines.push(match inline_comment(&line[prefix.len()..]) {
+            Some(comment) => format!("{} {} {}", prefix, value, comment),
+            None => format!("{} {}", prefix, value),
+        });
+    }
+
+    if !found {
+        // Insert before the trailing newline that precedes the closing `---`
+        let insert_at = if yaml.ends_with('\n') { lines.len() - 1 } else { lines.len() };
+        lines.insert(insert_at, format!("{} {}", prefix, value));
+    }
+
+    lines.join("\n")
+}
+
+/// Whether a line continues the value of the top-level key above it: either
+/// indented, or a `- item` of a sequence written at the key's own indentation
+fn is_continuation(line: &str) -> bool {
+    line.starts_with(' ') || line.starts_with('\t') || line == "-" || line.starts_with("- ")
+}
+
+/// Find a trailing `# comment` on a scalar value, ignoring `#` inside quotes
+fn inline_comment(value: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut prev = ' ';
+
+    for (i, c) in value.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '#') if prev.is_whitespace() => return Some(&value[i..]),
+            _ => {}
+        }
+        prev = c;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_markdown() {
+        let content = "---\r\nissue_id: 3 # adopted\r\n---\r\n# Old\r\n\r\nOld body.\r\n";
+        assert_eq!(
+            replace_markdown(content, "# New\n\nNew body.\n").unwrap(),
+            "---\r\nissue_id: 3 # adopted\r\n---\r\n# New\r\n\r\nNew body.\r\n"
+        );
+        assert_eq!(replace_markdown("# Old\n", "# New\n").unwrap(), "# New\n");
+    }
+
+    #[test]
+    fn test_spacing_after_front_matter_is_kept() {
+        for spacing in ["\n", "\n\n"] {
+            let content = format!("---\ntype: bug\n---{}# Title\n\nBody.\n", spacing);
+
+            let updated = update_keys(&content, &[("issue_id", "7".to_string())]).unwrap();
+            assert_eq!(updated, format!("---\ntype: bug\nissue_id: 7\n---{}# Title\n\nBody.\n", spacing));
+
+            let replaced = replace_markdown(&content, "# New\n").unwrap();
+            assert_eq!(replaced, format!("---\ntype: bug\n---{}# New\n", spacing));
+        }
+    }
+
+    #[test]
+    fn test_update_keys_preserves_comments() {
+        let content = "---\n# Owned by the platform team\ntype: bug # triaged\nissue_id: 3 # old number\ntags: [infra]\n---\n# Title\n\nBody.\n";
+
+        let updated = update_keys(content, &[
+            ("issue_id", "7".to_string()),
+            ("updated_at", "\"2025-01-20T15:45:32Z\"".to_string()),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            updated,
+            "---\n# Owned by the platform team\ntype: bug # triaged\nissue_id: 7 # old number\ntags: [infra]\nupdated_at: \"2025-01-20T15:45:32Z\"\n---\n# Title\n\nBody.\n"
+        );
+    }
+
+    #[test]
+    fn test_update_keys_preserves_line_endings() {
+        let updates = [("issue_id", "7".to_string()), ("updated_at", "\"now\"".to_string())];
+
+        let lf = "---\ntype: bug # triaged\n---\n# Title\n\nBody.";
+        assert_eq!(
+            update_keys(lf, &updates).unwrap(),
+            "---\ntype: bug # triaged\nissue_id: 7\nupdated_at: \"now\"\n---\n# Title\n\nBody."
+        );
+
+        let crlf = "---\r\ntype: bug # triaged\r\nissue_id: 3\r\n---\r\n# Title\r\n\r\nBody.\r\n";
+        assert_eq!(
+            update_keys(crlf, &updates).unwrap(),
+            "---\r\ntype: bug # triaged\r\nissue_id: 7\r\nupdated_at: \"now\"\r\n---\r\n# Title\r\n\r\nBody.\r\n"
+        );
+    }
+
+    #[test]
+    fn test_set_key_ignores_nested_and_quoted() {
+        let yaml = "\nextra:\n  issue_id: 1\ntitle: \"a # b\"\n";
+
+        assert_eq!(set_key(yaml, "issue_id", "2"), "\nextra:\n  issue_id: 1\ntitle: \"a # b\"\nissue_id: 2\n");
+        assert_eq!(set_key(yaml, "title", "c"), "\nextra:\n  issue_id: 1\ntitle: c\n");
+    }
+
+    #[test]
+    fn test_set_key_replaces_block_values() {
+        let yaml = "\ntags:\n  - a\n  - b\n# note\ntype: bug\n";
+        assert_eq!(set_key(yaml, "tags", "[c]"), "\ntags: [c]\n# note\ntype: bug\n");
+
+        let yaml = "\ntags:\n- a\n- b\n";
+        assert_eq!(set_key(yaml, "tags", "[c]"), "\ntags: [c]\n");
+
+        let yaml = "\nextra:\n  owner: me\ntags: [a]\n";
+        assert_eq!(set_key(yaml, "extra", "{}"), "\nextra: {}\ntags: [a]\n");
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run a git command in `dir` and return its stdout
+fn run_git(dir: &Path, args: &[&str]) -> Result<String> {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The top-level directory of the git repository containing `dir`
+pub fn repo_root(dir: &Path) -> Result<PathBuf> {
+    let root = run_git(dir, &["rev-parse", "--show-toplevel"])
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+
+    Ok(PathBuf::from(root.trim()))
+}
+
+/// Files changed in the working tree relative to `git_ref`, as absolute paths
+///
+/// Includes untracked files so task files that haven't been committed yet
+/// still count as changed.
+pub fn changed_files(dir: &Path, git_ref: &str) -> Result<HashSet<PathBuf>> {
+    let root = repo_root(dir)?;
+
+    let diff = run_git(&root, &["diff", "--name-only", git_ref])?;
+    let untracked = run_git(&root, &["ls-files", "--others", "--exclude-standard"])?;
+
+    Ok(diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| root.join(line))
+        .collect())
+}
+
+/// Which of `paths` have uncommitted changes: staged, unstaged or untracked
+///
+/// Paths that don't exist are left out, as are ignored files.
+pub fn uncommitted_files(dir: &Path, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let root = repo_root(dir)?;
+
+    let paths: Vec<String> = paths
+        .iter()
+        .filter_map(|p| fs::canonicalize(p).ok())
+        .map(|p| p.display().to_string())
+        .collect();
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut args = vec!["status", "--porcelain", "-z", "--untracked-files=all", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let status = run_git(&root, &args)?;
+
+    // Entries are `XY path`, and renames and copies are followed by their source
+    let mut files = Vec::new();
+    let mut entries = status.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        let (code, path) = entry.split_at(entry.len().min(3));
+        if code.starts_with(['R', 'C']) {
+            entries.next();
+        }
+        files.push(root.join(path));
+    }
+
+    Ok(files)
+}
+
+/// The commit checked out in the git repository containing `dir`
+pub fn head_sha(dir: &Path) -> Result<String> {
+    Ok(run_git(dir, &["rev-parse", "HEAD"])?.trim().to_string())
+}
+
+/// Check if `path` is one of the absolute paths returned by `changed_files`
+pub fn is_changed(changed: &HashSet<PathBuf>, path: &Path) -> bool {
+    fs::canonicalize(path)
+        .map(|p| changed.contains(&p))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        run_git(dir, &[&["-c", "user.name=test", "-c", "user.email=test@example.com"], args].concat()).unwrap();
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+
+        git(&root, &["init", "-q"]);
+        fs::write(root.join("a.md"), "a").unwrap();
+        fs::write(root.join("b.md"), "b").unwrap();
+        git(&root, &["add", "."]);
+        git(&root, &["commit", "-q", "-m", "init"]);
+
+        fs::write(root.join("b.md"), "b changed").unwrap();
+        fs::write(root.join("c.md"), "c").unwrap();
+
+        let changed = changed_files(&root, "HEAD").unwrap();
+        assert!(!is_changed(&changed, &root.join("a.md")));
+        assert!(is_changed(&changed, &root.join("b.md")));
+        assert!(is_changed(&changed, &root.join("c.md")));
+    }
+
+    #[test]
+    fn test_uncommitted_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+
+        git(&root, &["init", "-q"]);
+        for name in ["a.md", "b.md", "c.md", "ignored.md"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+        fs::write(root.join(".gitignore"), "ignored.md\n").unwrap();
+        git(&root, &["add", "a.md", "b.md", ".gitignore"]);
+        git(&root, &["commit", "-q", "-m", "init"]);
+
+        let paths: Vec<PathBuf> = ["a.md", "b.md", "c.md", "ignored.md", "missing.md"]
+            .iter()
+            .map(|name| root.join(name))
+            .collect();
+        assert_eq!(uncommitted_files(&root, &paths).unwrap(), vec![root.join("c.md")]);
+
+        fs::write(root.join("a.md"), "a changed").unwrap();
+        fs::write(root.join("b.md"), "b staged").unwrap();
+        git(&root, &["add", "b.md"]);
+
+        let mut dirty = uncommitted_files(&root, &paths).unwrap();
+        dirty.sort();
+        assert_eq!(dirty, vec![root.join("a.md"), root.join("b.md"), root.join("c.md")]);
+    }
+
+    #[test]
+    fn test_outside_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = changed_files(dir.path(), "main").unwrap_err();
+        assert!(err.to_string().contains("is not inside a git repository"));
+    }
+}
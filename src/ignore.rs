@@ -0,0 +1,54 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::codeowners::pattern_matches;
+
+/// Lists task files discovery skips, in the project directory, gitignore-style
+pub const IGNORE_FILE: &str = ".projectmdignore";
+
+/// Patterns from a `.projectmdignore` file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreFile {
+    /// Patterns in file order, and whether each is a `!` exception
+    patterns: Vec<(String, bool)>,
+}
+
+impl IgnoreFile {
+    /// Parse one pattern per line, skipping blank lines and `#` comments
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| match line.strip_prefix('!') {
+                Some(pattern) => (pattern.to_string(), true),
+                None => (line.to_string(), false),
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Read the project's ignore file; without one nothing is ignored
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = project_root.join(IGNORE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(Self::parse(&content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    /// Whether a path relative to the project directory is ignored, by the
+    /// last pattern matching it as in gitignore
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy().replace('\\', "/");
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern_matches(pattern, &path))
+            .is_some_and(|(_, exception)| !exception)
+    }
+}
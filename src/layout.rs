@@ -0,0 +1,120 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::types::{EpicRef, TaskItem};
+
+/// Format rows as columns aligned under their headers
+///
+/// Columns are separated by two spaces; the last column isn't padded.
+pub fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header_row: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    let mut out = String::new();
+    for row in std::iter::once(&header_row).chain(rows) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            if i + 1 < row.len() {
+                let padding = widths[i].saturating_sub(cell.chars().count());
+                line.extend(std::iter::repeat_n(' ', padding));
+            }
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Tasks in tree order, each with its depth below its epic
+///
+/// A task's epic is the task with the referenced path or issue number; tasks
+/// whose epic isn't in the list (or that are part of a cycle) are roots.
+/// Otherwise tasks keep their project.md order.
+pub fn task_tree<'a>(tasks: &'a [TaskItem], epics: &HashMap<PathBuf, EpicRef>) -> Vec<(usize, &'a TaskItem)> {
+    let parent_of = |task: &TaskItem| -> Option<usize> {
+        let epic = epics.get(task.path.as_ref()?)?;
+        tasks.iter().position(|t| match epic {
+            EpicRef::Task(path) => t.path.as_ref() == Some(path),
+            EpicRef::Issue(number) => t.status.issue_id() == Some(*number),
+        })
+    };
+    let parents: Vec<Option<usize>> = tasks.iter().map(parent_of).collect();
+
+    let mut tree = Vec::with_capacity(tasks.len());
+    let mut visited = HashSet::new();
+
+    // Roots first, then anything left unvisited because it sits in a cycle
+    let roots = (0..tasks.len()).filter(|&i| parents[i].is_none_or(|p| p == i));
+    for root in roots.chain(0..tasks.len()) {
+        let mut stack = vec![(root, 0)];
+        while let Some((index, depth)) = stack.pop() {
+            if !visited.insert(index) {
+                continue;
+            }
+            tree.push((depth, &tasks[index]));
+
+            let children = (0..tasks.len()).filter(|&i| i != index && parents[i] == Some(index));
+            stack.extend(children.rev().map(|i| (i, depth + 1)));
+        }
+    }
+
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_project_file;
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["new".to_string(), "-".to_string(), "tasks/a.md".to_string()],
+            vec!["wip".to_string(), "#12".to_string(), "".to_string()],
+        ];
+
+        assert_eq!(
+            format_table(&["STATUS", "ISSUE", "PATH"], &rows),
+            "STATUS  ISSUE  PATH\nnew     -      tasks/a.md\nwip     #12\n"
+        );
+    }
+
+    #[test]
+    fn test_task_tree_groups_by_epic() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\n---\n* [new] - child.md - Child\n* [#4] - epic.md - Epic\n* [new] - Loose end\n* [new] - grandchild.md - Grandchild\n* [new] - by_number.md - By number\n",
+        )
+        .unwrap();
+        let epics = HashMap::from([
+            (PathBuf::from("child.md"), EpicRef::parse("epic.md")),
+            (PathBuf::from("grandchild.md"), EpicRef::parse("child.md")),
+            (PathBuf::from("by_number.md"), EpicRef::parse("#4")),
+        ]);
+
+        let tree: Vec<(usize, &str)> = task_tree(&project.tasks, &epics)
+            .into_iter()
+            .map(|(depth, task)| (depth, task.description.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![(0, "Epic"), (1, "Child"), (2, "Grandchild"), (1, "By number"), (0, "Loose end")]
+        );
+
+        // A cycle still lists every task once
+        let cycle = HashMap::from([
+            (PathBuf::from("child.md"), EpicRef::parse("grandchild.md")),
+            (PathBuf::from("grandchild.md"), EpicRef::parse("child.md")),
+        ]);
+        assert_eq!(task_tree(&project.tasks, &cycle).len(), project.tasks.len());
+    }
+}
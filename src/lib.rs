@@ -1,7 +1,27 @@
+pub mod audit;
 pub mod backend;
+pub mod body;
+pub mod burndown;
+pub mod cache;
+pub mod clock;
+pub mod codeowners;
+pub mod encoding;
+pub mod frontmatter;
+pub mod git;
+pub mod ignore;
+pub mod layout;
+pub mod migrate;
+pub mod newline;
 pub mod parser;
+pub mod plan;
+pub mod preprocess;
+pub mod queue;
+pub mod report;
+pub mod state;
+pub mod toc;
 pub mod types;
 pub mod sync;
+pub mod validate;
 
 // Re-export commonly used types
 pub use types::{ProjectConfig, ProjectMd, TaskFile, TaskItem, TaskStatus};
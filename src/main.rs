@@ -1,35 +1,153 @@
-mod backend;
 mod cli;
 mod commands;
-mod parser;
-mod sync;
-mod types;
 
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use clap::Parser;
-use cli::{Cli, Commands};
+use projectmd::sync::SyncOptions;
+use cli::{Cli, Commands, DebugCommands, SyncOutput};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if let Some(dir) = &cli.chdir {
+        std::env::set_current_dir(dir).with_context(|| format!("Failed to change to directory {:?}", dir))?;
+    }
+    let backend_config = match &cli.backend_config {
+        Some(json) => serde_json::from_str(json).context("--backend-config must be a JSON object")?,
+        None => HashMap::new(),
+    };
+
     match cli.command {
-        Commands::Sync { dry_run } => {
-            let token = cli.github_token
-                .or_else(|| std::env::var("GITHUB_TOKEN").ok())
-                .context("GitHub token is required. Set GITHUB_TOKEN env var or use --github-token")?;
+        Commands::Sync { dry_run, verbose, dry_run_exit_code, truncate_titles, idempotent, changed_since, offline, require_clean, validate_assignees, assign_from_codeowners, repo_check, tag, follow_transfers, explain, dump_plan_md, render_to, output, retry_run, map_labels_file, reset_timestamps } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source || verbose > 0)?;
+
+            let options = SyncOptions { truncate_titles, idempotent, changed_since, offline, require_clean, validate_assignees, repo_check, tag, label_map_file: map_labels_file, follow_transfers, explain, retry_run, summary_only: output == SyncOutput::SummaryOnly, plan_md: dump_plan_md.clone(), render_to: render_to.clone(), reset_timestamps, ..Default::default() };
+            commands::sync(&cli.project_file, remote(&token, &backend_config), dry_run || dump_plan_md.is_some() || render_to.is_some(), verbose, dry_run_exit_code, assign_from_codeowners, options).await?;
+        }
+
+        Commands::Flush { idempotent } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+
+            let options = SyncOptions { idempotent, ..Default::default() };
+            commands::flush(&cli.project_file, remote(&token, &backend_config), options).await?;
+        }
+
+        Commands::Pull { concurrency } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+
+            let options = SyncOptions { concurrency: Some(concurrency), ..Default::default() };
+            commands::pull(&cli.project_file, remote(&token, &backend_config), options).await?;
+        }
+
+        Commands::WatchRemote { interval, concurrency } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+
+            let options = SyncOptions { concurrency: Some(concurrency), ..Default::default() };
+            commands::watch_remote(&cli.project_file, remote(&token, &backend_config), interval, options).await?;
+        }
+
+        Commands::CloseAll { tag, confirm, dry_run } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::close_all(&cli.project_file, remote(&token, &backend_config), &tag, confirm, dry_run).await?;
+        }
+
+        Commands::Milestone { milestone, label, close: _, confirm, dry_run } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            // clap makes --label and --close exclusive, and requires one
+            let action = match label {
+                Some(label) => commands::MilestoneAction::Label(label),
+                None => commands::MilestoneAction::Close,
+            };
+            commands::milestone(&cli.project_file, remote(&token, &backend_config), &milestone, action, confirm, dry_run).await?;
+        }
+
+        Commands::Validate => {
+            commands::validate(&cli.project_file)?;
+        }
+
+        Commands::Verify { fix, confirm } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::verify(&cli.project_file, remote(&token, &backend_config), fix, confirm).await?;
+        }
+
+        Commands::Gaps => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::gaps(&cli.project_file, remote(&token, &backend_config)).await?;
+        }
+
+        Commands::Status { verbose, cached, format, limit, include_archived } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source || verbose)?;
+            commands::status(&cli.project_file, remote(&token, &backend_config), verbose, cached, format, limit, include_archived).await?;
+        }
 
-            commands::sync(&cli.project_file, &token, dry_run).await?;
+        Commands::Add { description, path } => {
+            commands::add(&cli.project_file, &description, path)?;
         }
 
-        Commands::Status { verbose } => {
-            commands::status(&cli.project_file, cli.github_token.as_deref(), verbose).await?;
+        Commands::Adopt { issue, path, pull } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::adopt(&cli.project_file, &issue, &path, pull, remote(&token, &backend_config)).await?;
+        }
+
+        Commands::Toc { check } => {
+            commands::toc(&cli.project_file, check)?;
+        }
+
+        Commands::Upgrade { check } => {
+            commands::upgrade(&cli.project_file, check)?;
+        }
+
+        Commands::Orphans { dir, add } => {
+            commands::orphans(&cli.project_file, &dir, add)?;
+        }
+
+        Commands::Generate { from, backend, repo, force } => {
+            commands::generate(&cli.project_file, &from, &backend, &repo, force)?;
+        }
+
+        Commands::Tasks { layout, limit, include_archived } => {
+            commands::tasks(&cli.project_file, layout, limit, include_archived)?;
+        }
+
+        Commands::Tags { format } => {
+            commands::tags(&cli.project_file, format)?;
+        }
+
+        Commands::Burndown { format } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::burndown(&cli.project_file, remote(&token, &backend_config), format).await?;
+        }
+
+        Commands::Ratelimit => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::ratelimit(&cli.project_file, remote(&token, &backend_config)).await?;
         }
 
         Commands::Init { backend, repo } => {
             commands::init(&backend, &repo).await?;
         }
+
+        Commands::Show { target, format } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::show(&cli.project_file, &target, remote(&token, &backend_config), format).await?;
+        }
+
+        Commands::Open { target, print_url } => {
+            let token = commands::resolve_token(cli.github_token.as_deref(), cli.github_token_file.as_deref(), cli.print_token_source)?;
+            commands::open(&cli.project_file, &target, remote(&token, &backend_config), print_url).await?;
+        }
+
+        Commands::Debug { command: DebugCommands::Parse { file, verbose } } => {
+            commands::debug_parse(&cli.project_file, file.as_deref(), verbose)?;
+        }
     }
 
     Ok(())
 }
+
+/// The backend settings for a command, from its resolved token
+fn remote<'a>(token: &'a Option<String>, backend_config: &'a HashMap<String, serde_json::Value>) -> commands::Remote<'a> {
+    commands::Remote { token: token.as_deref(), backend_config }
+}
@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::newline::{to_lf, NewlineStyle};
+use crate::parser::{parse_project_file, parse_task_line};
+use crate::types::{ProjectConfig, TaskItem};
+
+/// Version of the project.md format this build reads
+///
+/// Files without a `version` predate versioning and are read as version 1.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Upgrades front matter from one version to the next
+type Migration = fn(&mut Mapping);
+
+/// Migration from version `i + 1` to `i + 2`, for each older version
+///
+/// Bump `FORMAT_VERSION` when appending one.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Check the front matter's `version` and bring older formats up to date
+///
+/// Fails with an upgrade hint if the file needs a newer projectmd.
+pub fn migrate_config(yaml: Value) -> Result<Value> {
+    migrate_with(yaml, FORMAT_VERSION, MIGRATIONS)
+}
+
+fn migrate_with(mut yaml: Value, current: u32, migrations: &[Migration]) -> Result<Value> {
+    let Some(mapping) = yaml.as_mapping_mut() else {
+        // Left for deserializing to report
+        return Ok(yaml);
+    };
+
+    let version = match mapping.get("version") {
+        None => return Ok(yaml),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|v| *v >= 1)
+            .with_context(|| format!("Invalid project.md version {:?}; expected a number from 1", value))?,
+    };
+
+    if version > current {
+        anyhow::bail!(
+            "project.md is version {}, but this projectmd only supports up to version {}; upgrade projectmd to read it",
+            version,
+            current
+        );
+    }
+
+    for migration in &migrations[(version - 1) as usize..] {
+        migration(mapping);
+    }
+    mapping.insert("version".into(), current.into());
+
+    Ok(yaml)
+}
+
+/// Rewrite project.md content in the current format, for `upgrade`
+///
+/// The front matter gets the current `version`, re-serialized only if older
+/// versions' migrations changed it. Task lines are rewritten from how they
+/// parse, keeping their bullet and checkbox, since every bullet and checkbox
+/// the parser reads is still current. Everything else, including lines that
+/// look like tasks but don't parse, is kept as it is, and so are line endings.
+pub fn upgrade_project(content: &str) -> Result<String> {
+    let style = NewlineStyle::detect(content);
+    let content = to_lf(content);
+    let project = parse_project_file(&content)?;
+
+    let Some((front_matter, body)) = content.split_once("\n---\n").map(|(yaml, body)| (format!("{}\n", yaml), body)) else {
+        anyhow::bail!("Missing YAML front matter");
+    };
+    let yaml: Value = serde_yaml::from_str(&front_matter).context("Failed to parse YAML front matter")?;
+    let migrated = migrate_config(yaml.clone())?;
+    let front_matter = match (yaml.get("version"), migrated == yaml) {
+        (None, _) => format!("{}version: {}\n", front_matter, FORMAT_VERSION),
+        (Some(_), true) => front_matter,
+        (Some(_), false) => serde_yaml::to_string(&migrated)?,
+    };
+
+    let body: String = body
+        .split_inclusive('\n')
+        .map(|line| {
+            let (text, newline) = line.strip_suffix('\n').map_or((line, ""), |text| (text, "\n"));
+            match parse_task_line(text, &project.config) {
+                Some(task) => format!("{}{}", canonical_line(&task, text, &project.config), newline),
+                None => line.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(style.apply(&format!("{}---\n{}", front_matter, body)))
+}
+
+/// A task line as written from `task`, with the checkbox of `text`, the line
+/// it was parsed from
+fn canonical_line(task: &TaskItem, text: &str, config: &ProjectConfig) -> String {
+    if !task.completed {
+        return task.line_in(config);
+    }
+    let checkbox = [" [x]", " [X]"].into_iter().find(|checkbox| text.ends_with(checkbox)).unwrap_or(" ✅");
+    format!("{}{}", task.line_in(config), checkbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(content: &str) -> Value {
+        serde_yaml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_versions() {
+        // Absent and current versions are read as they are
+        let unversioned = yaml("backend: github\nrepo: a/b\n");
+        assert_eq!(migrate_config(unversioned.clone()).unwrap(), unversioned);
+        let current = yaml(&format!("backend: github\nrepo: a/b\nversion: {}\n", FORMAT_VERSION));
+        assert_eq!(migrate_config(current.clone()).unwrap(), current);
+
+        let error = migrate_config(yaml("backend: github\nversion: 99\n")).unwrap_err().to_string();
+        assert!(error.contains("upgrade projectmd"), "{}", error);
+        assert!(migrate_config(yaml("version: 0\n")).is_err());
+        assert!(migrate_config(yaml("version: two\n")).is_err());
+
+        // Older versions run every migration after their own
+        fn rename_repo(mapping: &mut Mapping) {
+            if let Some(repo) = mapping.remove("github_repo") {
+                mapping.insert("repo".into(), repo);
+            }
+        }
+        fn add_backend(mapping: &mut Mapping) {
+            mapping.entry("backend".into()).or_insert("github".into());
+        }
+        let migrations: &[Migration] = &[rename_repo, add_backend];
+        assert_eq!(
+            migrate_with(yaml("github_repo: a/b\nversion: 1\n"), 3, migrations).unwrap(),
+            yaml("repo: a/b\nversion: 3\nbackend: github\n")
+        );
+        assert_eq!(
+            migrate_with(yaml("github_repo: a/b\nversion: 2\n"), 3, migrations).unwrap(),
+            yaml("github_repo: a/b\nversion: 3\nbackend: github\n")
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// Line terminator used in a file
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// How many newlines project.md ends with once sync writes it back
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FinalNewline {
+    /// Whatever the file ended with before
+    Preserve,
+    /// Exactly one, however many (or few) there were
+    Single,
+}
+
+/// A file's newline conventions, detected before a rewrite so they survive it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NewlineStyle {
+    pub line_ending: LineEnding,
+    pub trailing_newline: bool,
+    /// Collapse the newlines the text ends with into one
+    pub single_trailing_newline: bool,
+}
+
+impl NewlineStyle {
+    /// Detect the dominant line ending and whether the text ends with a newline
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+
+        Self {
+            line_ending: if crlf > lf { LineEnding::Crlf } else { LineEnding::Lf },
+            trailing_newline: text.ends_with('\n'),
+            single_trailing_newline: false,
+        }
+    }
+
+    /// Use `line_ending` instead of the detected line ending, if given
+    pub fn with_line_ending(mut self, line_ending: Option<LineEnding>) -> Self {
+        if let Some(line_ending) = line_ending {
+            self.line_ending = line_ending;
+        }
+        self
+    }
+
+    /// End with exactly one newline with `FinalNewline::Single`, if given
+    pub fn with_final_newline(mut self, final_newline: Option<FinalNewline>) -> Self {
+        if final_newline == Some(FinalNewline::Single) {
+            self.trailing_newline = true;
+            self.single_trailing_newline = true;
+        }
+        self
+    }
+
+    /// Rewrite text to this style, fixing any mixed line endings
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = to_lf(text);
+
+        if self.single_trailing_newline {
+            text.truncate(text.trim_end_matches('\n').len());
+        }
+        if self.trailing_newline && !text.ends_with('\n') {
+            text.push('\n');
+        } else if !self.trailing_newline {
+            text.truncate(text.trim_end_matches('\n').len());
+        }
+
+        match self.line_ending {
+            LineEnding::Lf => text,
+            LineEnding::Crlf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// Convert all line endings to `\n`
+pub fn to_lf(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            NewlineStyle::detect("a\r\nb\r\nc\n"),
+            NewlineStyle { line_ending: LineEnding::Crlf, trailing_newline: true, single_trailing_newline: false }
+        );
+        assert_eq!(
+            NewlineStyle::detect("a\nb"),
+            NewlineStyle { line_ending: LineEnding::Lf, trailing_newline: false, single_trailing_newline: false }
+        );
+        assert_eq!(NewlineStyle::detect("").line_ending, LineEnding::Lf);
+    }
+
+    #[test]
+    fn test_apply() {
+        let crlf = NewlineStyle::detect("a\r\nb\r\n");
+        assert_eq!(crlf.apply("a\nb\r\nc"), "a\r\nb\r\nc\r\n");
+
+        let lf_no_trailing = NewlineStyle::detect("a\nb");
+        assert_eq!(lf_no_trailing.apply("a\r\nb\n\n"), "a\nb");
+
+        assert_eq!(crlf.with_line_ending(Some(LineEnding::Lf)).apply("a\r\n"), "a\n");
+
+        let single = Some(FinalNewline::Single);
+        assert_eq!(crlf.with_final_newline(single).apply("a\n\n\n"), "a\r\n");
+        assert_eq!(lf_no_trailing.with_final_newline(single).apply("a"), "a\n");
+        assert_eq!(lf_no_trailing.with_final_newline(Some(FinalNewline::Preserve)).apply("a\n"), "a");
+    }
+}
@@ -3,13 +3,17 @@ use pest_derive::Parser;
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 
-use crate::types::{ProjectConfig, ProjectMd, TaskFile, TaskFileConfig, TaskItem, TaskStatus};
+use crate::migrate::migrate_config;
+use crate::types::{split_list_marker, FrontMatter, ProjectConfig, ProjectMd, TaskFile, TaskFileConfig, TaskItem, TaskStatus, DEFAULT_ISSUE_PREFIX, DEFAULT_LIST_MARKER};
 
 #[derive(Parser)]
 #[grammar = "projectmd.pest"]
 pub struct ProjectMdParser;
 
 /// Parse a project.md file
+///
+/// A malformed task line doesn't fail the whole file: it is recorded in
+/// `task_errors` and the other tasks are still returned.
 pub fn parse_project_file(content: &str) -> Result<ProjectMd> {
     let mut pairs = ProjectMdParser::parse(Rule::document, content)
         .context("Failed to parse project file")?;
@@ -18,6 +22,7 @@ pub fn parse_project_file(content: &str) -> Result<ProjectMd> {
 
     let mut config = None;
     let mut tasks = Vec::new();
+    let mut task_errors = Vec::new();
 
     for pair in document.into_inner() {
         match pair.as_rule() {
@@ -30,8 +35,22 @@ pub fn parse_project_file(content: &str) -> Result<ProjectMd> {
             }
             Rule::content => {
                 for content_pair in pair.into_inner() {
-                    if let Rule::task_item = content_pair.as_rule() {
-                        tasks.push(parse_task_item(content_pair)?);
+                    let line = content_pair.as_span().start_pos().line_col().0;
+                    match content_pair.as_rule() {
+                        Rule::task_item => match parse_task_item(content_pair.clone(), config.as_ref()) {
+                            Ok(Some(task)) => tasks.push(task),
+                            // A bracketed word like `[v2]` that isn't a marker in this project is prose
+                            Ok(None) => {
+                                if looks_like_task(content_pair.as_str(), config.as_ref()) {
+                                    task_errors.push((line, NOT_A_TASK.to_string()));
+                                }
+                            }
+                            Err(e) => task_errors.push((line, format!("{:#}", e))),
+                        },
+                        Rule::text_line if looks_like_task(content_pair.as_str(), config.as_ref()) => {
+                            task_errors.push((line, NOT_A_TASK.to_string()))
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -42,11 +61,45 @@ pub fn parse_project_file(content: &str) -> Result<ProjectMd> {
 
     let config = config.context("Missing YAML front matter")?;
 
-    Ok(ProjectMd { config, tasks })
+    Ok(ProjectMd { config, tasks, task_errors })
 }
 
-/// Parse a task markdown file
+const NOT_A_TASK: &str = "looks like a task but isn't `* [marker] - path - description` or `* [marker] - description`";
+
+/// Check if a line that didn't parse as a task starts like one, e.g. `* [#12]
+/// tasks/a.md` with a missing ` - `
+///
+/// Markdown links (`* [text](url)`) and checklists (`* [ ]`) don't count.
+fn looks_like_task(line: &str, config: Option<&ProjectConfig>) -> bool {
+    let Some(marker) = split_list_marker(line.trim_start()).and_then(|(_, rest)| rest.strip_prefix('[')) else {
+        return false;
+    };
+    if marker.split_once(']').is_some_and(|(_, after)| after.starts_with('(')) {
+        return false;
+    }
+    let marker = marker.to_ascii_lowercase();
+    let prefix = config.map_or(DEFAULT_ISSUE_PREFIX, |c| c.issue_prefix()).to_ascii_lowercase();
+    marker.starts_with('#') || marker.starts_with(&prefix) || marker.starts_with("new") || marker.starts_with("wip")
+}
+
+/// Parse a task markdown file, which must have front matter
 pub fn parse_task_file(content: &str) -> Result<TaskFile> {
+    parse_task_file_with(content, FrontMatter::Required)
+}
+
+/// Whether a task file opens with a `---` front matter delimiter
+pub fn has_front_matter(content: &str) -> bool {
+    content.trim_start_matches('\u{feff}').trim_start().starts_with("---")
+}
+
+/// Parse a task markdown file, reading one without front matter as plain
+/// markdown with an empty config if `front_matter` is optional
+pub fn parse_task_file_with(content: &str, front_matter: FrontMatter) -> Result<TaskFile> {
+    if front_matter == FrontMatter::Optional && !has_front_matter(content) {
+        let (title, body) = extract_title_and_body(content.trim());
+        return Ok(TaskFile { config: TaskFileConfig::default(), title, body });
+    }
+
     // Split by --- separator
     let parts: Vec<&str> = content.splitn(3, "---").collect();
 
@@ -70,22 +123,96 @@ pub fn parse_task_file(content: &str) -> Result<TaskFile> {
     })
 }
 
+/// Render the pest parse tree of a project file, one rule per line
+///
+/// Used by `projectmd debug parse -vv` to show how the grammar matched.
+pub fn parse_tree(content: &str) -> Result<String> {
+    let pairs = ProjectMdParser::parse(Rule::document, content)
+        .context("Failed to parse project file")?;
+
+    let mut out = String::new();
+    for pair in pairs {
+        write_pair(&mut out, pair, 0);
+    }
+
+    Ok(out)
+}
+
+fn write_pair(out: &mut String, pair: pest::iterators::Pair<Rule>, depth: usize) {
+    let span = pair.as_span();
+    out.push_str(&format!(
+        "{}{:?} {}..{} {:?}\n",
+        "  ".repeat(depth),
+        pair.as_rule(),
+        span.start(),
+        span.end(),
+        pair.as_str()
+    ));
+
+    for inner in pair.into_inner() {
+        write_pair(out, inner, depth + 1);
+    }
+}
+
 fn parse_yaml_frontmatter(yaml_str: &str) -> Result<ProjectConfig> {
-    serde_yaml::from_str(yaml_str)
-        .context("Failed to parse YAML front matter")
+    let yaml: serde_yaml::Value = serde_yaml::from_str(yaml_str)
+        .context("Failed to parse YAML front matter")?;
+    let migrated = migrate_config(yaml.clone())?;
+
+    // Parse unmigrated files from the source so errors keep their locations
+    if migrated == yaml {
+        serde_yaml::from_str(yaml_str)
+    } else {
+        serde_yaml::from_value(migrated)
+    }
+    .context("Failed to parse YAML front matter")
+}
+
+/// Parse a single project.md line as a task, or `None` if it isn't one
+pub fn parse_task_line(line: &str, config: &ProjectConfig) -> Option<TaskItem> {
+    let line = format!("{}\n", line);
+    let pair = ProjectMdParser::parse(Rule::task_item, &line).ok()?.next()?;
+    // A task line is the whole line, not a prefix of it
+    if pair.as_str() != line {
+        return None;
+    }
+    parse_task_item(pair, Some(config)).ok().flatten()
 }
 
-fn parse_task_item(pair: pest::iterators::Pair<Rule>) -> Result<TaskItem> {
+/// Parse a task line, checking its issue prefix against `config`'s
+///
+/// Markers written with `#` or the configured prefix (in any case) but not
+/// matching it exactly are errors. Any other bracketed prefix, like `[v2]`,
+/// means the line isn't a task and gives `None`.
+fn parse_task_item(pair: pest::iterators::Pair<Rule>, config: Option<&ProjectConfig>) -> Result<Option<TaskItem>> {
     let mut status = None;
     let mut path = None;
     let mut description = None;
+    let mut list_marker = DEFAULT_LIST_MARKER.to_string();
+    let mut completed = false;
 
     for inner in pair.into_inner() {
         match inner.as_rule() {
+            Rule::list_marker => {
+                list_marker = inner.as_str().to_string();
+            }
+            Rule::completed => {
+                completed = true;
+            }
             Rule::task_status => {
-                status = Some(parse_task_status(inner)?);
+                let marker = inner.as_str();
+                let parsed = parse_task_status(inner)?;
+                let expected = config.map_or(DEFAULT_ISSUE_PREFIX, |c| c.issue_prefix());
+                if parsed != TaskStatus::New && marker != parsed.marker_with(expected) {
+                    let written = written_prefix(marker);
+                    if written != DEFAULT_ISSUE_PREFIX && !written.eq_ignore_ascii_case(expected) {
+                        return Ok(None);
+                    }
+                    anyhow::bail!("marker {} doesn't use the issue prefix '{}'", marker, expected);
+                }
+                status = Some(parsed);
             }
-            Rule::task_path => {
+            Rule::task_path | Rule::bare_task_path => {
                 path = Some(PathBuf::from(inner.as_str()));
             }
             Rule::task_description => {
@@ -95,31 +222,47 @@ fn parse_task_item(pair: pest::iterators::Pair<Rule>) -> Result<TaskItem> {
         }
     }
 
-    Ok(TaskItem {
+    Ok(Some(TaskItem {
         status: status.context("Missing task status")?,
-        path: path.context("Missing task path")?,
-        description: description.context("Missing task description")?,
-    })
+        description: match (&path, description) {
+            (None, None) => anyhow::bail!("Missing task description"),
+            // Would make an issue with no title
+            (_, Some(description)) if description.trim().is_empty() => anyhow::bail!("Empty task description after ` - `"),
+            (_, description) => description,
+        },
+        path,
+        list_marker,
+        completed,
+    }))
+}
+
+/// The prefix a marker was written with, e.g. `GH-` for `[wip:GH-7]`
+fn written_prefix(marker: &str) -> &str {
+    let inner = marker.trim_start_matches('[');
+    let inner = inner.strip_prefix("wip:").unwrap_or(inner);
+    inner.trim_end_matches(']').trim_end_matches(|c: char| c.is_ascii_digit())
 }
 
 fn parse_task_status(pair: pest::iterators::Pair<Rule>) -> Result<TaskStatus> {
     let inner = pair.into_inner().next().context("Empty task status")?;
 
     match inner.as_rule() {
-        Rule::existing_issue => {
-            let issue_num = inner.into_inner()
-                .next()
-                .context("Missing issue number")?
-                .as_str()
-                .parse::<u64>()
-                .context("Invalid issue number")?;
-            Ok(TaskStatus::Existing(issue_num))
-        }
+        Rule::existing_issue => Ok(TaskStatus::Existing(parse_issue_number(inner)?)),
+        Rule::wip_issue => Ok(TaskStatus::InProgress(parse_issue_number(inner)?)),
         Rule::new_issue => Ok(TaskStatus::New),
         _ => anyhow::bail!("Invalid task status"),
     }
 }
 
+fn parse_issue_number(pair: pest::iterators::Pair<Rule>) -> Result<u64> {
+    pair.into_inner()
+        .find(|inner| inner.as_rule() == Rule::issue_number)
+        .context("Missing issue number")?
+        .as_str()
+        .parse::<u64>()
+        .context("Invalid issue number")
+}
+
 fn extract_title_and_body(markdown: &str) -> (String, String) {
     let lines: Vec<&str> = markdown.lines().collect();
 
@@ -143,6 +286,7 @@ fn extract_title_and_body(markdown: &str) -> (String, String) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::EpicRef;
 
     #[test]
     fn test_parse_project_file() {
@@ -164,11 +308,74 @@ Description paragraph.
         assert_eq!(result.tasks.len(), 2);
 
         assert_eq!(result.tasks[0].status, TaskStatus::Existing(1));
-        assert_eq!(result.tasks[0].path.to_str().unwrap(), "tasks/setup_auth.md");
+        assert_eq!(result.tasks[0].path.as_ref().unwrap().to_str().unwrap(), "tasks/setup_auth.md");
 
         assert_eq!(result.tasks[1].status, TaskStatus::New);
     }
 
+    #[test]
+    fn test_parse_reports_every_task_error() {
+        let content = "backend: github\nrepo: a/b\n---\n* [#1] - tasks/a.md - A\n* [#99999999999999999999] - tasks/b.md - B\n* [#3] tasks/c.md - C\n* [link](https://example.com)\n* [ ] checklist item\n* [new] - Quick fix\n";
+
+        let result = parse_project_file(content).unwrap();
+        assert_eq!(result.tasks.len(), 2);
+        assert_eq!(result.tasks[1].description.as_deref(), Some("Quick fix"));
+
+        let lines: Vec<usize> = result.task_errors.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![5, 6]);
+        assert!(result.task_errors[0].1.contains("Invalid issue number"));
+        assert!(result.task_errors[1].1.contains("looks like a task"));
+    }
+
+    #[test]
+    fn test_parse_wip_marker() {
+        let content = "backend: github\nrepo: a/b\n---\n* [wip:#7] - tasks/auth.md - Auth\n";
+
+        let result = parse_project_file(content).unwrap();
+        assert_eq!(result.tasks[0].status, TaskStatus::InProgress(7));
+        assert_eq!(result.tasks[0].status.issue_id(), Some(7));
+        assert_eq!(result.tasks[0].line(), "* [wip:#7] - tasks/auth.md - Auth");
+    }
+
+    #[test]
+    fn test_parse_completed_checkbox() {
+        let content = "backend: github\nrepo: a/b\n---\n* [#5] - tasks/x.md - Desc ✅\n* [#6] - tasks/y.md [x]\n* [new] - Quick fix [X]\n* [#7] - Not done\n* [#8] - tasks/[x].md - Brackets\n";
+
+        let result = parse_project_file(content).unwrap();
+        assert!(result.task_errors.is_empty(), "{:?}", result.task_errors);
+        let completed: Vec<_> = result.tasks.iter().map(|t| t.completed).collect();
+        assert_eq!(completed, vec![true, true, true, false, false]);
+        assert_eq!(result.tasks[0].description.as_deref(), Some("Desc"));
+        assert_eq!(result.tasks[1].path, Some(PathBuf::from("tasks/y.md")));
+        assert_eq!(result.tasks[1].description, None);
+        assert_eq!(result.tasks[2].description.as_deref(), Some("Quick fix"));
+        assert_eq!(result.tasks[2].line(), "* [new] - Quick fix");
+    }
+
+    #[test]
+    fn test_parse_issue_prefix() {
+        let content = "backend: github\nrepo: a/b\nissue_prefix: GH-\n---\n* [GH-42] - tasks/a.md - A\n* [wip:GH-7] - B\n* [new] - C\n* [#3] - D\n";
+
+        let result = parse_project_file(content).unwrap();
+        let statuses: Vec<_> = result.tasks.iter().map(|t| t.status.clone()).collect();
+        assert_eq!(statuses, vec![TaskStatus::Existing(42), TaskStatus::InProgress(7), TaskStatus::New]);
+        assert_eq!(result.tasks[0].line_in(&result.config), "* [GH-42] - tasks/a.md - A");
+
+        // `#` markers are errors once another prefix is configured, as are
+        // ones in the configured prefix's other case...
+        assert_eq!(result.task_errors.len(), 1);
+        assert!(result.task_errors[0].1.contains("issue prefix 'GH-'"), "{:?}", result.task_errors);
+        let case = parse_project_file("backend: github\nrepo: a/b\nissue_prefix: GH-\n---\n* [gh-1] - A\n").unwrap();
+        assert_eq!(case.task_errors.len(), 1);
+
+        // ...but other bracketed prefixes are prose, not markers
+        let default = parse_project_file("backend: github\nrepo: a/b\n---\n* [GH-1] - A\n* [v2] - shipped auth\n").unwrap();
+        assert!(default.tasks.is_empty());
+        assert!(default.task_errors.is_empty(), "{:?}", default.task_errors);
+        let missing_separator = parse_project_file("backend: github\nrepo: a/b\nissue_prefix: GH-\n---\n* [GH-1] tasks/a.md\n").unwrap();
+        assert_eq!(missing_separator.task_errors.len(), 1);
+    }
+
     #[test]
     fn test_parse_task_file() {
         let content = r#"---
@@ -186,4 +393,53 @@ Some details go here.
         assert_eq!(result.title, "Setup the authentication");
         assert_eq!(result.body, "Some details go here.");
     }
+
+    #[test]
+    fn test_parse_task_file_archived() {
+        let archived = parse_task_file("---\narchived: true\n---\n# Old\n").unwrap();
+        assert_eq!(archived.config.archived, Some(true));
+        assert!(!archived.config.extra.contains_key("archived"));
+
+        assert_eq!(parse_task_file("---\n---\n# New\n").unwrap().config.archived, None);
+        assert!(parse_task_file("---\narchived: soon\n---\n# Old\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_task_file_without_front_matter() {
+        let content = "# Plain task\n\nNo front matter here.\n\n---\n\nJust a rule.\n";
+
+        let error = parse_task_file(content).unwrap_err().to_string();
+        assert!(error.contains("front matter"), "{}", error);
+
+        let result = parse_task_file_with(content, FrontMatter::Optional).unwrap();
+        assert!(result.config.issue_id.is_none());
+        assert_eq!(result.title, "Plain task");
+        assert_eq!(result.body, "No front matter here.\n\n---\n\nJust a rule.");
+
+        // Files that do have front matter are read the same either way
+        let with = parse_task_file_with("---\nissue_id: 3\n---\n# T\n", FrontMatter::Optional).unwrap();
+        assert_eq!(with.config.issue_id, Some(3));
+    }
+
+    #[test]
+    fn test_parse_tree() {
+        let tree = parse_tree("backend: github\nrepo: a/b\n---\n* [#3] - tasks/a.md - A\n").unwrap();
+
+        assert!(tree.starts_with("document 0.."));
+        assert!(tree.contains("\n        existing_issue "));
+        assert!(tree.contains("task_path 39..49 \"tasks/a.md\""));
+    }
+
+    #[test]
+    fn test_parse_task_file_epic() {
+        let by_number = parse_task_file("---\nepic: 12\n---\n# Child\n").unwrap();
+        assert_eq!(by_number.config.epic.as_deref(), Some("12"));
+        assert_eq!(EpicRef::parse("#12"), EpicRef::Issue(12));
+
+        let by_path = parse_task_file("---\nepic: tasks/auth.md\n---\n# Child\n").unwrap();
+        assert_eq!(
+            by_path.config.epic.as_deref().map(EpicRef::parse),
+            Some(EpicRef::Task(PathBuf::from("tasks/auth.md")))
+        );
+    }
 }
@@ -0,0 +1,292 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::backend::Issue;
+use crate::sync::IssueFields;
+use crate::types::TaskItem;
+
+/// What a sync would do with a task
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanAction {
+    Create,
+    Update(u64),
+    Unchanged,
+}
+
+/// A task in a sync plan, with what would be sent for it
+#[derive(Debug, Clone)]
+pub struct PlanEntry {
+    pub task: TaskItem,
+    pub action: PlanAction,
+    /// The fields sync would send, or why the task file couldn't be read
+    pub fields: Result<IssueFields, String>,
+    /// The issue as it is now, for updates when the backend could be reached
+    pub current: Option<Issue>,
+}
+
+/// An issue a sync would close after syncing its task
+#[derive(Debug, Clone)]
+pub struct PlanClose {
+    pub task: TaskItem,
+    /// `None` for an issue the same sync creates
+    pub number: Option<u64>,
+    /// The issue it is closed as a duplicate of, if any
+    pub duplicate_of: Option<u64>,
+}
+
+impl PlanClose {
+    /// The issue and task, e.g. `#5 tasks/a.md (duplicate of #3)`
+    pub fn summary(&self) -> String {
+        let issue = self.number.map_or("(new issue)".to_string(), |n| format!("#{}", n));
+        match self.duplicate_of {
+            Some(original) => format!("{} {} (duplicate of #{})", issue, self.task.name(), original),
+            None => format!("{} {}", issue, self.task.name()),
+        }
+    }
+}
+
+/// A sync plan as a markdown document for reviewing, e.g. attached to a PR
+///
+/// Creates and updates get a section each, with an update's changes shown
+/// as a diff against its current issue when that was fetched. Closes and
+/// unchanged tasks are only listed.
+pub fn render_plan_md(repo: &str, entries: &[PlanEntry], closes: &[PlanClose]) -> String {
+    let of = |action: fn(&PlanAction) -> bool| entries.iter().filter(move |e| action(&e.action));
+    let creates: Vec<_> = of(|a| *a == PlanAction::Create).collect();
+    let updates: Vec<_> = of(|a| matches!(a, PlanAction::Update(_))).collect();
+    let unchanged: Vec<_> = of(|a| *a == PlanAction::Unchanged).collect();
+
+    let mut md = format!(
+        "# Sync plan for {}\n\n{} to create, {} to update, {} to close, {} unchanged.\n",
+        repo,
+        creates.len(),
+        updates.len(),
+        closes.len(),
+        unchanged.len()
+    );
+
+    if !creates.is_empty() {
+        md.push_str("\n## Create\n");
+        for entry in creates {
+            md.push_str(&format!("\n### {}\n\n", entry.task.name()));
+            md.push_str(&render_fields(&entry.fields));
+        }
+    }
+
+    if !updates.is_empty() {
+        md.push_str("\n## Update\n");
+        for entry in updates {
+            let PlanAction::Update(number) = entry.action else { continue };
+            md.push_str(&format!("\n### #{} {}\n\n", number, entry.task.name()));
+            match (&entry.fields, &entry.current) {
+                (Ok(fields), Some(issue)) => {
+                    let old = format!("# {}\n\n{}", issue.title, issue.body.trim_end());
+                    let new = format!("# {}\n\n{}", fields.title, fields.body.trim_end());
+                    md.push_str(&format!("````diff\n{}````\n", line_diff(&old, &new)));
+                }
+                (fields, _) => md.push_str(&render_fields(fields)),
+            }
+        }
+    }
+
+    if !closes.is_empty() {
+        md.push_str("\n## Close\n\n");
+        for close in closes {
+            md.push_str(&format!("- {}\n", close.summary()));
+        }
+    }
+
+    if !unchanged.is_empty() {
+        md.push_str("\n## Unchanged\n\n");
+        for entry in unchanged {
+            md.push_str(&format!("- {}\n", entry.task.name()));
+        }
+    }
+
+    md
+}
+
+/// The title, labels, assignees and body of a task's issue
+fn render_fields(fields: &Result<IssueFields, String>) -> String {
+    let fields = match fields {
+        Ok(fields) => fields,
+        Err(e) => return format!("Can't be synced: {}\n", e),
+    };
+    let list = |items: &[String]| match items.is_empty() {
+        true => "(none)".to_string(),
+        false => items.join(", "),
+    };
+
+    let mut md = format!(
+        "- Title: {}\n- Labels: {}\n- Assignees: {}\n",
+        fields.title,
+        list(&fields.labels),
+        list(&fields.assignees)
+    );
+    if let Some(milestone) = &fields.milestone {
+        md.push_str(&format!("- Milestone: {}\n", milestone));
+    }
+    // Four backticks, so code blocks in the body don't end the fence
+    md.push_str(&format!("\n````markdown\n{}\n````\n", fields.body.trim_end()));
+    md
+}
+
+/// The fields in a rendered issue file's front matter
+#[derive(Serialize)]
+struct RenderedIssue<'a> {
+    title: &'a str,
+    labels: &'a [String],
+    assignees: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    milestone: Option<&'a str>,
+}
+
+/// An issue as sync would send it: title, labels, assignees and milestone as
+/// YAML front matter, followed by the body exactly
+pub fn render_issue_file(fields: &IssueFields) -> Result<String> {
+    let front_matter = serde_yaml::to_string(&RenderedIssue {
+        title: &fields.title,
+        labels: &fields.labels,
+        assignees: &fields.assignees,
+        milestone: fields.milestone.as_deref(),
+    })?;
+    Ok(format!("---\n{}---\n{}", front_matter, fields.body))
+}
+
+/// A diff of two texts by line, each line prefixed with `-`, `+` or a space
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths of each pair of suffixes
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = match old[i] == new[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = String::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!(" {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskStatus;
+
+    fn task(status: TaskStatus, path: Option<&str>, description: &str) -> TaskItem {
+        TaskItem::new(status, path.map(Into::into), Some(description.to_string()))
+    }
+
+    fn fields(title: &str, body: &str) -> IssueFields {
+        IssueFields {
+            title: title.to_string(),
+            body: body.to_string(),
+            labels: vec!["api".to_string()],
+            assignees: Vec::new(),
+            milestone: None,
+            keep_labels: false,
+        }
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("a\nb\nc", "a\nc\nd"), " a\n-b\n c\n+d\n");
+        assert_eq!(line_diff("", "a"), "+a\n");
+    }
+
+    #[test]
+    fn test_render_issue_file() {
+        let mut fields = fields("Task A", "Body\n");
+        assert_eq!(render_issue_file(&fields).unwrap(), "---\ntitle: Task A\nlabels:\n- api\nassignees: []\n---\nBody\n");
+
+        fields.milestone = Some("v2".to_string());
+        assert!(render_issue_file(&fields).unwrap().contains("assignees: []\nmilestone: v2\n---\n"));
+    }
+
+    #[test]
+    fn test_render_plan_md() {
+        let current = Issue {
+            id: 1,
+            node_id: "I_1".to_string(),
+            number: 1,
+            title: "Old title".to_string(),
+            body: "Same line\nOld line\n".to_string(),
+            state: "open".to_string(),
+            labels: Vec::new(),
+            url: String::new(),
+            comments: None,
+            reactions: None,
+            closed_at: None,
+            milestone: None,
+        };
+        let entries = vec![
+            PlanEntry {
+                task: task(TaskStatus::New, Some("tasks/a.md"), "A"),
+                action: PlanAction::Create,
+                fields: Ok(fields("Task A", "```\ncode\n```")),
+                current: None,
+            },
+            PlanEntry {
+                task: task(TaskStatus::Existing(1), Some("tasks/c.md"), "C"),
+                action: PlanAction::Update(1),
+                fields: Ok(fields("Task C", "Same line\nNew line")),
+                current: Some(current),
+            },
+            PlanEntry {
+                task: task(TaskStatus::Existing(2), None, "Quick fix"),
+                action: PlanAction::Update(2),
+                fields: Err("task file doesn't exist".to_string()),
+                current: None,
+            },
+            PlanEntry {
+                task: task(TaskStatus::Existing(3), Some("tasks/b.md"), "B"),
+                action: PlanAction::Unchanged,
+                fields: Ok(fields("Task B", "")),
+                current: None,
+            },
+        ];
+
+        let closes = vec![
+            PlanClose { task: task(TaskStatus::Existing(3), Some("tasks/b.md"), "B"), number: Some(3), duplicate_of: Some(1) },
+            PlanClose { task: task(TaskStatus::New, Some("tasks/a.md"), "A"), number: None, duplicate_of: None },
+        ];
+
+        assert_eq!(
+            render_plan_md("a/b", &entries, &closes),
+            "# Sync plan for a/b\n\n1 to create, 2 to update, 2 to close, 1 unchanged.\n\
+             \n## Create\n\
+             \n### tasks/a.md\n\n\
+             - Title: Task A\n- Labels: api\n- Assignees: (none)\n\
+             \n````markdown\n```\ncode\n```\n````\n\
+             \n## Update\n\
+             \n### #1 tasks/c.md\n\n\
+             ````diff\n-# Old title\n+# Task C\n \n Same line\n-Old line\n+New line\n````\n\
+             \n### #2 \"Quick fix\"\n\n\
+             Can't be synced: task file doesn't exist\n\
+             \n## Close\n\n\
+             - #3 tasks/b.md (duplicate of #1)\n\
+             - (new issue) tasks/a.md\n\
+             \n## Unchanged\n\n\
+             - tasks/b.md\n"
+        );
+    }
+}
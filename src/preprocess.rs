@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::types::ProjectConfig;
+
+/// Transforms a task body before it is pushed to the backend
+pub trait BodyPreprocessor: Send + Sync {
+    /// Process the body of the task at `task_path` (`None` for description-only tasks)
+    fn process(&self, task_path: Option<&Path>, body: &str) -> Result<String>;
+}
+
+/// Pushes bodies unchanged
+pub struct IdentityPreprocessor;
+
+impl BodyPreprocessor for IdentityPreprocessor {
+    fn process(&self, _task_path: Option<&Path>, body: &str) -> Result<String> {
+        Ok(body.to_string())
+    }
+}
+
+/// Pipes each body through a shell command and pushes its stdout
+///
+/// The command runs in the project root with `PROJECTMD_TASK_PATH` set to the
+/// task's path (empty for description-only tasks).
+pub struct CommandPreprocessor {
+    command: String,
+    dir: PathBuf,
+}
+
+impl CommandPreprocessor {
+    pub fn new(command: &str, dir: &Path) -> Self {
+        Self {
+            command: command.to_string(),
+            dir: dir.to_path_buf(),
+        }
+    }
+}
+
+impl BodyPreprocessor for CommandPreprocessor {
+    fn process(&self, task_path: Option<&Path>, body: &str) -> Result<String> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .current_dir(&self.dir)
+            .env("PROJECTMD_TASK_PATH", task_path.unwrap_or(Path::new("")))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run body preprocessor `{}`", self.command))?;
+
+        // Write from a separate thread so a command that streams its output
+        // can't deadlock against a full stdin pipe
+        let mut stdin = child.stdin.take().context("Failed to open preprocessor stdin")?;
+        let input = body.to_string();
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("Failed to run body preprocessor `{}`", self.command))?;
+        let written = writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("Body preprocessor input thread panicked"))?;
+
+        // A command that fails without reading its input breaks the pipe, and
+        // its own error says more
+        if !output.status.success() {
+            anyhow::bail!(
+                "Body preprocessor `{}` failed ({}): {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        written.context("Failed to write task body to preprocessor")?;
+
+        String::from_utf8(output.stdout).context("Body preprocessor output is not valid UTF-8")
+    }
+}
+
+/// The preprocessor selected by `body_preprocessor` in the project config
+pub fn preprocessor_for(config: &ProjectConfig, project_root: &Path) -> Box<dyn BodyPreprocessor> {
+    match &config.body_preprocessor {
+        Some(command) => Box::new(CommandPreprocessor::new(command, project_root)),
+        None => Box::new(IdentityPreprocessor),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_preprocessor() {
+        let dir = tempfile::tempdir().unwrap();
+        let upper = CommandPreprocessor::new("tr a-z A-Z; printf \"$PROJECTMD_TASK_PATH\"", dir.path());
+
+        assert_eq!(upper.process(Some(Path::new("tasks/a.md")), "some body\n").unwrap(), "SOME BODY\ntasks/a.md");
+        assert_eq!(IdentityPreprocessor.process(None, "some body\n").unwrap(), "some body\n");
+    }
+
+    #[test]
+    fn test_failing_command_preprocessor() {
+        let dir = tempfile::tempdir().unwrap();
+        let failing = CommandPreprocessor::new("echo nope >&2; exit 3", dir.path());
+
+        let error = failing.process(None, "body").unwrap_err().to_string();
+        assert!(error.contains("nope"), "{}", error);
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backend::{Backend, Comment, Issue};
+use crate::sync::IssueFields;
+use crate::types::TaskItem;
+
+/// Where offline syncs queue their operations, relative to the project root
+pub const QUEUE_FILE: &str = ".projectmd/queue.json";
+
+/// A create or update recorded by an offline sync, replayed by `flush`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedOp {
+    pub task: TaskItem,
+    /// Issue to update, or `None` to create one
+    pub issue: Option<u64>,
+    pub fields: IssueFields,
+    /// `in_progress` flag to record in the task file once applied
+    pub in_progress: Option<bool>,
+    /// Board marker to move the issue to once applied (see `board_marker`)
+    pub board_marker: Option<String>,
+}
+
+/// Operations waiting to be sent to the backend, oldest first
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Queue {
+    pub ops: Vec<QueuedOp>,
+}
+
+impl Queue {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(QUEUE_FILE)
+    }
+
+    /// Load the queue, which is empty if nothing has been queued
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read queue: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse queue: {:?}", path))
+    }
+
+    /// Save the queue, removing the file once it is empty
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = Self::path(project_root);
+
+        if self.ops.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path).context("Failed to remove empty queue")?;
+            }
+            return Ok(());
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create queue directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context("Failed to write queue")
+    }
+
+    /// Queue an operation, replacing any already queued for the same task
+    ///
+    /// Local files aren't updated until a flush, so syncing offline again
+    /// queues the same task again; only its latest content is kept, in the
+    /// place of the first so epics still run before their children.
+    pub fn push(&mut self, op: QueuedOp) {
+        match self.ops.iter_mut().find(|queued| same_task(&queued.task, &op.task)) {
+            Some(queued) => *queued = op,
+            None => self.ops.push(op),
+        }
+    }
+}
+
+fn same_task(a: &TaskItem, b: &TaskItem) -> bool {
+    match (&a.path, &b.path) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => a.description == b.description && a.status == b.status,
+        _ => false,
+    }
+}
+
+/// Stand-in backend for offline syncs, which queue operations instead
+pub struct OfflineBackend;
+
+#[async_trait]
+impl Backend for OfflineBackend {
+    async fn create_issue(&self, _title: &str, _body: &str, _labels: Vec<String>, _assignees: Vec<String>) -> Result<Issue> {
+        anyhow::bail!("Cannot create issues while offline")
+    }
+
+    async fn update_issue(&self, _number: u64, _title: &str, _body: &str, _labels: Vec<String>, _assignees: Vec<String>) -> Result<Issue> {
+        anyhow::bail!("Cannot update issues while offline")
+    }
+
+    async fn close_issue(&self, _number: u64) -> Result<Issue> {
+        anyhow::bail!("Cannot close issues while offline")
+    }
+
+    async fn get_issue(&self, _number: u64) -> Result<Issue> {
+        anyhow::bail!("Cannot fetch issues while offline")
+    }
+
+    async fn list_issues(&self) -> Result<Vec<Issue>> {
+        anyhow::bail!("Cannot list issues while offline")
+    }
+
+    async fn find_issue_by_key(&self, _key: &str) -> Result<Option<Issue>> {
+        anyhow::bail!("Cannot search issues while offline")
+    }
+
+    async fn list_comments(&self, _number: u64) -> Result<Vec<Comment>> {
+        anyhow::bail!("Cannot list comments while offline")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TaskStatus;
+
+    fn op(path: Option<&str>, description: &str, title: &str) -> QueuedOp {
+        QueuedOp {
+            task: TaskItem::new(TaskStatus::New, path.map(PathBuf::from), Some(description.to_string())),
+            issue: None,
+            fields: IssueFields {
+                title: title.to_string(),
+                body: String::new(),
+                labels: Vec::new(),
+                assignees: Vec::new(),
+                milestone: None,
+                keep_labels: false,
+            },
+            in_progress: None,
+            board_marker: Some("new".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_queue_round_trip_and_dedupe() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut queue = Queue::default();
+        queue.push(op(Some("a.md"), "A", "First"));
+        queue.push(op(None, "Quick fix", "Quick fix"));
+        queue.push(op(Some("a.md"), "A", "Second"));
+        queue.save(dir.path()).unwrap();
+
+        let loaded = Queue::load(dir.path()).unwrap();
+        assert_eq!(loaded, queue);
+        assert_eq!(loaded.ops.len(), 2);
+        assert_eq!(loaded.ops[0].fields.title, "Second");
+
+        Queue::default().save(dir.path()).unwrap();
+        assert!(!Queue::path(dir.path()).exists());
+        assert_eq!(Queue::load(dir.path()).unwrap(), Queue::default());
+    }
+}
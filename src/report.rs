@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::backend::Issue;
+use crate::cache::TaskSummary;
+
+/// Everything `status` reports, shared by its text, JSON and YAML output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusReport {
+    pub project: PathBuf,
+    pub backend: String,
+    pub repo: String,
+    pub tasks: Vec<TaskReport>,
+    /// Issue counts from the backend, when it could be reached
+    pub live: Option<LiveReport>,
+}
+
+/// A task line from project.md
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskReport {
+    /// The marker as written in project.md, e.g. `[wip:#12]`
+    pub marker: String,
+    pub path: Option<PathBuf>,
+    pub description: String,
+    pub issue_number: Option<u64>,
+    /// Details from the task file, with `--verbose`
+    pub details: Option<TaskSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveReport {
+    pub total: usize,
+    pub open: usize,
+    pub closed: usize,
+    /// Issues in any other state, e.g. `unknown` for a state the backend
+    /// didn't recognize, so `open + closed + other == total`
+    #[serde(default)]
+    pub other: usize,
+    pub tracked: Vec<TrackedIssue>,
+}
+
+impl LiveReport {
+    /// Count the backend's issues by state
+    pub fn new(issues: &[Issue], tracked: Vec<TrackedIssue>) -> Self {
+        let count = |state: &str| issues.iter().filter(|i| i.state == state).count();
+        let (open, closed) = (count("open"), count("closed"));
+        Self { total: issues.len(), open, closed, other: issues.len() - open - closed, tracked }
+    }
+}
+
+/// The live state of an issue a task points to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackedIssue {
+    pub number: u64,
+    /// The task, as its path or quoted description
+    pub task: String,
+    /// `None` if the issue wasn't among those fetched
+    pub state: Option<String>,
+    pub comments: Option<u64>,
+    pub reactions: Option<u64>,
+}
+
+/// A tag and how many tasks use it, as `tags` lists them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub tasks: usize,
+}
+
+/// Each distinct tag of the tasks, most used first and then by name
+///
+/// A task listing a tag twice counts once.
+pub fn count_tags<'a>(summaries: impl IntoIterator<Item = &'a TaskSummary>) -> Vec<TagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for summary in summaries {
+        let mut tags: Vec<&str> = summary.tags.iter().flatten().map(String::as_str).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut counts: Vec<TagCount> = counts.into_iter().map(|(tag, tasks)| TagCount { tag: tag.to_string(), tasks }).collect();
+    counts.sort_by(|a, b| b.tasks.cmp(&a.tasks).then_with(|| a.tag.cmp(&b.tag)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_report_yaml_round_trip() {
+        let report = StatusReport {
+            project: PathBuf::from("project.md"),
+            backend: "github".to_string(),
+            repo: "a/b".to_string(),
+            tasks: vec![
+                TaskReport {
+                    marker: "[wip:#3]".to_string(),
+                    path: Some(PathBuf::from("tasks/a.md")),
+                    description: "A: the first".to_string(),
+                    issue_number: Some(3),
+                    details: Some(TaskSummary {
+                        title: "A".to_string(),
+                        task_type: Some("bug".to_string()),
+                        tags: Some(vec!["infra".to_string()]),
+                    }),
+                },
+                TaskReport {
+                    marker: "[new]".to_string(),
+                    path: None,
+                    description: "Quick fix".to_string(),
+                    issue_number: None,
+                    details: None,
+                },
+            ],
+            live: Some(LiveReport {
+                total: 2,
+                open: 1,
+                closed: 1,
+                other: 0,
+                tracked: vec![TrackedIssue {
+                    number: 3,
+                    task: "tasks/a.md".to_string(),
+                    state: Some("open".to_string()),
+                    comments: Some(2),
+                    reactions: None,
+                }],
+            }),
+        };
+
+        let yaml = serde_yaml::to_string(&report).unwrap();
+        assert_eq!(serde_yaml::from_str::<StatusReport>(&yaml).unwrap(), report);
+    }
+
+    #[test]
+    fn test_live_report_counts_other_states() {
+        let issue = |number: u64, state: &str| Issue {
+            id: number,
+            node_id: format!("I_{}", number),
+            number,
+            title: String::new(),
+            body: String::new(),
+            state: state.to_string(),
+            labels: Vec::new(),
+            url: String::new(),
+            comments: None,
+            reactions: None,
+            closed_at: None,
+            milestone: None,
+        };
+        let issues = [issue(1, "open"), issue(2, "closed"), issue(3, "unknown"), issue(4, "open")];
+
+        let live = LiveReport::new(&issues, Vec::new());
+        assert_eq!((live.total, live.open, live.closed, live.other), (4, 2, 1, 1));
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where state carried from one sync to the next is kept, relative to the
+/// project root
+pub const STATE_FILE: &str = ".projectmd/state.json";
+
+/// State carried from one sync to the next
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// Position in `round_robin_assignees` of who gets the next new issue
+    #[serde(default)]
+    pub round_robin_index: usize,
+    /// The description each description-only task's issue was last synced
+    /// from, by issue number, since there's no task file to record it in
+    #[serde(default)]
+    pub synced_descriptions: BTreeMap<u64, String>,
+}
+
+impl SyncState {
+    pub fn path(project_root: &Path) -> PathBuf {
+        project_root.join(STATE_FILE)
+    }
+
+    /// Load the state, which is the default before the first sync saves it
+    pub fn load(project_root: &Path) -> Result<Self> {
+        let path = Self::path(project_root);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read sync state: {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse sync state: {:?}", path))
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<()> {
+        let path = Self::path(project_root);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create state directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context("Failed to write sync state")
+    }
+}
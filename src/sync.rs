@@ -1,15 +1,313 @@
 use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::time::SystemTime;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
 use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use tokio::sync::Notify;
+use serde::{Deserialize, Serialize};
 
-use crate::backend::Backend;
-use crate::parser::{parse_project_file, parse_task_file};
-use crate::types::{TaskItem, TaskStatus, TaskFileConfig};
+use crate::audit::{SyncAudit, AUDIT_FILE};
+use crate::backend::error::{classify, ErrorKind};
+use crate::backend::{Backend, CloseReason, Issue, RateLimit, RateLimitState, RepoAccess, Transfer};
+use crate::clock::{Clock, SystemClock};
+use crate::body::{idempotency_key, render_issue_body, rewrite_image_paths, split_footer, split_meta, IssueMeta};
+use crate::codeowners::CodeOwners;
+use crate::encoding::{read_utf8, read_utf8_within};
+use crate::frontmatter;
+use crate::git;
+use crate::ignore::IgnoreFile;
+use crate::migrate::FORMAT_VERSION;
+use crate::newline::{to_lf, NewlineStyle};
+use crate::parser::{parse_project_file, parse_task_file, parse_task_file_with, parse_task_line};
+use crate::preprocess::preprocessor_for;
+use crate::queue::{Queue, QueuedOp};
+use crate::state::SyncState;
+use crate::validate::{check_duplicate_issue_ids, check_task_errors};
+use crate::types::{format_marker, split_completed, split_list_marker, DEFAULT_TASKS_SECTION, EpicRef, ErrorAction, FrontMatter, LabelMap, MissingFilePolicy, ProjectConfig, ProjectMd, TaskFile, TaskItem, TaskStatus, TaskFileConfig, TitleSource};
+
+/// Check if a file can be opened for writing
+///
+/// Files that don't exist are treated as writable; a missing task file is
+/// reported as an error when that task is synced.
+fn is_writable(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.permissions().readonly() => false,
+        Ok(_) => fs::OpenOptions::new().write(true).open(path).is_ok(),
+        Err(_) => true,
+    }
+}
+
+/// Find the files sync would write metadata back to that are not writable
+pub fn find_read_only_files(project_file: &Path, project_root: &Path, project: &ProjectMd) -> Vec<PathBuf> {
+    let mut read_only = Vec::new();
+
+    // project.md is only rewritten when new issues get numbers
+    if project.tasks.iter().any(|t| t.status.is_new()) && !is_writable(project_file) {
+        read_only.push(project_file.to_path_buf());
+    }
+
+    for path in project.tasks.iter().filter_map(|t| t.path.as_ref()) {
+        let task_file_path = project_root.join(path);
+        if !is_writable(&task_file_path) {
+            read_only.push(task_file_path);
+        }
+    }
+
+    read_only
+}
+
+/// Fail before touching the backend if any write-back target is read-only
+///
+/// Otherwise issues would be created remotely and their numbers lost locally.
+pub fn check_write_permissions(project_file: &Path, project_root: &Path, project: &ProjectMd) -> Result<()> {
+    let read_only = find_read_only_files(project_file, project_root, project);
+
+    if read_only.is_empty() {
+        return Ok(());
+    }
+
+    let files: Vec<String> = read_only
+        .iter()
+        .map(|p| format!("  - {}", p.display()))
+        .collect();
+
+    anyhow::bail!(
+        "Cannot write sync metadata back to read-only files:\n{}",
+        files.join("\n")
+    )
+}
+
+/// Task directory `orphans` scans when none is given
+pub const DEFAULT_TASK_DIR: &str = "tasks";
+
+/// A relative path with `.` components dropped, for comparing task paths
+fn normalize_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// Markdown files under `task_dir` that no project.md line references
+///
+/// Paths are relative to the project root, sorted. Hidden files and
+/// directories are skipped.
+pub fn find_orphans(project_root: &Path, task_dir: &Path, project: &ProjectMd) -> Result<Vec<PathBuf>> {
+    let referenced: HashSet<PathBuf> = project.tasks
+        .iter()
+        .filter_map(|t| t.path.as_deref())
+        .map(normalize_path)
+        .collect();
+
+    let mut files = find_task_files(project_root, task_dir)?;
+    files.retain(|path| !referenced.contains(path));
+    Ok(files)
+}
+
+/// Where project.md and the backend disagree about which issues there are
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IssueGaps {
+    /// Numbers project.md references that the backend doesn't have
+    pub missing_remotely: Vec<u64>,
+    /// Numbers of the backend's issues that no project.md line references
+    pub untracked: Vec<u64>,
+}
+
+/// Compare the issue numbers project.md references with the backend's, each
+/// list sorted
+pub fn find_gaps(tracked: &[u64], remote: &[u64]) -> IssueGaps {
+    let tracked: BTreeSet<u64> = tracked.iter().copied().collect();
+    let remote: BTreeSet<u64> = remote.iter().copied().collect();
+    IssueGaps {
+        missing_remotely: tracked.difference(&remote).copied().collect(),
+        untracked: remote.difference(&tracked).copied().collect(),
+    }
+}
+
+/// Markdown files under `task_dir`, relative to the project root and sorted,
+/// skipping hidden files and directories and those `.projectmdignore` lists
+pub fn find_task_files(project_root: &Path, task_dir: &Path) -> Result<Vec<PathBuf>> {
+    let ignored = IgnoreFile::load(project_root)?;
+    let mut files = Vec::new();
+    let mut dirs = vec![normalize_path(task_dir)];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(project_root.join(&dir))
+            .with_context(|| format!("Failed to read task directory: {:?}", project_root.join(&dir)))?;
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let path = dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "md") && !ignored.is_ignored(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// The description for a task line pointing at a file: its title, or the
+/// file name if it has none
+pub fn title_or_stem(task_file: &TaskFile, path: &Path) -> String {
+    match task_file.title.trim() {
+        "" => path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        title => title.to_string(),
+    }
+}
+
+/// A project.md listing every task file under `task_dir`
+///
+/// Files with an `issue_id` get an `[#N]` marker, the rest `[new]`. Files that
+/// can't be parsed are left out with a warning.
+pub fn generate_project(project_root: &Path, task_dir: &Path, backend: &str, repo: &str) -> Result<String> {
+    let mut content = format!(
+        "version: {}\nbackend: {}\nrepo: {}\n---\n\n# My Project\n\n## {}\n\n",
+        FORMAT_VERSION, backend, repo, DEFAULT_TASKS_SECTION
+    );
+
+    for path in find_task_files(project_root, task_dir)? {
+        let task_file = match read_utf8(project_root.join(&path))
+            .with_context(|| format!("Failed to read task file: {:?}", project_root.join(&path)))
+            .and_then(|content| parse_task_file(&content))
+        {
+            Ok(task_file) => task_file,
+            Err(e) => {
+                eprintln!("Warning: skipping {}: {:#}", path.display(), e);
+                continue;
+            }
+        };
+
+        let status = match task_file.config.issue_id {
+            Some(number) => TaskStatus::Existing(number),
+            None => TaskStatus::New,
+        };
+        let description = title_or_stem(&task_file, &path);
+        let task = TaskItem::new(status, Some(path), Some(description));
+        content.push_str(&format!("{}\n", task.line()));
+    }
+
+    Ok(content)
+}
+
+/// Fail unless the token can write to the repo, so a sync doesn't get as far
+/// as its first create before finding out
+pub fn check_repo_access(access: RepoAccess, repo: &str) -> Result<()> {
+    match access {
+        RepoAccess::Write | RepoAccess::Unchecked => Ok(()),
+        RepoAccess::ReadOnly => anyhow::bail!("No write access to repo {}; check the token's permissions", repo),
+        RepoAccess::NotFound => anyhow::bail!("Repo {} not found, or not visible to the token", repo),
+    }
+}
+
+/// Whether a task file's `tags` include `tag`, for `sync --tag`
+///
+/// Files that don't parse count as tagged, so syncing them reports the error.
+pub fn is_tagged(task_content: &str, tag: &str, front_matter: FrontMatter) -> bool {
+    match parse_task_file_with(task_content, front_matter) {
+        Ok(task_file) => task_file.config.tags.is_some_and(|tags| tags.iter().any(|t| t == tag)),
+        Err(_) => true,
+    }
+}
+
+/// Whether a task's file sets `archived: true`, hiding it from `status` and `tasks`
+///
+/// Description-only tasks and files that can't be read or parsed aren't archived.
+pub fn is_archived(project_root: &Path, task_item: &TaskItem, config: &ProjectConfig) -> bool {
+    let Some(path) = &task_item.path else { return false };
+    let Ok(content) = read_utf8_within(project_root.join(path), config.max_task_file_bytes) else { return false };
+    parse_task_file_with(&content, config.front_matter)
+        .is_ok_and(|task_file| task_file.config.archived == Some(true))
+}
+
+/// Fail unless a task's front matter `repo`, if it has one, is the repo this
+/// project syncs to
+///
+/// A mistyped repo would otherwise have the task created somewhere it wasn't
+/// meant to be.
+pub fn check_task_repo(task_config: &TaskFileConfig, config: &ProjectConfig) -> Result<()> {
+    match &task_config.repo {
+        Some(repo) if *repo != config.repo => {
+            anyhow::bail!("Task repo {} isn't the project's repo {}, which tasks are synced to", repo, config.repo)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether `--follow-transfers` recorded a task's issue as moved: its file
+/// names another repo and a number other than the one project.md still has
+fn followed_transfer(task_item: &TaskItem, task_config: &TaskFileConfig, config: &ProjectConfig) -> bool {
+    task_config.repo.as_ref().is_some_and(|repo| *repo != config.repo)
+        && task_item.status.issue_id().is_some_and(|num| task_config.issue_id.is_some_and(|id| id != num))
+}
+
+/// The project's `required_fields` a task's front matter leaves out or sets to null
+///
+/// Fields are front matter keys as written, so `type` and any extra key count.
+pub fn missing_required_fields<'a>(task_config: &TaskFileConfig, config: &'a ProjectConfig) -> Vec<&'a str> {
+    let front_matter = serde_yaml::to_value(task_config).unwrap_or_default();
+    config
+        .required_fields
+        .iter()
+        .map(String::as_str)
+        .filter(|field| front_matter.get(field).is_none_or(serde_yaml::Value::is_null))
+        .collect()
+}
+
+/// Fail if a task's front matter lacks any of the project's `required_fields`
+pub fn check_required_fields(task_config: &TaskFileConfig, config: &ProjectConfig) -> Result<()> {
+    let missing = missing_required_fields(task_config, config);
+    if !missing.is_empty() {
+        anyhow::bail!("Missing required front matter fields: {}", missing.join(", "));
+    }
+    Ok(())
+}
+
+/// Fail if project.md or any task file has changes that aren't committed
+///
+/// Outside a git repository there is nothing to compare against, so this only
+/// warns.
+pub fn check_clean(project_file: &Path, project_root: &Path, project: &ProjectMd) -> Result<()> {
+    if git::repo_root(project_root).is_err() {
+        eprintln!("Warning: not inside a git repository; --require-clean has no effect");
+        return Ok(());
+    }
+
+    let files: Vec<PathBuf> = std::iter::once(project_file.to_path_buf())
+        .chain(project.tasks.iter().filter_map(|t| Some(project_root.join(t.path.as_ref()?))))
+        .collect();
+    let dirty = git::uncommitted_files(project_root, &files)?;
+
+    if dirty.is_empty() {
+        return Ok(());
+    }
+
+    let files: Vec<String> = dirty
+        .iter()
+        .map(|p| format!("  - {}", p.display()))
+        .collect();
+
+    anyhow::bail!(
+        "Refusing to sync files with uncommitted changes (--require-clean):\n{}",
+        files.join("\n")
+    )
+}
 
 /// Check if a task should be synced based on file modification time
 fn should_sync_task(task_file_path: &Path, config: &TaskFileConfig) -> Result<bool> {
+    Ok(file_change(task_file_path, config)? != SyncReason::Unchanged)
+}
+
+/// Whether a task file changed since its last sync, going by its mtime
+fn file_change(task_file_path: &Path, config: &TaskFileConfig) -> Result<SyncReason> {
     // Get file modification time
     let metadata = fs::metadata(task_file_path)?;
     let mtime: SystemTime = metadata.modified()?;
@@ -17,7 +315,7 @@ fn should_sync_task(task_file_path: &Path, config: &TaskFileConfig) -> Result<bo
 
     // If no updated_at, always sync (first time)
     let Some(updated_at_str) = &config.updated_at else {
-        return Ok(true);
+        return Ok(SyncReason::NeverSynced);
     };
 
     // Parse stored updated_at timestamp
@@ -26,233 +324,3571 @@ fn should_sync_task(task_file_path: &Path, config: &TaskFileConfig) -> Result<bo
         .with_timezone(&Utc);
 
     // Only sync if file was modified after last sync
-    Ok(mtime_utc > updated_at)
+    Ok(match mtime_utc > updated_at {
+        true => SyncReason::FileModified,
+        false => SyncReason::Unchanged,
+    })
 }
 
-/// Sync engine for managing project tasks and backend issues
-pub struct SyncEngine<B: Backend> {
-    backend: B,
-    project_root: PathBuf,
+/// Whether a task's marker moved between `[#N]` and `[wip:#N]` since it was synced
+///
+/// A marker change in project.md doesn't touch the task file's mtime.
+fn marker_changed(task_item: &TaskItem, config: &TaskFileConfig) -> bool {
+    task_item.status.is_in_progress() != (config.in_progress == Some(true))
 }
 
-impl<B: Backend> SyncEngine<B> {
-    pub fn new(backend: B, project_root: PathBuf) -> Self {
-        Self {
-            backend,
-            project_root,
-        }
+/// Whether a task's description, which `title_source: description` makes its
+/// title, changed in project.md since it was synced
+///
+/// Like a marker change, this doesn't touch the task file's mtime.
+fn description_changed(task_item: &TaskItem, task_config: &TaskFileConfig, config: &ProjectConfig) -> bool {
+    config.title_source == TitleSource::Description
+        && task_item.description.is_some()
+        && task_item.description != task_config.synced_description
+}
+
+/// Whether syncing a task would create or update its issue
+///
+/// New tasks are always pending; description-only tasks when their
+/// description changed since the last sync, and other tasks when their file,
+/// marker or (as their title) description did.
+pub fn is_pending(project_root: &Path, task_item: &TaskItem, config: &ProjectConfig) -> Result<bool> {
+    let Some(path) = &task_item.path else { return Ok(!description_synced(project_root, task_item)?) };
+    if task_item.status.is_new() {
+        return Ok(true);
     }
 
-    /// Sync all tasks in the project file with the backend
-    pub async fn sync(&self, project_file: &Path) -> Result<SyncResult> {
-        let content = fs::read_to_string(project_file)
-            .context("Failed to read project file")?;
+    let task_file_path = project_root.join(path);
+    let task_content = read_utf8_within(&task_file_path, config.max_task_file_bytes)
+        .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+    let task_file = parse_task_file_with(&task_content, config.front_matter)?;
 
-        let project = parse_project_file(&content)?;
+    Ok(marker_changed(task_item, &task_file.config)
+        || description_changed(task_item, &task_file.config, config)
+        || should_sync_task(&task_file_path, &task_file.config)?)
+}
 
-        let mut result = SyncResult {
-            created: Vec::new(),
-            updated: Vec::new(),
-            skipped: Vec::new(),
-            errors: Vec::new(),
-        };
+/// Whether a description-only task's issue was last synced from its current
+/// description
+fn description_synced(project_root: &Path, task_item: &TaskItem) -> Result<bool> {
+    let Some(issue_num) = task_item.status.issue_id() else { return Ok(false) };
+    Ok(SyncState::load(project_root)?.synced_descriptions.get(&issue_num) == task_item.description.as_ref())
+}
 
-        for task_item in &project.tasks {
-            match self.sync_task_item(task_item).await {
-                Ok(action) => match action {
-                    SyncAction::Created(issue_num) => {
-                        result.created.push((task_item.path.clone(), issue_num));
-                    }
-                    SyncAction::Updated(issue_num) => {
-                        result.updated.push((task_item.path.clone(), issue_num));
-                    }
-                    SyncAction::Skipped => {
-                        result.skipped.push(task_item.path.clone());
-                    }
-                },
-                Err(e) => {
-                    result.errors.push((task_item.path.clone(), format!("{:?}", e)));
-                }
-            }
-        }
+/// Each of `tasks` that synced, with its issue number, including one just
+/// created
+fn closable<'a, T>(tasks: impl IntoIterator<Item = (&'a TaskItem, T)>, result: &SyncResult) -> Vec<(&'a TaskItem, u64, T)> {
+    tasks
+        .into_iter()
+        .filter(|(task, _)| !result.errors.iter().any(|(t, _)| t == *task))
+        .filter_map(|(task, extra)| {
+            let created = result.created.iter().find(|(t, _)| t == task).map(|(_, num)| *num);
+            Some((task, created.or(task.status.issue_id())?, extra))
+        })
+        .collect()
+}
 
-        // Update project.md with new issue numbers
-        if !result.created.is_empty() {
-            self.update_project_file(project_file, &content, &result.created)?;
+/// The tasks whose issues sync closes after syncing them: checked-off tasks,
+/// and tasks whose files set `duplicate_of` along with the issue they duplicate
+///
+/// Issues that are closed already are left alone, so whether sync closes one
+/// depends on its state. A duplicate whose line is checked off has been
+/// handled, so only its state as a completed task is checked.
+pub fn close_candidates<'a>(
+    project_root: &Path,
+    tasks: impl IntoIterator<Item = &'a TaskItem>,
+    config: &ProjectConfig,
+) -> (Vec<&'a TaskItem>, Vec<(&'a TaskItem, u64)>) {
+    let mut completed = Vec::new();
+    let mut duplicates = Vec::new();
+    for task in tasks {
+        if task.completed {
+            completed.push(task);
+        }
+        let Some(path) = &task.path else { continue };
+        let task_file = read_utf8_within(project_root.join(path), config.max_task_file_bytes)
+            .ok()
+            .and_then(|content| parse_task_file(&content).ok());
+        if let Some(original) = task_file.and_then(|f| f.config.duplicate_of).filter(|_| !task.completed) {
+            duplicates.push((task, original));
         }
+    }
+    (completed, duplicates)
+}
 
-        Ok(result)
+/// Order tasks so that epics are synced before their children
+fn sync_order<'a>(tasks: &'a [TaskItem], epics: &HashMap<PathBuf, EpicRef>) -> Vec<&'a TaskItem> {
+    let epic_paths: HashSet<&Path> = epics
+        .values()
+        .filter_map(|epic| match epic {
+            EpicRef::Task(path) => Some(path.as_path()),
+            EpicRef::Issue(_) => None,
+        })
+        .collect();
+
+    let (epic_tasks, other_tasks): (Vec<_>, Vec<_>) = tasks
+        .iter()
+        .partition(|t| t.path.as_deref().is_some_and(|p| epic_paths.contains(p)));
+
+    epic_tasks.into_iter().chain(other_tasks).collect()
+}
+
+/// Which `ProjectBoardConfig::columns` entry applies to a task
+///
+/// `new` applies when the issue is first created; afterwards the marker picks
+/// between `open` and `wip`.
+pub fn board_marker(status: &TaskStatus, created: bool) -> &'static str {
+    match status {
+        _ if created => "new",
+        TaskStatus::InProgress(_) => "wip",
+        _ => "open",
     }
+}
 
-    /// Sync a single task item
-    async fn sync_task_item(&self, task_item: &TaskItem) -> Result<SyncAction> {
-        let task_file_path = self.project_root.join(&task_item.path);
+/// The fields sent to the backend when creating or updating a task's issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueFields {
+    pub title: String,
+    pub body: String,
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    /// Milestone title, from `repo_defaults`
+    #[serde(default)]
+    pub milestone: Option<String>,
+    /// Add `labels` to an existing issue's labels rather than replacing them,
+    /// for tasks with no `tags` key
+    #[serde(default)]
+    pub keep_labels: bool,
+}
 
-        // Read and parse the task file
-        let task_content = fs::read_to_string(&task_file_path)
-            .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+/// The stand-in task file for a description-only task
+///
+/// The description is used as both the title and the body.
+pub fn description_task_file(task_item: &TaskItem) -> TaskFile {
+    TaskFile {
+        config: TaskFileConfig::default(),
+        title: task_item.description.clone().unwrap_or_default(),
+        body: task_item.description.clone().unwrap_or_default(),
+    }
+}
+
+/// A minimal task file for a task whose file is missing, titled with its
+/// description (or, without one, its file name)
+pub fn stub_task_file(task_item: &TaskItem) -> String {
+    let stem = task_item.path.as_ref().and_then(|p| p.file_stem()).map(|s| s.to_string_lossy());
+    let title = task_item.description.as_deref().map(Into::into).or(stem).unwrap_or_default();
+    format!("---\n---\n# {}\n", title)
+}
 
-        let task_file = parse_task_file(&task_content)?;
+/// A task file's body, or the project's `default_body` if it has none
+fn body_or_default(config: &ProjectConfig, task_path: &Path, task_file: &TaskFile) -> String {
+    match &config.default_body {
+        Some(default) if split_meta(&task_file.body).1.trim().is_empty() => default
+            .replace("{{title}}", &task_file.title)
+            .replace("{{path}}", &task_path.to_string_lossy()),
+        _ => task_file.body.clone(),
+    }
+}
 
-        // Check if we need to sync this task (only for existing issues)
-        if matches!(task_item.status, TaskStatus::Existing(_)) {
-            if !should_sync_task(&task_file_path, &task_file.config)? {
-                return Ok(SyncAction::Skipped);
+/// Resolve everything that would be sent to the backend for a task
+///
+/// Shared by sync and dry runs so both show exactly the same values.
+pub fn prepare_issue(task_item: &TaskItem, task_file: &TaskFile, config: &ProjectConfig, options: &SyncOptions) -> IssueFields {
+    IssueFields {
+        title: prepare_title(config, issue_title(task_item, task_file, config), options.truncate_titles),
+        body: match &task_item.path {
+            Some(path) => {
+                let mut meta = IssueMeta::new(path, &task_file.config, task_item.status.issue_id());
+                meta.task_type = task_file.config.effective_type(config.tag_as_type).map(str::to_string);
+                render_issue_body(&meta, path, &body_or_default(config, path, task_file))
             }
-        }
+            None => task_file.body.clone(),
+        },
+        labels: resolve_labels(task_item, &task_file.config, config),
+        assignees: resolve_assignees(&task_file.config, config, &code_owners(task_item, options)),
+        milestone: config.repo_defaults.as_ref().and_then(|d| d.milestone.clone()),
+        // `tags: []` clears the tag labels; no `tags` at all leaves them be
+        keep_labels: task_file.config.tags.is_none(),
+    }
+}
 
-        // Extract labels from tags
-        let labels = task_file.config.tags
-            .clone()
-            .unwrap_or_default()
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect();
+/// An issue's current labels with `labels` added, for tasks that leave their
+/// issue's labels alone
+///
+/// `state_labels` (see `ProjectConfig::state_labels`) are still dropped unless
+/// `labels` has them, so e.g. the WIP label comes off once a task is no longer
+/// in progress.
+pub fn merge_labels(current: &[String], labels: Vec<String>, state_labels: &[&str]) -> Vec<String> {
+    let mut merged: Vec<String> = current
+        .iter()
+        .filter(|label| !state_labels.contains(&label.as_str()) && !labels.contains(label))
+        .cloned()
+        .collect();
+    merged.extend(labels);
+    merged
+}
 
-        match &task_item.status {
-            TaskStatus::New => {
-                // Create new issue
-                let issue = self.backend
-                    .create_issue(&task_file.title, &task_file.body, labels)
-                    .await?;
+/// Point the relative images in a task body at raw.githubusercontent.com when
+/// `raw_image_ref` is set, so they show on GitHub
+///
+/// Warns about images missing from the working tree or not committed, whose
+/// URLs won't resolve.
+pub fn rewrite_images(config: &ProjectConfig, project_root: &Path, task_path: &Path, body: String) -> String {
+    let Some(git_ref) = &config.raw_image_ref else {
+        return body;
+    };
 
-                // Update the task file with the new issue ID and timestamps
-                self.update_task_file_with_metadata(&task_file_path, &task_content, issue.number, true)?;
+    let project_root = match project_root.as_os_str().is_empty() {
+        true => Path::new("."),
+        false => project_root,
+    };
+    let project_root = project_root.canonicalize().unwrap_or_else(|_| project_root.to_path_buf());
+    let repo_root = git::repo_root(&project_root).ok();
+    let root = repo_root.clone().unwrap_or_else(|| project_root.clone());
+    let dir = project_root.join(task_path).parent().map(Path::to_path_buf).unwrap_or_default();
+    let dir = dir.strip_prefix(&root).unwrap_or(Path::new(""));
 
-                Ok(SyncAction::Created(issue.number))
-            }
-            TaskStatus::Existing(issue_num) => {
-                // Check if the task file has been modified (issue_id should match)
-                if task_file.config.issue_id.is_none() ||
-                   task_file.config.issue_id != Some(*issue_num) {
-                    // Update the task file to match the project file
-                    self.update_task_file_with_metadata(&task_file_path, &task_content, *issue_num, false)?;
-                }
+    let base_url = format!("https://raw.githubusercontent.com/{}/{}", config.repo, git_ref);
+    let (body, images) = rewrite_image_paths(&body, &base_url, dir);
 
-                // Update the issue
-                let issue = self.backend
-                    .update_issue(*issue_num, &task_file.title, &task_file.body, labels)
-                    .await?;
+    let (present, missing): (Vec<PathBuf>, Vec<PathBuf>) = images.iter().map(|image| root.join(image)).partition(|path| path.exists());
+    for path in missing {
+        eprintln!("Warning: {}: image {} does not exist", task_path.display(), path.display());
+    }
+    let uncommitted = match &repo_root {
+        Some(repo_root) => git::uncommitted_files(repo_root, &present).unwrap_or_else(|e| {
+            eprintln!("Warning: failed to check that images in {} are committed: {:#}", task_path.display(), e);
+            Vec::new()
+        }),
+        None => present,
+    };
+    for path in uncommitted {
+        eprintln!("Warning: {}: image {} is not committed, so its URL won't resolve", task_path.display(), path.display());
+    }
 
-                // Update the updated_at timestamp
-                self.update_task_file_with_metadata(&task_file_path, &task_content, *issue_num, false)?;
+    body
+}
 
-                Ok(SyncAction::Updated(issue.number))
-            }
+/// Merge the `tag_labels` and `label_color_rules` of a label map file into the
+/// config, keeping the inline entries where both have one
+///
+/// `file` (from `--map-labels-file`) is used instead of the config's
+/// `label_map_file`, which is relative to the project root.
+pub fn load_label_map(config: &mut ProjectConfig, project_root: &Path, file: Option<&Path>) -> Result<()> {
+    let path = match (file, &config.label_map_file) {
+        (Some(file), _) => file.to_path_buf(),
+        (None, Some(file)) => project_root.join(file),
+        (None, None) => return Ok(()),
+    };
+
+    let content = read_utf8(&path)
+        .with_context(|| format!("Failed to read label map file: {:?}", path))?;
+    let map: LabelMap = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse label map file: {:?}", path))?;
+
+    for (tag, label) in map.tag_labels {
+        config.tag_labels.entry(tag).or_insert(label);
+    }
+    for (prefix, color) in map.label_color_rules {
+        config.label_color_rules.entry(prefix).or_insert(color);
+    }
+    Ok(())
+}
+
+/// Labels to send for a task: its tags (renamed by `tag_labels`), the WIP label for `[wip:#N]` tasks,
+/// its `status_label_map` labels, the managed label and the target repo's default labels, if configured
+///
+/// Since updates replace the issue's labels, leaving the WIP label out once a
+/// task is no longer in progress removes it.
+pub fn resolve_labels(task_item: &TaskItem, task_config: &TaskFileConfig, config: &ProjectConfig) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for tag in task_config.tags.iter().flatten() {
+        let label = config.tag_labels.get(tag).unwrap_or(tag);
+        if !labels.contains(label) {
+            labels.push(label.clone());
         }
     }
 
-    /// Update the task file with issue_id and timestamps
-    fn update_task_file_with_metadata(
-        &self,
-        path: &Path,
-        content: &str,
-        issue_id: u64,
-        is_new: bool
-    ) -> Result<()> {
-        // Parse the file to get the config
-        let task_file = parse_task_file(content)?;
+    let wip_label = task_item.status.is_in_progress().then(|| config.wip_label());
+    let repo_labels = config.repo_defaults.iter().flat_map(|d| d.labels.iter().map(String::as_str));
+    let state_labels = status_labels(task_item, task_config, config);
+    for label in wip_label.into_iter().chain(state_labels).chain(config.managed_label.as_deref()).chain(repo_labels) {
+        if !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
 
-        // Update the config
-        let mut updated_config = task_file.config;
-        updated_config.issue_id = Some(issue_id);
+    labels
+}
 
-        // Set timestamps
-        let now = Utc::now().to_rfc3339();
+/// `status_label_map` labels for a task's marker and flags
+///
+/// A task being created is `new`; after that its marker picks `open` or `wip`.
+pub fn status_labels<'a>(task_item: &TaskItem, task_config: &TaskFileConfig, config: &'a ProjectConfig) -> Vec<&'a str> {
+    let marker = board_marker(&task_item.status, task_item.status.is_new());
+    let archived = task_config.archived == Some(true);
+    [Some(marker), archived.then_some("archived")]
+        .into_iter()
+        .flatten()
+        .filter_map(|state| config.status_label_map.get(state).map(String::as_str))
+        .collect()
+}
 
-        if is_new || updated_config.created_at.is_none() {
-            updated_config.created_at = Some(now.clone());
-        }
+/// Color for labels no `label_color_rules` prefix matches (GitHub's default gray)
+pub const DEFAULT_LABEL_COLOR: &str = "ededed";
 
-        updated_config.updated_at = Some(now);
+/// Color for a label from `label_color_rules`, without a leading `#`
+///
+/// The longest matching prefix wins, so `area/api/` can override `area/`.
+pub fn label_color<'a>(label: &str, rules: &'a HashMap<String, String>) -> &'a str {
+    rules
+        .iter()
+        .filter(|(prefix, _)| label.starts_with(prefix.as_str()))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(DEFAULT_LABEL_COLOR, |(_, color)| color.trim_start_matches('#'))
+}
 
-        // Serialize back to YAML
-        let yaml_str = serde_yaml::to_string(&updated_config)?;
+/// Assignees for a task: its explicit assignees, then those mapped from its
+/// tags, then its code owners
+///
+/// Duplicates are dropped, keeping the first occurrence. Tasks that resolve no
+/// assignees get the target repo's default assignees.
+pub fn resolve_assignees(task_config: &TaskFileConfig, config: &ProjectConfig, owners: &[String]) -> Vec<String> {
+    let explicit = task_config.assignees.iter().flatten();
+    let mapped = task_config
+        .tags
+        .iter()
+        .flatten()
+        .filter_map(|tag| config.tag_assignees.get(tag))
+        .flatten();
 
-        // Reconstruct the file
-        let parts: Vec<&str> = content.splitn(3, "---").collect();
-        if parts.len() < 3 {
-            anyhow::bail!("Invalid task file format");
+    let mut assignees: Vec<String> = Vec::new();
+    for login in explicit.chain(mapped).chain(owners) {
+        if !assignees.contains(login) {
+            assignees.push(login.clone());
         }
+    }
 
-        let updated_content = format!("---\n{}\n---\n{}", yaml_str.trim(), parts[2]);
+    if assignees.is_empty() {
+        if let Some(defaults) = &config.repo_defaults {
+            assignees = defaults.assignees.clone();
+        }
+    }
 
-        fs::write(path, updated_content)
-            .context("Failed to write updated task file")?;
+    assignees
+}
 
-        Ok(())
+/// Users owning a task's file in CODEOWNERS, with `--assign-from-codeowners`
+fn code_owners(task_item: &TaskItem, options: &SyncOptions) -> Vec<String> {
+    match (&options.codeowners, &task_item.path) {
+        (Some(codeowners), Some(path)) => codeowners.assignees_for(path),
+        _ => Vec::new(),
     }
+}
 
-    /// Update project.md with new issue numbers
-    fn update_project_file(&self, project_file: &Path, content: &str, created: &[(PathBuf, u64)]) -> Result<()> {
-        let mut updated_content = content.to_string();
+/// Append `- [ ] #N` lines for children not yet referenced in an epic body
+///
+/// Returns `None` if every child is already listed.
+pub fn append_child_task_list(body: &str, child_nums: &[u64]) -> Option<String> {
+    let listed = |num: u64| {
+        body.lines().any(|line| {
+            let line = line.trim();
+            [format!("- [ ] #{}", num), format!("- [x] #{}", num)]
+                .iter()
+                .any(|item| line == item || line.starts_with(&format!("{} ", item)))
+        })
+    };
+
+    let missing: Vec<String> = child_nums
+        .iter()
+        .filter(|n| !listed(**n))
+        .map(|n| format!("- [ ] #{}", n))
+        .collect();
+
+    if missing.is_empty() {
+        return None;
+    }
+
+    let body = body.trim_end();
+    if body.is_empty() {
+        Some(missing.join("\n"))
+    } else {
+        Some(format!("{}\n\n{}", body, missing.join("\n")))
+    }
+}
+
+/// Point a task's line in LF project.md content at issue `issue_num`
+///
+/// The marker is usually `[new]`, but recreated issues replace an old number.
+/// `[wip:#N]` lines stay in progress.
+pub fn set_issue_number(content: &str, task: &TaskItem, issue_num: u64, config: &ProjectConfig) -> String {
+    let new_status = match task.status {
+        TaskStatus::InProgress(_) => TaskStatus::InProgress(issue_num),
+        _ => TaskStatus::Existing(issue_num),
+    };
+    set_status(content, task, new_status, config)
+}
+
+/// Point the lines of every created task in LF project.md content at its new
+/// issue, in a single pass
+///
+/// Each task takes the first line that parses to it and hasn't been taken
+/// yet, so identical lines each get their own issue.
+pub fn set_issue_numbers(content: &str, created: &[(TaskItem, u64)], config: &ProjectConfig) -> String {
+    let changes: Vec<(&TaskItem, TaskStatus)> = created
+        .iter()
+        .map(|(task, issue_num)| match task.status {
+            TaskStatus::InProgress(_) => (task, TaskStatus::InProgress(*issue_num)),
+            _ => (task, TaskStatus::Existing(*issue_num)),
+        })
+        .collect();
+    set_statuses(content, &changes, config)
+}
+
+/// Give the lines of tasks in LF project.md content new markers, in a single
+/// pass, matching lines like `set_issue_numbers`
+fn set_statuses(content: &str, changes: &[(&TaskItem, TaskStatus)], config: &ProjectConfig) -> String {
+    let mut pending: Vec<Option<&(&TaskItem, TaskStatus)>> = changes.iter().map(Some).collect();
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.strip_suffix('\n').unwrap_or(line);
+            let Some(parsed) = parse_task_line(text, config) else { return line.to_string() };
+            let taken = pending.iter_mut().find(|slot| slot.is_some_and(|(task, _)| **task == parsed)).and_then(Option::take);
+            let Some((task, status)) = taken else { return line.to_string() };
+            let (_, checkbox) = split_completed(text);
+            let newline = &line[text.len()..];
+            format!("{}{}{}", TaskItem { status: status.clone(), ..(*task).clone() }.line_in(config), checkbox, newline)
+        })
+        .collect()
+}
+
+/// Replace the marker on a task's line in LF project.md content, using the
+/// project's `issue_prefix`
+pub fn set_status(content: &str, task: &TaskItem, new_status: TaskStatus, config: &ProjectConfig) -> String {
+    let marker = format_marker(&task.status, config);
+    let new_marker = format_marker(&new_status, config);
 
-        for (task_path, issue_num) in created {
+    let (pattern, replacement) = match (&task.path, &task.description) {
+        (Some(task_path), Some(_)) => {
             // Find and replace [new] - path - with [#issue_num] - path -
             let task_path_str = task_path.to_string_lossy();
 
             // Pattern to match: * [new] - path/to/file.md -
-            let pattern = format!("* [new] - {} -", task_path_str);
-            let replacement = format!("* [#{}] - {} -", issue_num, task_path_str);
-
-            updated_content = updated_content.replace(&pattern, &replacement);
+            (
+                format!("{} {} - {} -", task.list_marker, marker, task_path_str),
+                format!("{} {} - {} -", task.list_marker, new_marker, task_path_str),
+            )
         }
+        // Description-only tasks and tasks without a description are
+        // matched by how their line parses, and only the first matching line
+        // changes, so identical lines can be set one at a time
+        _ => return set_statuses(content, &[(task, new_status)], config),
+    };
 
-        fs::write(project_file, updated_content)
-            .context("Failed to write updated project file")?;
-
-        Ok(())
-    }
+    content.replace(&pattern, &replacement)
 }
 
-#[derive(Debug)]
-pub enum SyncAction {
-    Created(u64),
-    Updated(u64),
-    Skipped,
+/// Check a task's line in LF project.md content off with ` ✅`, unless it
+/// already is
+pub fn check_off(content: &str, task: &TaskItem, config: &ProjectConfig) -> String {
+    let mut pending = true;
+    let mut matches = |text: &str| match (&task.path, &task.description) {
+        // Like `set_status`, lines of tasks with a file and description are
+        // matched up to the description, and others by how they parse, one
+        // line per call
+        (Some(task_path), Some(_)) => {
+            let marker = format_marker(&task.status, config);
+            text.starts_with(&format!("{} {} - {} -", task.list_marker, marker, task_path.to_string_lossy()))
+        }
+        _ => {
+            let taken = pending && parse_task_line(text, config).is_some_and(|parsed| parsed == TaskItem { completed: false, ..task.clone() });
+            pending &= !taken;
+            taken
+        }
+    };
+
+    content
+        .split_inclusive('\n')
+        .map(|l| match l.strip_suffix('\n').map(split_completed) {
+            Some((text, "")) if matches(text) => format!("{} ✅\n", text),
+            _ => l.to_string(),
+        })
+        .collect()
 }
 
-#[derive(Debug)]
-pub struct SyncResult {
-    pub created: Vec<(PathBuf, u64)>,
-    pub updated: Vec<(PathBuf, u64)>,
-    pub skipped: Vec<PathBuf>,
-    pub errors: Vec<(PathBuf, String)>,
+/// Text of a markdown heading line, e.g. `Tasks` for `## Tasks`
+fn heading(line: &str) -> Option<&str> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then(|| text.trim())
 }
 
-impl SyncResult {
-    pub fn print_summary(&self) {
-        println!("\n=== Sync Summary ===");
+/// Add a task line to the end of the `section` heading's task list
+///
+/// The line goes after the section's last non-blank line, before the next
+/// heading. The heading is matched regardless of level and case, and appended
+/// as `## <section>` if project.md has none. `#` lines in fenced code blocks
+/// aren't headings.
+/// Tasks are still parsed from anywhere in the file; this only decides where
+/// new lines are written.
+pub fn append_task_line(content: &str, section: &str, line: &str) -> String {
+    let style = NewlineStyle::detect(content);
+    let content = to_lf(content);
+    let mut lines: Vec<&str> = content.lines().collect();
 
-        if !self.created.is_empty() {
-            println!("\nCreated ({}):", self.created.len());
-            for (path, issue_num) in &self.created {
-                println!("  - {} -> Issue #{}", path.display(), issue_num);
+    let mut in_code = false;
+    let headings: Vec<Option<&str>> = lines
+        .iter()
+        .map(|l| {
+            let trimmed = l.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code = !in_code;
+                None
+            } else {
+                heading(l).filter(|_| !in_code)
             }
-        }
+        })
+        .collect();
 
-        if !self.updated.is_empty() {
-            println!("\nUpdated ({}):", self.updated.len());
-            for (path, issue_num) in &self.updated {
-                println!("  - {} -> Issue #{}", path.display(), issue_num);
+    let start = headings
+        .iter()
+        .position(|text| text.is_some_and(|text| text.eq_ignore_ascii_case(section)));
+
+    match start {
+        Some(start) => {
+            let end = headings[start + 1..]
+                .iter()
+                .position(Option::is_some)
+                .map_or(lines.len(), |i| start + 1 + i);
+            let last = (start..end).rev().find(|&i| !lines[i].trim().is_empty()).unwrap_or(start);
+
+            // Keep a blank line between the heading and the list, and before
+            // the next heading
+            let mut insert = vec![line];
+            if last == start {
+                insert.insert(0, "");
+            }
+            if end < lines.len() && last + 1 == end {
+                insert.push("");
             }
+            lines.splice(last + 1..last + 1, insert);
         }
-
-        if !self.skipped.is_empty() {
-            println!("\nSkipped (no changes) ({}):", self.skipped.len());
-            for path in &self.skipped {
-                println!("  ✓ {}", path.display());
+        None => {
+            let heading = format!("## {}", section);
+            while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                lines.pop();
+            }
+            let mut appended = lines.join("\n");
+            if !appended.is_empty() {
+                appended.push_str("\n\n");
             }
+            appended.push_str(&format!("{}\n\n{}", heading, line));
+            return style.apply(&(appended + "\n"));
         }
+    }
 
-        if !self.errors.is_empty() {
-            println!("\nErrors ({}):", self.errors.len());
-            for (path, error) in &self.errors {
-                println!("  - {}: {}", path.display(), error);
+    style.apply(&(lines.join("\n") + "\n"))
+}
+
+/// Longest title GitHub accepts, in characters
+pub const BACKEND_TITLE_LIMIT: usize = 256;
+
+/// The untruncated title of a task's issue: its file's title, or with
+/// `title_source: description` its description when it has one
+pub fn issue_title<'a>(task_item: &'a TaskItem, task_file: &'a TaskFile, config: &ProjectConfig) -> &'a str {
+    match (config.title_source, &task_item.description) {
+        (TitleSource::Description, Some(description)) => description,
+        _ => &task_file.title,
+    }
+}
+
+/// The title sent for an issue, the same when creating and updating it
+///
+/// With `truncate`, titles longer than `max_title_len` are shortened to it.
+/// Titles the backend would reject are always shortened to
+/// `BACKEND_TITLE_LIMIT`.
+pub fn prepare_title(config: &ProjectConfig, raw: &str, truncate: bool) -> String {
+    let max = match config.max_title_len {
+        Some(max) if truncate => max.get().min(BACKEND_TITLE_LIMIT),
+        _ => BACKEND_TITLE_LIMIT,
+    };
+    truncate_title(raw, max)
+}
+
+/// Shorten a title to at most `max` characters, ending it with `…`
+pub fn truncate_title(title: &str, max: usize) -> String {
+    if title.chars().count() <= max {
+        return title.to_string();
+    }
+
+    let kept: String = title.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", kept.trim_end())
+}
+
+/// Close requests sent to the backend at once by `close_issues`
+pub const CLOSE_BATCH_SIZE: usize = 10;
+
+/// Close issues in concurrent batches, returning each issue's outcome in order
+pub async fn close_issues<B: Backend + ?Sized>(backend: &B, numbers: &[u64]) -> Vec<(u64, Result<Issue>)> {
+    let mut results = Vec::with_capacity(numbers.len());
+
+    for batch in numbers.chunks(CLOSE_BATCH_SIZE) {
+        let closed = join_all(batch.iter().map(|n| backend.close_issue(*n))).await;
+        results.extend(batch.iter().copied().zip(closed));
+    }
+
+    results
+}
+
+/// Turn the `[wip:#N]` markers of closed issues back into `[#N]`
+///
+/// A closed issue is no longer being worked on; the next sync then drops its
+/// WIP label and `in_progress` flag.
+pub fn clear_wip_markers(content: &str, closed: &[u64], config: &ProjectConfig) -> String {
+    let markers: Vec<(String, String)> = closed
+        .iter()
+        .map(|num| {
+            let wip = format_marker(&TaskStatus::InProgress(*num), config);
+            (format!("{} -", wip), format!("{} -", format_marker(&TaskStatus::Existing(*num), config)))
+        })
+        .collect();
+
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let Some((bullet, rest)) = split_list_marker(line) else {
+                return line.to_string();
+            };
+            match markers.iter().find(|(wip, _)| rest.starts_with(wip.as_str())) {
+                Some((wip, open)) => format!("{} {}{}", bullet, open, &rest[wip.len()..]),
+                None => line.to_string(),
             }
-        }
+        })
+        .collect()
+}
 
-        println!("\nTotal: {} tasks processed",
-            self.created.len() + self.updated.len() + self.skipped.len() + self.errors.len());
+/// Front matter keys to change so a task file matches an issue's metadata block
+///
+/// Only `type` and `tags` are pulled, plus `issue_id` if the file has none.
+/// A type that is the first tag of a file with no `type` isn't written back,
+/// since `tag_as_type` may have put it there, whether or not it's still set.
+/// Values are formatted as JSON, which YAML reads as quoted scalars and flow
+/// sequences.
+pub fn meta_updates(config: &TaskFileConfig, meta: &IssueMeta) -> Vec<(&'static str, String)> {
+    let mut updates = Vec::new();
+
+    if meta.task_type.is_some() && meta.task_type.as_deref() != config.effective_type(true) {
+        updates.push(("type", serde_json::to_string(&meta.task_type).unwrap_or_default()));
+    }
+    if meta.tags.is_some() && meta.tags != config.tags {
+        updates.push(("tags", serde_json::to_string(&meta.tags).unwrap_or_default()));
+    }
+    if let (Some(issue_id), None) = (meta.issue_id, config.issue_id) {
+        updates.push(("issue_id", issue_id.to_string()));
+    }
+
+    updates
+}
+
+/// Options controlling how a sync is performed
+#[derive(Debug, Clone, Default)]
+pub struct SyncOptions {
+    /// Truncate titles longer than `max_title_len` instead of pushing them as-is
+    pub truncate_titles: bool,
+    /// Before creating, search for an issue carrying the task's idempotency key
+    pub idempotent: bool,
+    /// Only sync tasks whose files changed relative to this git ref
+    pub changed_since: Option<String>,
+    /// Queue creates and updates in `QUEUE_FILE` for `flush` instead of sending them
+    pub offline: bool,
+    /// Refuse to sync if project.md or a task file has uncommitted changes
+    pub require_clean: bool,
+    /// Before syncing, warn about assignees who aren't collaborators on the repo
+    pub validate_assignees: bool,
+    /// Issues `pull` fetches at once (defaults to `DEFAULT_CONCURRENCY`)
+    pub concurrency: Option<usize>,
+    /// Assign each task's CODEOWNERS owners, merged with its other assignees
+    pub codeowners: Option<CodeOwners>,
+    /// Before syncing, check that the repo exists and the token can write to it
+    pub repo_check: bool,
+    /// Only sync tasks whose files have this tag
+    pub tag: Option<String>,
+    /// Label map file to use instead of the project's `label_map_file`
+    pub label_map_file: Option<PathBuf>,
+    /// Record where transferred issues went in their task files instead of
+    /// failing
+    pub follow_transfers: bool,
+    /// Print why each task was created, updated or skipped after syncing
+    pub explain: bool,
+    /// Times to re-run the whole sync while any task fails
+    pub retry_run: usize,
+    /// Print only `SyncResult::summary_line` instead of the full summary
+    pub summary_only: bool,
+    /// Where a dry run writes its plan as a markdown report
+    pub plan_md: Option<PathBuf>,
+    /// Where a dry run writes each task's rendered issue, as `<path>.issue.md`
+    pub render_to: Option<PathBuf>,
+    /// Set `created_at` to now in each task file synced, unless it sets
+    /// `freeze_created_at`
+    pub reset_timestamps: bool,
+}
+
+/// Issues fetched at once when no concurrency is configured
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Requests to have in flight at once, out of at most `max`, given the quota
+/// left as of the latest response
+///
+/// Concurrency shrinks in proportion to the quota left, down to one request
+/// at a time, and goes back to `max` once the quota resets.
+pub fn adaptive_concurrency(max: usize, latest: Option<&RateLimit>, now: DateTime<Utc>) -> usize {
+    let max = max.max(1);
+    match latest {
+        Some(rate) if now < rate.reset && rate.limit > 0 => {
+            let scaled = (max as u64 * rate.remaining).div_ceil(rate.limit);
+            (scaled as usize).clamp(1, max)
+        }
+        _ => max,
+    }
+}
+
+/// Limits requests in flight to `adaptive_concurrency` of the backend's
+/// latest rate limit quota
+struct AdaptiveLimit {
+    max: usize,
+    state: Option<Arc<RateLimitState>>,
+    in_flight: Mutex<usize>,
+    released: Notify,
+}
+
+impl AdaptiveLimit {
+    fn new(max: usize, state: Option<Arc<RateLimitState>>) -> Self {
+        Self { max, state, in_flight: Mutex::new(0), released: Notify::new() }
+    }
+
+    /// Wait until another request may start, and count it as in flight
+    async fn acquire(&self) {
+        loop {
+            // Registered before checking, so a release in between isn't missed
+            let released = self.released.notified();
+            {
+                let latest = self.state.as_ref().and_then(|s| s.latest());
+                let target = adaptive_concurrency(self.max, latest.as_ref(), Utc::now());
+                let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+                if *in_flight < target {
+                    *in_flight += 1;
+                    return;
+                }
+            }
+            released.await;
+        }
+    }
+
+    fn release(&self) {
+        *self.in_flight.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+        self.released.notify_waiters();
+    }
+}
+
+/// Wait before the first retry of a failed task, doubled for each retry after
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Sync engine for managing project tasks and backend issues
+pub struct SyncEngine<B: Backend> {
+    backend: B,
+    project_root: PathBuf,
+    options: SyncOptions,
+    /// Task file contents already read while filtering, taken when the task syncs
+    contents: Mutex<HashMap<PathBuf, String>>,
+    /// Time written to `created_at` and `updated_at`
+    clock: Box<dyn Clock>,
+    /// Called with each task's outcome as it completes
+    on_event: Option<Box<dyn Fn(SyncEvent) + Send + Sync>>,
+    /// Tasks whose create failed in a way that may have opened the issue, which
+    /// `retry_run` doesn't re-run
+    ambiguous_creates: Mutex<HashSet<String>>,
+}
+
+impl<B: Backend> SyncEngine<B> {
+    pub fn new(backend: B, project_root: PathBuf) -> Self {
+        Self {
+            backend,
+            project_root,
+            options: SyncOptions::default(),
+            contents: Mutex::new(HashMap::new()),
+            clock: Box::new(SystemClock),
+            on_event: None,
+            ambiguous_creates: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn with_options(mut self, options: SyncOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Report each task's outcome to `on_event` as it completes, e.g. to show
+    /// progress; the outcomes are still collected in the `SyncResult`
+    pub fn with_events(mut self, on_event: impl Fn(SyncEvent) + Send + Sync + 'static) -> Self {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    /// Record a task's outcome, reporting it first
+    fn record(&self, result: &mut SyncResult, event: SyncEvent) {
+        if let Some(on_event) = &self.on_event {
+            on_event(event.clone());
+        }
+        result.record(event);
+    }
+
+    /// Sync all tasks in the project file with the backend
+    ///
+    /// With `retry_run`, the whole sync runs again while tasks fail. Tasks
+    /// synced by an earlier attempt are skipped as unchanged, so only the
+    /// failures are retried. Creates that failed with a server or network
+    /// error may have opened the issue anyway, so unless `idempotent` can find
+    /// it they fail again without being re-sent.
+    pub async fn sync(&self, project_file: &Path) -> Result<SyncResult> {
+        self.ambiguous_creates.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        let (mut result, mut config) = self.sync_once(project_file).await?;
+
+        for attempt in 1..=self.options.retry_run {
+            let ambiguous = self.ambiguous_creates.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            if result.errors.iter().all(|(task, _)| ambiguous.contains(&task.name())) {
+                break;
+            }
+            eprintln!(
+                "{} tasks failed; re-running sync (retry {} of {})",
+                result.errors.len(),
+                attempt,
+                self.options.retry_run
+            );
+            let (retry, retry_config) = self.sync_once(project_file).await?;
+            result = retry.after(result);
+            config = retry_config;
+        }
+
+        self.write_audit(&config, &result);
+        Ok(result)
+    }
+
+    /// One attempt of `sync`, returning the project config alongside the result
+    async fn sync_once(&self, project_file: &Path) -> Result<(SyncResult, ProjectConfig)> {
+        let content = read_utf8(project_file)
+            .context("Failed to read project file")?;
+
+        let mut project = parse_project_file(&content)?;
+        load_label_map(&mut project.config, &self.project_root, self.options.label_map_file.as_deref())?;
+
+        check_task_errors(&project)?;
+        check_write_permissions(project_file, &self.project_root, &project)?;
+        check_duplicate_issue_ids(&self.project_root, &project)?;
+        if self.options.require_clean {
+            check_clean(project_file, &self.project_root, &project)?;
+        }
+        // Offline syncs only write the queue
+        if self.options.repo_check && !self.options.offline {
+            let access = self.backend.verify_repo().await?;
+            check_repo_access(access, &project.config.repo)?;
+        }
+        if self.options.validate_assignees {
+            match self.unknown_assignees(&project).await {
+                Ok(unknown) => {
+                    for (task, login) in unknown {
+                        eprintln!("Warning: {} is assigned to '{}', who is not a collaborator on {}", task.name(), login, project.config.repo);
+                    }
+                }
+                Err(e) => eprintln!("Warning: failed to validate assignees: {:#}", e),
+            }
+        }
+
+        let mut result = SyncResult::default();
+        let mut queue = match self.options.offline {
+            true => Some(Queue::load(&self.project_root)?),
+            false => None,
+        };
+
+        let epics = self.read_epic_refs(&project.tasks, &project.config);
+
+        let changed = match &self.options.changed_since {
+            Some(git_ref) => Some(git::changed_files(&self.project_root, git_ref)?),
+            None => None,
+        };
+
+        // Tasks left out by `--changed-since` or `--tag` aren't closed either
+        let mut processed = Vec::new();
+
+        // One task at a time, so new issues are numbered in sync order on every run
+        for task_item in sync_order(&project.tasks, &epics) {
+            if let Some(changed) = &changed {
+                // Description-only tasks live in project.md itself
+                let task_file = match &task_item.path {
+                    Some(path) => self.project_root.join(path),
+                    None => project_file.to_path_buf(),
+                };
+                if !git::is_changed(changed, &task_file) {
+                    result.reasons.push((task_item.clone(), SyncReason::NotChangedSince(self.options.changed_since.clone().unwrap_or_default())));
+                    self.record(&mut result, SyncEvent::Skipped(task_item.clone()));
+                    continue;
+                }
+            }
+            if let Some(tag) = &self.options.tag {
+                if !self.has_tag(task_item, tag, &project.config) {
+                    result.reasons.push((task_item.clone(), SyncReason::NotTagged(tag.clone())));
+                    self.record(&mut result, SyncEvent::Skipped(task_item.clone()));
+                    continue;
+                }
+            }
+
+            processed.push(task_item);
+            let event = match self.sync_with_policy(task_item, &project.config).await {
+                Ok(action) => {
+                    let (event, reason) = match action {
+                        SyncAction::Created(issue_num) => (SyncEvent::Created(task_item.clone(), issue_num), SyncReason::New),
+                        SyncAction::Updated(issue_num, reason) => (SyncEvent::Updated(task_item.clone(), issue_num), reason),
+                        SyncAction::Skipped(reason) => (SyncEvent::Skipped(task_item.clone()), reason),
+                        SyncAction::Queued(op) => {
+                            if let Some(queue) = &mut queue {
+                                queue.push(*op);
+                            }
+                            (SyncEvent::Queued(task_item.clone()), SyncReason::Offline)
+                        }
+                    };
+                    result.reasons.push((task_item.clone(), reason));
+                    event
+                }
+                Err(e) => SyncEvent::Failed(task_item.clone(), format!("{:?}", e)),
+            };
+            self.record(&mut result, event);
+        }
+
+        // Local files are left alone until the queue is flushed
+        if let Some(queue) = queue {
+            queue.save(&self.project_root)?;
+            return Ok((result, project.config));
+        }
+
+        // Update project.md with new issue numbers
+        if !result.created.is_empty() {
+            self.update_project_file(project_file, &content, &result.created, &project.config)?;
+        }
+
+        self.link_epics(&project.tasks, &epics, &project.config, &mut result).await;
+        let (completed, duplicates) = close_candidates(&self.project_root, processed, &project.config);
+        self.close_duplicates(project_file, &duplicates, &project.config, &mut result).await?;
+        self.close_completed(&completed, &mut result).await;
+
+        Ok((result, project.config))
+    }
+
+    /// Record what a sync did in the audit file, warning if it can't be written
+    fn write_audit(&self, config: &ProjectConfig, result: &SyncResult) {
+        let path = self.project_root.join(config.audit_file.as_deref().unwrap_or(Path::new(AUDIT_FILE)));
+        let audit = SyncAudit::new(result, self.clock.now(), git::head_sha(&self.project_root).ok());
+        if let Err(e) = audit.save(&path) {
+            eprintln!("Warning: failed to write the sync audit record: {:#}", e);
+        }
+    }
+
+    /// Apply the operations queued by offline syncs, in the order they were queued
+    ///
+    /// Issue numbers and timestamps are written back as each operation is
+    /// applied. Failed operations stay queued for the next flush.
+    pub async fn flush(&self, project_file: &Path) -> Result<SyncResult> {
+        let content = read_utf8(project_file)
+            .context("Failed to read project file")?;
+
+        let mut project = parse_project_file(&content)?;
+        load_label_map(&mut project.config, &self.project_root, self.options.label_map_file.as_deref())?;
+        let mut queue = Queue::load(&self.project_root)?;
+        let mut result = SyncResult::default();
+        let mut failed = Vec::new();
+        // Issues opened by creates that then failed to be recorded locally
+        let mut opened = Vec::new();
+
+        for mut op in queue.ops.drain(..) {
+            let create = op.issue.is_none();
+            let event = match self.apply_queued(&mut op, &project.config).await {
+                Ok(SyncAction::Created(issue_num)) => SyncEvent::Created(op.task.clone(), issue_num),
+                Ok(SyncAction::Updated(issue_num, _)) => SyncEvent::Updated(op.task.clone(), issue_num),
+                Ok(_) => SyncEvent::Skipped(op.task.clone()),
+                Err(e) => {
+                    if let (true, Some(issue_num)) = (create, op.issue) {
+                        opened.push((op.task.clone(), issue_num));
+                    }
+                    let event = SyncEvent::Failed(op.task.clone(), format!("{:?}", e));
+                    failed.push(op);
+                    event
+                }
+            };
+            self.record(&mut result, event);
+        }
+
+        queue.ops = failed;
+        queue.save(&self.project_root)?;
+
+        let created: Vec<(TaskItem, u64)> = result.created.iter().cloned().chain(opened).collect();
+        if !created.is_empty() {
+            self.update_project_file(project_file, &content, &created, &project.config)?;
+        }
+
+        let epics = self.read_epic_refs(&project.tasks, &project.config);
+        self.link_epics(&project.tasks, &epics, &project.config, &mut result).await;
+
+        Ok(result)
+    }
+
+    /// Send a single queued operation and record its result locally
+    ///
+    /// Once a create is sent, `op` becomes an update of the issue it opened,
+    /// so that if recording it locally fails, requeuing `op` doesn't open a
+    /// second issue.
+    async fn apply_queued(&self, op: &mut QueuedOp, config: &ProjectConfig) -> Result<SyncAction> {
+        let create = op.issue.is_none();
+        let issue_num = match (op.issue, &op.task.path) {
+            (Some(issue_num), _) => Some(issue_num),
+            (None, Some(path)) if self.options.idempotent => {
+                self.backend.find_issue_by_key(&idempotency_key(path)).await?.map(|i| i.number)
+            }
+            (None, _) => None,
+        };
+        let issue = self.send_issue(issue_num, op.fields.clone(), config).await?;
+        op.issue = Some(issue.number);
+
+        if let Some(path) = &op.task.path {
+            let task_file_path = self.project_root.join(path);
+            let task_content = read_utf8_within(&task_file_path, config.max_task_file_bytes)
+                .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+            self.update_task_file_with_metadata(
+                &task_file_path,
+                &task_content,
+                issue.number,
+                &op.task,
+                op.in_progress,
+                config,
+            )?;
+        } else {
+            self.record_synced_description(issue.number, &op.task);
+        }
+
+        if let Some(marker) = &op.board_marker {
+            self.move_on_board(&issue, config, marker).await;
+        }
+
+        Ok(if create {
+            SyncAction::Created(issue.number)
+        } else {
+            SyncAction::Updated(issue.number, SyncReason::Offline)
+        })
+    }
+
+    /// Update local front matter from the metadata blocks of the tasks' issues
+    ///
+    /// Task bodies are left alone; tasks without an issue are not pulled.
+    pub async fn pull(&self, project_file: &Path) -> Result<SyncResult> {
+        self.pull_since(project_file, None).await
+    }
+
+    /// `pull`, but when given `etags` (issue number to the ETag it was last
+    /// pulled at) skip issues unchanged since, and record the new ETags
+    async fn pull_since(&self, project_file: &Path, etags: Option<&Mutex<HashMap<u64, String>>>) -> Result<SyncResult> {
+        let content = read_utf8(project_file)
+            .context("Failed to read project file")?;
+
+        let project = parse_project_file(&content)?;
+        let mut result = SyncResult::default();
+
+        let tracked: Vec<(&TaskItem, &Path, u64)> = project.tasks
+            .iter()
+            .filter_map(|t| Some((t, t.path.as_deref()?, t.status.issue_id()?)))
+            .collect();
+
+        // Fetch concurrently, but write task files one at a time afterwards
+        // since two tasks may share a file. Fewer requests are made at once as
+        // the backend's rate limit quota runs low.
+        let concurrency = self.options.concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1);
+        let limit = &AdaptiveLimit::new(concurrency, self.backend.rate_limit_state());
+        let mut fetched: Vec<(usize, Result<_>)> = stream::iter(tracked.iter().enumerate())
+            .map(|(i, (_, _, issue_num))| async move {
+                limit.acquire().await;
+                let issue = match etags {
+                    Some(etags) => {
+                        let etag = etags.lock().unwrap_or_else(|e| e.into_inner()).get(issue_num).cloned();
+                        self.backend.get_issue_if_changed(*issue_num, etag.as_deref()).await
+                    }
+                    None => self.backend.get_issue(*issue_num).await.map(|issue| Some((issue, None))),
+                };
+                limit.release();
+                (i, issue)
+            })
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        fetched.sort_by_key(|(i, _)| *i);
+
+        for (i, issue) in fetched {
+            let (task_item, path, issue_num) = tracked[i];
+
+            let pulled = issue.and_then(|issue| match issue {
+                Some((issue, etag)) => self.pull_task_item(path, &issue, &project.config).map(|changed| (changed, etag)),
+                None => Ok((false, None)),
+            });
+            let event = match pulled {
+                Ok((changed, etag)) => {
+                    // Only once pulled, so a failed pull is retried next time
+                    if let (Some(etags), Some(etag)) = (etags, etag) {
+                        etags.lock().unwrap_or_else(|e| e.into_inner()).insert(issue_num, etag);
+                    }
+                    if changed {
+                        SyncEvent::Updated(task_item.clone(), issue_num)
+                    } else {
+                        SyncEvent::Skipped(task_item.clone())
+                    }
+                }
+                Err(e) => SyncEvent::Failed(task_item.clone(), format!("{:?}", e)),
+            };
+            self.record(&mut result, event);
+        }
+
+        Ok(result)
+    }
+
+    /// Pull every `interval`, starting right away, until `stop` completes
+    ///
+    /// Each cycle's result, or why it failed, goes to `report`; a failed cycle
+    /// doesn't end the watch. Stopping interrupts a cycle under way between
+    /// task file writes. After the first cycle, issues are fetched
+    /// conditionally on their ETags, so unchanged ones are skipped and, on
+    /// GitHub, cost no rate limit quota.
+    pub async fn watch_remote(
+        &self,
+        project_file: &Path,
+        interval: Duration,
+        stop: impl std::future::Future<Output = ()>,
+        mut report: impl FnMut(Result<SyncResult>),
+    ) {
+        let etags = Mutex::new(HashMap::new());
+        tokio::pin!(stop);
+        loop {
+            tokio::select! {
+                _ = &mut stop => return,
+                result = self.pull_since(project_file, Some(&etags)) => report(result),
+            }
+            tokio::select! {
+                _ = &mut stop => return,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+    }
+
+    /// Pull a single task's metadata from its fetched issue, returning whether
+    /// its file changed
+    fn pull_task_item(&self, path: &Path, issue: &Issue, config: &ProjectConfig) -> Result<bool> {
+        let issue_num = issue.number;
+
+        // Issues created by hand or before metadata blocks existed
+        let Some(meta) = split_meta(&issue.body).0 else {
+            return Ok(false);
+        };
+        if !meta.is_for(path) {
+            anyhow::bail!("Issue #{} belongs to {}, not this task", issue_num, meta.path);
+        }
+
+        let task_file_path = self.project_root.join(path);
+        let task_content = read_utf8_within(&task_file_path, config.max_task_file_bytes)
+            .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+        let task_file = parse_task_file_with(&task_content, config.front_matter)?;
+
+        let updates = meta_updates(&task_file.config, &meta);
+        if updates.is_empty() {
+            return Ok(false);
+        }
+
+        fs::write(&task_file_path, frontmatter::update_keys(&task_content, &updates)?)
+            .context("Failed to write updated task file")?;
+
+        Ok(true)
+    }
+
+    /// Assignees of each task that aren't collaborators on the repo
+    ///
+    /// GitHub silently drops such assignees, so a typo'd login would otherwise
+    /// go unnoticed. Logins are compared case-insensitively, as on GitHub.
+    pub async fn unknown_assignees(&self, project: &ProjectMd) -> Result<Vec<(TaskItem, String)>> {
+        let Some(collaborators) = self.backend.list_collaborators().await? else {
+            return Ok(Vec::new());
+        };
+        let collaborators: HashSet<String> = collaborators.iter().map(|c| c.to_lowercase()).collect();
+
+        let mut unknown = Vec::new();
+        for task_item in &project.tasks {
+            // Unreadable files are reported when the task itself is synced
+            let Some(path) = &task_item.path else { continue };
+            let Ok(content) = read_utf8_within(self.project_root.join(path), project.config.max_task_file_bytes) else { continue };
+            let Ok(task_file) = parse_task_file_with(&content, project.config.front_matter) else { continue };
+
+            for login in resolve_assignees(&task_file.config, &project.config, &code_owners(task_item, &self.options)) {
+                if !collaborators.contains(&login.to_lowercase()) {
+                    unknown.push((task_item.clone(), login));
+                }
+            }
+        }
+
+        Ok(unknown)
+    }
+
+    /// Read the `epic` reference of every task that has one
+    fn read_epic_refs(&self, tasks: &[TaskItem], config: &ProjectConfig) -> HashMap<PathBuf, EpicRef> {
+        tasks
+            .iter()
+            .filter_map(|task| {
+                let path = task.path.as_ref()?;
+                // Unreadable files are reported when the task itself is synced
+                let content = read_utf8_within(self.project_root.join(path), config.max_task_file_bytes).ok()?;
+                let task_file = parse_task_file(&content).ok()?;
+                let epic = task_file.config.epic.as_deref().map(EpicRef::parse)?;
+                Some((path.clone(), epic))
+            })
+            .collect()
+    }
+
+    /// Link child issues to their epic issues, as sub-issues with `sub_issues`
+    /// or else as task lists in the epic body
+    ///
+    /// Runs after all tasks are synced so new epics and children have numbers.
+    /// An epic is only touched when it or one of its children changed this run.
+    async fn link_epics(&self, tasks: &[TaskItem], epics: &HashMap<PathBuf, EpicRef>, config: &ProjectConfig, result: &mut SyncResult) {
+        let mut numbers: HashMap<&Path, u64> = tasks
+            .iter()
+            .filter_map(|t| Some((t.path.as_deref()?, t.status.issue_id()?)))
+            .collect();
+        for (task, issue_num) in &result.created {
+            if let Some(path) = &task.path {
+                numbers.insert(path.as_path(), *issue_num);
+            }
+        }
+
+        let created: HashSet<u64> = result.created.iter().map(|(_, n)| *n).collect();
+        let updated: HashSet<u64> = result.updated.iter().map(|(_, n)| *n).collect();
+
+        let mut children: BTreeMap<u64, (&TaskItem, Vec<u64>)> = BTreeMap::new();
+        for task in tasks {
+            let Some(path) = &task.path else { continue };
+            let Some(epic) = epics.get(path) else { continue };
+            let Some(&child) = numbers.get(path.as_path()) else { continue };
+
+            let (epic_num, epic_task) = match epic {
+                EpicRef::Issue(n) => (Some(*n), Some(task)),
+                EpicRef::Task(epic_path) => (
+                    numbers.get(epic_path.as_path()).copied(),
+                    tasks.iter().find(|t| t.path.as_ref() == Some(epic_path)),
+                ),
+            };
+            // The epic failed to sync or isn't listed in project.md
+            let (Some(epic_num), Some(epic_task)) = (epic_num, epic_task) else { continue };
+
+            children
+                .entry(epic_num)
+                .or_insert_with(|| (epic_task, Vec::new()))
+                .1
+                .push(child);
+        }
+
+        for (epic_num, (epic_task, child_nums)) in children {
+            let epic_changed = created.contains(&epic_num) || updated.contains(&epic_num);
+            if !epic_changed && !child_nums.iter().any(|n| created.contains(n)) {
+                continue;
+            }
+
+            if config.sub_issues {
+                match self.add_sub_issues(epic_num, &child_nums).await {
+                    Ok(()) => continue,
+                    Err(e) => eprintln!("Warning: Listing children in epic #{} instead of adding sub-issues: {:#}", epic_num, e),
+                }
+            }
+
+            if let Err(e) = self.append_children_to_epic(epic_num, &child_nums).await {
+                self.record(result, SyncEvent::Failed(epic_task.clone(), format!("{:?}", e)));
+            }
+        }
+    }
+
+    /// Close the open issues of tasks whose lines are checked off with ` ✅` or
+    /// ` [x]`
+    ///
+    /// Runs after all tasks are synced, so a task created this run is closed
+    /// as soon as it has an issue. Tasks that failed to sync are left alone.
+    async fn close_completed(&self, tasks: &[&TaskItem], result: &mut SyncResult) {
+        let tasks = closable(tasks.iter().map(|&task| (task, ())), result);
+        let states = self.open_states(&tasks).await;
+        for ((task, num, ()), open) in tasks.into_iter().zip(states) {
+            let closed = match open {
+                Ok(false) => continue,
+                Ok(true) => self.backend.close_issue(num).await,
+                Err(e) => Err(e),
+            };
+            match closed {
+                Ok(_) => result.closed.push((task.clone(), num)),
+                Err(e) => result.errors.push((task.clone(), format!("Failed to close completed task's issue #{}: {:#}", num, e))),
+            }
+        }
+    }
+
+    /// Close the issues of tasks whose files set `duplicate_of` as not planned,
+    /// commenting `Duplicate of #N` so GitHub links the two, and check their
+    /// lines off in project.md
+    ///
+    /// Issues that are already closed are only checked off. Tasks that failed
+    /// to sync are left alone.
+    async fn close_duplicates(&self, project_file: &Path, tasks: &[(&TaskItem, u64)], config: &ProjectConfig, result: &mut SyncResult) -> Result<()> {
+        let mut check_off_tasks = Vec::new();
+        let tasks = closable(tasks.iter().copied(), result);
+        let states = self.open_states(&tasks).await;
+        for ((task, num, original), open) in tasks.into_iter().zip(states) {
+            let closed = match open {
+                Ok(false) => Ok(false),
+                Ok(true) => self.close_as_duplicate(num, original).await.map(|()| true),
+                Err(e) => Err(e),
+            };
+            match closed {
+                Ok(closed) => {
+                    if closed {
+                        result.duplicates.push((task.clone(), num, original));
+                    }
+                    if !task.completed {
+                        // The line carries the new number of a task created this run
+                        let status = match task.status {
+                            TaskStatus::InProgress(_) => TaskStatus::InProgress(num),
+                            _ => TaskStatus::Existing(num),
+                        };
+                        check_off_tasks.push(TaskItem { status, ..task.clone() });
+                    }
+                }
+                Err(e) => result.errors.push((task.clone(), format!("Failed to close duplicate task's issue #{}: {:#}", num, e))),
+            }
+        }
+
+        if check_off_tasks.is_empty() {
+            return Ok(());
+        }
+        let content = read_utf8(project_file).context("Failed to read project file")?;
+        let style = NewlineStyle::detect(&content).with_line_ending(config.line_ending).with_final_newline(config.final_newline);
+        let updated = check_off_tasks.iter().fold(to_lf(&content), |content, task| check_off(&content, task, config));
+        fs::write(project_file, style.apply(&updated)).context("Failed to write updated project file")
+    }
+
+    /// Closed before commenting, so a failed close can't leave a comment
+    /// behind to be posted again on the next sync
+    async fn close_as_duplicate(&self, num: u64, original: u64) -> Result<()> {
+        self.backend.close_issue_with_reason(num, CloseReason::NotPlanned).await?;
+        self.backend
+            .add_comment(num, &format!("Duplicate of #{}", original))
+            .await
+            .context("Closed, but failed to comment")?;
+        Ok(())
+    }
+
+    /// Whether the issue of each of `tasks` is open, fetched in one batch
+    async fn open_states<T>(&self, tasks: &[(&TaskItem, u64, T)]) -> Vec<Result<bool>> {
+        if tasks.is_empty() {
+            return Vec::new();
+        }
+        let nums: Vec<u64> = tasks.iter().map(|(_, num, _)| *num).collect();
+        let issues = self.backend.get_issues_batch(&nums).await;
+        nums.iter()
+            .map(|num| match &issues {
+                Ok(issues) => issues.get(num).map(|issue| issue.state != "closed").with_context(|| format!("Issue #{} not found", num)),
+                Err(e) => Err(anyhow::anyhow!("{:#}", e)),
+            })
+            .collect()
+    }
+
+    /// Make each child a sub-issue of the epic
+    async fn add_sub_issues(&self, epic_num: u64, child_nums: &[u64]) -> Result<()> {
+        for &child in child_nums {
+            self.backend.add_sub_issue(epic_num, child).await?;
+        }
+        Ok(())
+    }
+
+    /// Add any missing child references to an epic's issue body
+    async fn append_children_to_epic(&self, epic_num: u64, child_nums: &[u64]) -> Result<()> {
+        let epic = self.backend.get_issue(epic_num).await?;
+
+        // Keep the managed footer at the end of the body
+        let (content, footer) = split_footer(&epic.body);
+        if let Some(content) = append_child_task_list(content, child_nums) {
+            let body = match footer {
+                Some(footer) => format!("{}\n\n{}", content, footer),
+                None => content,
+            };
+
+            self.backend
+                .update_issue(epic_num, &epic.title, &body, epic.labels, Vec::new())
+                .await
+                .with_context(|| format!("Failed to link children to epic #{}", epic_num))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sync a task, handling failures as the `error_policy` says for their kind
+    async fn sync_with_policy(&self, task_item: &TaskItem, config: &ProjectConfig) -> Result<SyncAction> {
+        let policy = &config.error_policy;
+        let mut retries = 0;
+
+        if self.ambiguous_creates.lock().unwrap_or_else(|e| e.into_inner()).contains(&task_item.name()) {
+            anyhow::bail!(
+                "Not re-run, since an earlier attempt's create may have opened the issue anyway; with --idempotent, creates are re-run without duplicating it"
+            );
+        }
+
+        loop {
+            let error = match self.sync_task_item(task_item, config).await {
+                Ok(action) => return Ok(action),
+                Err(e) => e,
+            };
+
+            let kind = classify(&error);
+            if self.is_ambiguous_create(task_item, kind) {
+                self.ambiguous_creates.lock().unwrap_or_else(|e| e.into_inner()).insert(task_item.name());
+            }
+
+            match policy.action(kind) {
+                ErrorAction::Retry if self.is_ambiguous_create(task_item, kind) => {
+                    return Err(error.context(
+                        "Not retried, since the issue may have been created anyway; with --idempotent, creates are retried without duplicating it",
+                    ));
+                }
+                ErrorAction::Retry if retries < policy.max_retries() => {
+                    let delay = RETRY_DELAY * 2u32.pow(retries);
+                    eprintln!("Warning: failed to sync {}, retrying in {}s: {:#}", task_item.name(), delay.as_secs(), error);
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                ErrorAction::Skip => {
+                    eprintln!("Warning: skipping {}: {:#}", task_item.name(), error);
+                    return Ok(SyncAction::Skipped(SyncReason::ErrorSkipped(kind)));
+                }
+                // The task is recorded as created, so project.md gets the new number
+                ErrorAction::Recreate if kind == ErrorKind::NotFound && !task_item.status.is_new() => {
+                    eprintln!("Warning: issue for {} no longer exists, creating a new one", task_item.name());
+                    let recreated = TaskItem { status: TaskStatus::New, ..task_item.clone() };
+                    return self.sync_task_item(&recreated, config).await;
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Whether a task's create failed in a way that may still have opened its
+    /// issue (the request timed out, or the server failed after receiving it)
+    ///
+    /// Retrying would then open a second issue, unless `idempotent` has the
+    /// retry look for the first one. Description-only tasks have no key to
+    /// look for.
+    fn is_ambiguous_create(&self, task_item: &TaskItem, kind: ErrorKind) -> bool {
+        task_item.status.is_new()
+            && matches!(kind, ErrorKind::Server | ErrorKind::Network)
+            && !(self.options.idempotent && task_item.path.is_some())
+    }
+
+    /// Record a transferred issue's new home in the task's file, or fail
+    /// without `follow_transfers`
+    ///
+    /// Tasks can only be synced to the project's repo, so a followed task is
+    /// skipped from then on (see `followed_transfer`).
+    fn follow_transfer(&self, task_item: &TaskItem, issue_num: u64, transfer: &Transfer, config: &ProjectConfig) -> Result<SyncAction> {
+        let moved = format!("Issue #{} appears to have been transferred to {}#{}", issue_num, transfer.repo, transfer.number);
+        let Some(path) = task_item.path.as_ref().filter(|_| self.options.follow_transfers) else {
+            anyhow::bail!("{} ({}); rerun with --follow-transfers to record it in the task file", moved, transfer.url);
+        };
+
+        let task_file_path = self.project_root.join(path);
+        let content = read_utf8_within(&task_file_path, config.max_task_file_bytes)
+            .with_context(|| format!("Failed to read task file: {:?}", task_file_path))?;
+        let updates = [("repo", transfer.repo.clone()), ("issue_id", transfer.number.to_string())];
+        fs::write(&task_file_path, frontmatter::update_keys(&content, &updates)?)
+            .context("Failed to write updated task file")?;
+
+        eprintln!("Warning: {}; recorded it in {}", moved, path.display());
+        Ok(SyncAction::Skipped(SyncReason::Transferred))
+    }
+
+    /// Whether a task's file is tagged `tag`
+    ///
+    /// Tasks whose file can't be read or parsed are kept so that syncing them
+    /// reports why. The content is kept for the sync to reuse.
+    fn has_tag(&self, task_item: &TaskItem, tag: &str, config: &ProjectConfig) -> bool {
+        let Some(path) = &task_item.path else { return false };
+        let task_file_path = self.project_root.join(path);
+        let Ok(content) = read_utf8_within(&task_file_path, config.max_task_file_bytes) else { return true };
+
+        let tagged = is_tagged(&content, tag, config.front_matter);
+        if tagged {
+            self.contents.lock().unwrap_or_else(|e| e.into_inner()).insert(task_file_path, content);
+        }
+        tagged
+    }
+
+    /// Sync a single task item
+    async fn sync_task_item(&self, task_item: &TaskItem, config: &ProjectConfig) -> Result<SyncAction> {
+        let Some(path) = &task_item.path else {
+            return self.sync_description_task(task_item, config).await;
+        };
+        let task_file_path = self.project_root.join(path);
+
+        // Read and parse the task file, unless filtering already read it
+        let cached = self.contents.lock().unwrap_or_else(|e| e.into_inner()).remove(&task_file_path);
+        let task_content = match cached.map_or_else(|| read_utf8_within(&task_file_path, config.max_task_file_bytes), Ok) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && config.on_missing_file != MissingFilePolicy::Error => {
+                if config.on_missing_file == MissingFilePolicy::Skip {
+                    eprintln!("Warning: skipping {}: task file doesn't exist", task_item.name());
+                    return Ok(SyncAction::Skipped(SyncReason::MissingFile));
+                }
+
+                let stub = stub_task_file(task_item);
+                if let Some(dir) = task_file_path.parent() {
+                    fs::create_dir_all(dir).context("Failed to create task directory")?;
+                }
+                fs::write(&task_file_path, &stub)
+                    .with_context(|| format!("Failed to write task file: {:?}", task_file_path))?;
+                eprintln!("Created task file {}", path.display());
+                stub
+            }
+            result => result.with_context(|| format!("Failed to read task file: {:?}", task_file_path))?,
+        };
+
+        let mut task_file = parse_task_file_with(&task_content, config.front_matter)?;
+        if followed_transfer(task_item, &task_file.config, config) {
+            return Ok(SyncAction::Skipped(SyncReason::Transferred));
+        }
+        check_task_repo(&task_file.config, config)?;
+        check_required_fields(&task_file.config, config)?;
+
+        let was_in_progress = task_file.config.in_progress == Some(true);
+        let marker_changed = marker_changed(task_item, &task_file.config);
+
+        // Check if we need to sync this task (only for existing issues)
+        let reason = match task_item.status.is_new() {
+            true => SyncReason::New,
+            false if marker_changed => SyncReason::MarkerChanged,
+            false => match file_change(&task_file_path, &task_file.config)? {
+                SyncReason::Unchanged if description_changed(task_item, &task_file.config, config) => SyncReason::DescriptionChanged,
+                reason => reason,
+            },
+        };
+        if reason == SyncReason::Unchanged {
+            return Ok(SyncAction::Skipped(reason));
+        }
+
+        // Requests for a transferred issue are redirected to its new home, so
+        // look before writing over it there
+        if let (Some(issue_num), false) = (task_item.status.issue_id(), self.options.offline) {
+            let issue = self.backend.get_issue(issue_num).await?;
+            if let Some(transfer) = self.backend.transfer_of(&issue) {
+                return self.follow_transfer(task_item, issue_num, &transfer, config);
+            }
+        }
+
+        // Only the title depends on the description, so the unchanged body
+        // isn't rendered and pushed again; offline syncs queue a full update
+        if let (SyncReason::DescriptionChanged, Some(issue_num), false) = (&reason, task_item.status.issue_id(), self.options.offline) {
+            let title = prepare_title(config, issue_title(task_item, &task_file, config), self.options.truncate_titles);
+            self.backend.update_title(issue_num, &title).await?;
+            self.update_task_file_with_metadata(&task_file_path, &task_content, issue_num, task_item, task_file.config.in_progress, config)?;
+            return Ok(SyncAction::Updated(issue_num, reason));
+        }
+
+        // Only record the flag for tasks that have ever been in progress
+        let in_progress = (task_item.status.is_in_progress() || was_in_progress)
+            .then_some(task_item.status.is_in_progress());
+
+        task_file.body = preprocessor_for(config, &self.project_root).process(Some(path), &task_file.body)?;
+        task_file.body = rewrite_images(config, &self.project_root, path, task_file.body);
+
+        let fields = prepare_issue(task_item, &task_file, config, &self.options);
+
+        if self.options.offline {
+            let board_marker = (task_item.status.is_new() || marker_changed)
+                .then(|| board_marker(&task_item.status, task_item.status.is_new()).to_string());
+            return Ok(SyncAction::Queued(Box::new(QueuedOp {
+                task: task_item.clone(),
+                issue: task_item.status.issue_id(),
+                fields,
+                in_progress,
+                board_marker,
+            })));
+        }
+
+        match &task_item.status {
+            TaskStatus::New => {
+                // A previous run may have created the issue but failed to record it
+                let existing = if self.options.idempotent {
+                    self.backend.find_issue_by_key(&idempotency_key(path)).await?
+                } else {
+                    None
+                };
+
+                let issue = self.send_issue(existing.map(|i| i.number), fields, config).await?;
+
+                // Update the task file with the new issue ID and timestamps
+                self.update_task_file_with_metadata(&task_file_path, &task_content, issue.number, task_item, in_progress, config)?;
+
+                self.move_on_board(&issue, config, board_marker(&task_item.status, true)).await;
+
+                Ok(SyncAction::Created(issue.number))
+            }
+            TaskStatus::Existing(issue_num) | TaskStatus::InProgress(issue_num) => {
+                // Update the issue
+                let issue = self.send_issue(Some(*issue_num), fields, config).await?;
+
+                // Record issue_id (if it was missing or stale) and the new updated_at
+                self.update_task_file_with_metadata(&task_file_path, &task_content, *issue_num, task_item, in_progress, config)?;
+
+                if marker_changed {
+                    self.move_on_board(&issue, config, board_marker(&task_item.status, false)).await;
+                }
+
+                Ok(SyncAction::Updated(issue.number, reason))
+            }
+        }
+    }
+
+    /// Sync a description-only task, which has no task file to read or update
+    ///
+    /// There is no `updated_at` to compare against, so existing issues are
+    /// updated when their description isn't the one last synced.
+    async fn sync_description_task(&self, task_item: &TaskItem, config: &ProjectConfig) -> Result<SyncAction> {
+        if description_synced(&self.project_root, task_item)? {
+            return Ok(SyncAction::Skipped(SyncReason::DescriptionUnchanged));
+        }
+
+        let mut task_file = description_task_file(task_item);
+        task_file.body = preprocessor_for(config, &self.project_root).process(None, &task_file.body)?;
+
+        let fields = prepare_issue(task_item, &task_file, config, &self.options);
+
+        if self.options.offline {
+            return Ok(SyncAction::Queued(Box::new(QueuedOp {
+                task: task_item.clone(),
+                issue: task_item.status.issue_id(),
+                fields,
+                in_progress: None,
+                board_marker: task_item.status.is_new().then(|| "new".to_string()),
+            })));
+        }
+
+        match &task_item.status {
+            TaskStatus::New => {
+                let issue = self.send_issue(None, fields, config).await?;
+                self.record_synced_description(issue.number, task_item);
+
+                self.move_on_board(&issue, config, board_marker(&task_item.status, true)).await;
+
+                Ok(SyncAction::Created(issue.number))
+            }
+            TaskStatus::Existing(issue_num) | TaskStatus::InProgress(issue_num) => {
+                let issue = self.send_issue(Some(*issue_num), fields, config).await?;
+                self.record_synced_description(issue.number, task_item);
+
+                Ok(SyncAction::Updated(issue.number, SyncReason::DescriptionOnly))
+            }
+        }
+    }
+
+    /// Update issue `number` with the fields, or create an issue if there is none
+    async fn send_issue(&self, number: Option<u64>, fields: IssueFields, config: &ProjectConfig) -> Result<Issue> {
+        let IssueFields { title, body, mut labels, assignees, milestone, keep_labels } = fields;
+
+        if let (Some(number), true) = (number, keep_labels) {
+            let current = self.backend.get_issue(number).await?;
+            labels = merge_labels(&current.labels, labels, &config.state_labels());
+        }
+
+        // Without color rules, let the backend create labels as it uses them
+        if !config.label_color_rules.is_empty() {
+            for label in &labels {
+                let color = label_color(label, &config.label_color_rules);
+                if let Err(e) = self.backend.ensure_label(label, color).await {
+                    eprintln!("Warning: failed to create label '{}': {:#}", label, e);
+                }
+            }
+        }
+
+        let issue = match number {
+            Some(number) => self.backend
+                .update_issue(number, &title, &body, labels, assignees)
+                .await?,
+            None => {
+                let (assignees, next_turn) = match assignees.is_empty() {
+                    true => self.round_robin_turn(config)?,
+                    false => (assignees, None),
+                };
+                let issue = self.backend.create_issue(&title, &body, labels, assignees).await?;
+                // Only a created issue uses up a turn
+                if let Some(state) = next_turn {
+                    if let Err(e) = state.save(&self.project_root) {
+                        eprintln!("Warning: failed to save the round-robin position: {:#}", e);
+                    }
+                }
+                issue
+            }
+        };
+
+        // Like board moves, a failure only warns since the issue was synced
+        if let Some(milestone) = milestone {
+            if let Err(e) = self.backend.set_milestone(issue.number, &milestone).await {
+                eprintln!("Warning: failed to set milestone '{}' on issue #{}: {:#}", milestone, issue.number, e);
+            }
+        }
+
+        Ok(issue)
+    }
+
+    /// Record the description a description-only task's issue was synced from
+    ///
+    /// Like the round-robin position, a failure only warns: the issue was
+    /// synced, and the next sync just sends it again.
+    fn record_synced_description(&self, issue_num: u64, task_item: &TaskItem) {
+        let Some(description) = &task_item.description else { return };
+        let saved = SyncState::load(&self.project_root).and_then(|mut state| {
+            state.synced_descriptions.insert(issue_num, description.clone());
+            state.save(&self.project_root)
+        });
+        if let Err(e) = saved {
+            eprintln!("Warning: failed to record the synced description of issue #{}: {:#}", issue_num, e);
+        }
+    }
+
+    /// Whose turn it is to be assigned a new issue from `round_robin_assignees`,
+    /// and the state to save once the issue is created
+    fn round_robin_turn(&self, config: &ProjectConfig) -> Result<(Vec<String>, Option<SyncState>)> {
+        let team = config.round_robin_assignees.as_deref().unwrap_or_default();
+        if team.is_empty() {
+            return Ok((Vec::new(), None));
+        }
+
+        let mut state = SyncState::load(&self.project_root)?;
+        let assignee = team[state.round_robin_index % team.len()].clone();
+        state.round_robin_index = (state.round_robin_index + 1) % team.len();
+        Ok((vec![assignee], Some(state)))
+    }
+
+    /// Move an issue to the board column configured for its marker
+    ///
+    /// Failures only warn: the issue itself has been synced and recorded, and
+    /// failing the task would drop its number from project.md.
+    async fn move_on_board(&self, issue: &Issue, config: &ProjectConfig, marker: &str) {
+        let Some(board) = &config.project_board else { return };
+        let Some(column) = board.column_for(marker) else { return };
+
+        if let Err(e) = self.backend.set_board_column(issue, board, column).await {
+            eprintln!("Warning: failed to move issue #{} to '{}' on the project board: {:#}", issue.number, column, e);
+        }
+    }
+
+    /// Update the task file with issue_id and timestamps
+    ///
+    /// Only the managed keys are rewritten so user comments and formatting in
+    /// the front matter survive.
+    fn update_task_file_with_metadata(
+        &self,
+        path: &Path,
+        content: &str,
+        issue_id: u64,
+        task_item: &TaskItem,
+        in_progress: Option<bool>,
+        config: &ProjectConfig,
+    ) -> Result<()> {
+        // Parse the file to get the config
+        let task_file = parse_task_file_with(content, config.front_matter)?;
+
+        // Set timestamps
+        let now_time = self.clock.now();
+        let now = now_time.to_rfc3339();
+
+        let mut updates = vec![("issue_id", issue_id.to_string())];
+
+        let frozen = task_file.config.freeze_created_at == Some(true) && task_file.config.created_at.is_some();
+        if !frozen && (task_item.status.is_new() || self.options.reset_timestamps || task_file.config.created_at.is_none()) {
+            updates.push(("created_at", format!("\"{}\"", now)));
+        }
+
+        updates.push(("updated_at", format!("\"{}\"", now)));
+
+        if let Some(in_progress) = in_progress {
+            updates.push(("in_progress", in_progress.to_string()));
+        }
+
+        if let (TitleSource::Description, Some(description)) = (config.title_source, &task_item.description) {
+            updates.push(("synced_description", serde_json::to_string(description)?));
+        }
+
+        let mut updated_content = frontmatter::update_keys(content, &updates)?;
+        if config.line_ending.is_some() {
+            updated_content = NewlineStyle::detect(content)
+                .with_line_ending(config.line_ending)
+                .apply(&updated_content);
+        }
+
+        fs::write(path, updated_content)
+            .context("Failed to write updated task file")?;
+
+        // Writing the file bumps its mtime past updated_at, which would make the
+        // next run treat the unchanged task as modified
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(now_time.into()))
+            .context("Failed to set task file modification time")?;
+
+        Ok(())
+    }
+
+    /// Update project.md with new issue numbers
+    fn update_project_file(&self, project_file: &Path, content: &str, created: &[(TaskItem, u64)], config: &ProjectConfig) -> Result<()> {
+        let style = NewlineStyle::detect(content).with_line_ending(config.line_ending).with_final_newline(config.final_newline);
+        let updated_content = set_issue_numbers(&to_lf(content), created, config);
+
+        fs::write(project_file, style.apply(&updated_content))
+            .context("Failed to write updated project file")?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncAction {
+    Created(u64),
+    Updated(u64, SyncReason),
+    Skipped(SyncReason),
+    /// Recorded for a later `flush` by an offline sync
+    Queued(Box<QueuedOp>),
+}
+
+/// Why sync created, updated or skipped a task, for `sync --explain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncReason {
+    /// The task has no issue yet
+    New,
+    /// The file has no `updated_at`, so it was never synced
+    NeverSynced,
+    /// The file's mtime is later than its `updated_at`
+    FileModified,
+    /// The task moved between `[#N]` and `[wip:#N]`
+    MarkerChanged,
+    /// With `title_source: description`, its description changed; only the
+    /// title is updated
+    DescriptionChanged,
+    /// A description-only task's description isn't the one its issue was last
+    /// synced from
+    DescriptionOnly,
+    /// A description-only task's issue was last synced from its description
+    DescriptionUnchanged,
+    /// The file's mtime isn't later than its `updated_at`
+    Unchanged,
+    /// `--changed-since` left it out
+    NotChangedSince(String),
+    /// `--tag` left it out
+    NotTagged(String),
+    /// The task file doesn't exist and `on_missing_file` is `skip`
+    MissingFile,
+    /// `error_policy` skips this kind of error
+    ErrorSkipped(ErrorKind),
+    /// The issue was transferred to another repo and `--follow-transfers` recorded it
+    Transferred,
+    /// Queued by an offline sync, or sent by `flush`
+    Offline,
+}
+
+impl std::fmt::Display for SyncReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncReason::New => write!(f, "no issue yet"),
+            SyncReason::NeverSynced => write!(f, "no updated_at, never synced"),
+            SyncReason::FileModified => write!(f, "file modified after updated_at"),
+            SyncReason::MarkerChanged => write!(f, "marker changed between [#N] and [wip:#N]"),
+            SyncReason::DescriptionChanged => write!(f, "description changed, so only the title was updated"),
+            SyncReason::DescriptionOnly => write!(f, "description changed since the last sync"),
+            SyncReason::DescriptionUnchanged => write!(f, "description unchanged since the last sync"),
+            SyncReason::Unchanged => write!(f, "file not modified since updated_at"),
+            SyncReason::NotChangedSince(git_ref) => write!(f, "not changed since {}", git_ref),
+            SyncReason::NotTagged(tag) => write!(f, "not tagged {}", tag),
+            SyncReason::MissingFile => write!(f, "task file doesn't exist (on_missing_file: skip)"),
+            SyncReason::ErrorSkipped(kind) => write!(f, "error_policy skips {:?} errors", kind),
+            SyncReason::Transferred => write!(f, "issue transferred to another repo"),
+            SyncReason::Offline => write!(f, "queued by an offline sync"),
+        }
+    }
+}
+
+/// A task's outcome, as reported to `SyncEngine::with_events`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncEvent {
+    Created(TaskItem, u64),
+    Updated(TaskItem, u64),
+    Skipped(TaskItem),
+    Queued(TaskItem),
+    Failed(TaskItem, String),
+}
+
+#[derive(Debug, Default)]
+pub struct SyncResult {
+    pub created: Vec<(TaskItem, u64)>,
+    pub updated: Vec<(TaskItem, u64)>,
+    pub skipped: Vec<TaskItem>,
+    pub queued: Vec<TaskItem>,
+    /// Issues closed because their task lines were checked off
+    pub closed: Vec<(TaskItem, u64)>,
+    /// Issues closed as duplicates, with the issue each duplicates
+    pub duplicates: Vec<(TaskItem, u64, u64)>,
+    pub errors: Vec<(TaskItem, String)>,
+    /// Why each task that didn't fail was created, updated, skipped or queued, in sync order
+    pub reasons: Vec<(TaskItem, SyncReason)>,
+}
+
+impl SyncResult {
+    /// Add a task's outcome to its list
+    pub fn record(&mut self, event: SyncEvent) {
+        match event {
+            SyncEvent::Created(task, issue_num) => self.created.push((task, issue_num)),
+            SyncEvent::Updated(task, issue_num) => self.updated.push((task, issue_num)),
+            SyncEvent::Skipped(task) => self.skipped.push(task),
+            SyncEvent::Queued(task) => self.queued.push(task),
+            SyncEvent::Failed(task, error) => self.errors.push((task, error)),
+        }
+    }
+
+    /// Combine a retried sync's result with what the `earlier` attempts did
+    ///
+    /// Tasks created or updated earlier were skipped by the retry, so they
+    /// keep their earlier outcome and reason.
+    pub fn after(mut self, earlier: SyncResult) -> SyncResult {
+        let synced: HashSet<String> = earlier.created.iter().chain(&earlier.updated).map(|(task, _)| task.name()).collect();
+        self.skipped.retain(|task| !synced.contains(&task.name()));
+        for (task, reason) in self.reasons.iter_mut().filter(|(task, _)| synced.contains(&task.name())) {
+            if let Some((_, earlier_reason)) = earlier.reasons.iter().find(|(t, _)| t.name() == task.name()) {
+                *reason = earlier_reason.clone();
+            }
+        }
+
+        SyncResult {
+            created: earlier.created.into_iter().chain(self.created).collect(),
+            updated: earlier.updated.into_iter().chain(self.updated).collect(),
+            closed: earlier.closed.into_iter().chain(self.closed).collect(),
+            duplicates: earlier.duplicates.into_iter().chain(self.duplicates).collect(),
+            ..self
+        }
+    }
+
+    /// Print each task's outcome and the reason for it, in sync order
+    pub fn print_explanation(&self) {
+        println!("\n=== Sync Decisions ===\n");
+        let has = |tasks: &[(TaskItem, u64)], task: &TaskItem| tasks.iter().any(|(t, _)| t == task);
+
+        for (task, reason) in &self.reasons {
+            let outcome = if has(&self.created, task) {
+                "created"
+            } else if has(&self.updated, task) {
+                "updated"
+            } else if self.queued.contains(task) {
+                "queued"
+            } else {
+                "skipped"
+            };
+            println!("  {}: {} ({})", task.name(), outcome, reason);
+        }
+        for (task, _) in &self.errors {
+            println!("  {}: failed (see the errors below)", task.name());
+        }
+    }
+
+    /// Counts of each outcome on one line, like `created=3 updated=2 skipped=5
+    /// queued=0 errors=0`, for scripts
+    pub fn summary_line(&self) -> String {
+        format!(
+            "created={} updated={} skipped={} queued={} errors={}",
+            self.created.len(),
+            self.updated.len(),
+            self.skipped.len(),
+            self.queued.len(),
+            self.errors.len()
+        )
+    }
+
+    pub fn print_summary(&self) {
+        println!("\n=== Sync Summary ===");
+
+        if !self.created.is_empty() {
+            println!("\nCreated ({}):", self.created.len());
+            for (task, issue_num) in &self.created {
+                println!("  - {} -> Issue #{}", task.name(), issue_num);
+            }
+        }
+
+        if !self.updated.is_empty() {
+            println!("\nUpdated ({}):", self.updated.len());
+            for (task, issue_num) in &self.updated {
+                println!("  - {} -> Issue #{}", task.name(), issue_num);
+            }
+        }
+
+        if !self.skipped.is_empty() {
+            println!("\nSkipped (no changes) ({}):", self.skipped.len());
+            for task in &self.skipped {
+                println!("  ✓ {}", task.name());
+            }
+        }
+
+        if !self.closed.is_empty() {
+            println!("\nClosed as completed ({}):", self.closed.len());
+            for (task, issue_num) in &self.closed {
+                println!("  - {} -> Issue #{}", task.name(), issue_num);
+            }
+        }
+
+        if !self.duplicates.is_empty() {
+            println!("\nClosed as duplicates ({}):", self.duplicates.len());
+            for (task, issue_num, original) in &self.duplicates {
+                println!("  - {} -> Issue #{} (duplicate of #{})", task.name(), issue_num, original);
+            }
+        }
+
+        if !self.queued.is_empty() {
+            println!("\nQueued for flush ({}):", self.queued.len());
+            for task in &self.queued {
+                println!("  - {}", task.name());
+            }
+        }
+
+        if !self.errors.is_empty() {
+            println!("\nErrors ({}):", self.errors.len());
+            for (task, error) in &self.errors {
+                println!("  - {}: {}", task.name(), error);
+            }
+        }
+
+        println!("\nTotal: {} tasks processed",
+            self.created.len() + self.updated.len() + self.skipped.len() + self.queued.len() + self.errors.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::error::BackendError;
+    use crate::backend::Comment;
+    use crate::parser::parse_project_file;
+    use crate::types::DEFAULT_TASKS_SECTION;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    /// Backend that hands out sequential issue numbers and records updates
+    #[derive(Default)]
+    struct MockBackend {
+        next_number: Mutex<u64>,
+        updated: Mutex<Vec<u64>>,
+        closed: Mutex<Vec<u64>>,
+        bodies: Mutex<HashMap<u64, String>>,
+        titles: Mutex<HashMap<u64, String>>,
+        labels: Mutex<HashMap<u64, Vec<String>>>,
+        milestones: Mutex<HashMap<u64, String>>,
+        ensured_labels: Mutex<Vec<(String, String)>>,
+        /// Errors the next updates fail with, in order
+        failures: Mutex<Vec<ErrorKind>>,
+        /// Errors the next creates fail with, in order
+        create_failures: Mutex<Vec<ErrorKind>>,
+        /// What `verify_repo` reports, `Unchecked` if unset
+        access: Option<RepoAccess>,
+        /// Issues moved elsewhere, which fetches and updates are redirected to
+        transfers: HashMap<u64, Transfer>,
+        /// `(parent, child)` pairs from `add_sub_issue`, or `None` if unsupported
+        sub_issues: Option<Mutex<Vec<(u64, u64)>>>,
+        /// Issues `get_issue_if_changed` sent in full, having changed
+        fetched: Mutex<Vec<u64>>,
+    }
+
+    fn mock_issue(number: u64, title: &str, body: &str, labels: Vec<String>) -> Issue {
+        Issue {
+            id: number,
+            node_id: format!("I_{}", number),
+            number,
+            title: title.to_string(),
+            body: body.to_string(),
+            state: "open".to_string(),
+            labels,
+            url: format!("https://example.com/issues/{}", number),
+            comments: None,
+            reactions: None,
+            closed_at: None,
+            milestone: None,
+        }
+    }
+
+    #[async_trait]
+    impl Backend for MockBackend {
+        async fn create_issue(&self, title: &str, body: &str, labels: Vec<String>, _assignees: Vec<String>) -> Result<Issue> {
+            let mut failures = self.create_failures.lock().unwrap();
+            if !failures.is_empty() {
+                let kind = failures.remove(0);
+                return Err(BackendError::new(kind, format!("Simulated {:?} error", kind)).into());
+            }
+            drop(failures);
+            let mut next = self.next_number.lock().unwrap();
+            *next += 1;
+            self.titles.lock().unwrap().insert(*next, title.to_string());
+            self.labels.lock().unwrap().insert(*next, labels.clone());
+            Ok(mock_issue(*next, title, body, labels))
+        }
+
+        async fn update_issue(&self, number: u64, title: &str, body: &str, labels: Vec<String>, _assignees: Vec<String>) -> Result<Issue> {
+            let mut failures = self.failures.lock().unwrap();
+            if !failures.is_empty() {
+                let kind = failures.remove(0);
+                return Err(BackendError::new(kind, format!("Simulated {:?} error", kind)).into());
+            }
+            drop(failures);
+            self.updated.lock().unwrap().push(number);
+            if let Some(transfer) = self.transfers.get(&number) {
+                return Ok(Issue { url: transfer.url.clone(), ..mock_issue(transfer.number, title, body, labels) });
+            }
+            self.titles.lock().unwrap().insert(number, title.to_string());
+            self.labels.lock().unwrap().insert(number, labels.clone());
+            Ok(mock_issue(number, title, body, labels))
+        }
+
+        async fn close_issue(&self, number: u64) -> Result<Issue> {
+            self.closed.lock().unwrap().push(number);
+            Ok(mock_issue(number, "", "", Vec::new()))
+        }
+
+        async fn get_issue(&self, number: u64) -> Result<Issue> {
+            if let Some(transfer) = self.transfers.get(&number) {
+                return Ok(Issue { url: transfer.url.clone(), ..mock_issue(transfer.number, "", "", Vec::new()) });
+            }
+            let body = self.bodies.lock().unwrap().get(&number).cloned().unwrap_or_default();
+            Ok(mock_issue(number, "", &body, Vec::new()))
+        }
+
+        async fn get_issue_if_changed(&self, number: u64, etag: Option<&str>) -> Result<Option<(Issue, Option<String>)>> {
+            use std::hash::{Hash, Hasher};
+            let issue = self.get_issue(number).await?;
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            issue.body.hash(&mut hasher);
+            let current = format!("\"{:x}\"", hasher.finish());
+            if etag == Some(current.as_str()) {
+                return Ok(None);
+            }
+            self.fetched.lock().unwrap().push(number);
+            Ok(Some((issue, Some(current))))
+        }
+
+        async fn list_issues(&self) -> Result<Vec<Issue>> {
+            Ok(Vec::new())
+        }
+
+        async fn find_issue_by_key(&self, _key: &str) -> Result<Option<Issue>> {
+            Ok(None)
+        }
+
+        async fn list_comments(&self, _number: u64) -> Result<Vec<Comment>> {
+            Ok(Vec::new())
+        }
+
+        async fn list_collaborators(&self) -> Result<Option<Vec<String>>> {
+            Ok(Some(vec!["Alice".to_string(), "bob".to_string()]))
+        }
+
+        async fn ensure_label(&self, name: &str, color: &str) -> Result<()> {
+            self.ensured_labels.lock().unwrap().push((name.to_string(), color.to_string()));
+            Ok(())
+        }
+
+        async fn set_milestone(&self, number: u64, milestone: &str) -> Result<()> {
+            self.milestones.lock().unwrap().insert(number, milestone.to_string());
+            Ok(())
+        }
+
+        async fn verify_repo(&self) -> Result<RepoAccess> {
+            Ok(self.access.unwrap_or(RepoAccess::Unchecked))
+        }
+
+        fn transfer_of(&self, issue: &Issue) -> Option<Transfer> {
+            self.transfers.values().find(|transfer| transfer.url == issue.url).cloned()
+        }
+
+        async fn add_sub_issue(&self, parent: u64, child: u64) -> Result<()> {
+            let Some(links) = &self.sub_issues else {
+                anyhow::bail!("Sub-issues are not supported by this backend");
+            };
+            links.lock().unwrap().push((parent, child));
+            Ok(())
+        }
+    }
+
+    const PROJECT: &str = "backend: github\nrepo: test/perms\n---\n\n* [new] - tasks/a.md - A\n* [#2] - tasks/b.md - B\n";
+
+    /// Make description-only tasks sync again, as if they'd never synced
+    fn forget_synced_descriptions(project_root: &Path) {
+        let _ = fs::remove_file(SyncState::path(project_root));
+    }
+
+    fn set_read_only(path: &Path) {
+        let mut perms = fs::metadata(path).unwrap().permissions();
+        perms.set_readonly(true);
+        fs::set_permissions(path, perms).unwrap();
+    }
+
+    #[test]
+    fn test_find_read_only_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, PROJECT).unwrap();
+        fs::create_dir(dir.path().join("tasks")).unwrap();
+        fs::write(dir.path().join("tasks/a.md"), "---\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("tasks/b.md"), "---\n---\n# B\n").unwrap();
+
+        let project = parse_project_file(PROJECT).unwrap();
+        assert!(find_read_only_files(&project_file, dir.path(), &project).is_empty());
+
+        set_read_only(&project_file);
+        set_read_only(&dir.path().join("tasks/b.md"));
+
+        let read_only = find_read_only_files(&project_file, dir.path(), &project);
+        assert_eq!(read_only, vec![project_file.clone(), dir.path().join("tasks/b.md")]);
+        assert!(check_write_permissions(&project_file, dir.path(), &project).is_err());
+    }
+
+    #[test]
+    fn test_find_gaps() {
+        assert_eq!(
+            find_gaps(&[5, 1, 2, 5, 9], &[3, 2, 1, 4]),
+            IssueGaps { missing_remotely: vec![5, 9], untracked: vec![3, 4] }
+        );
+        assert_eq!(find_gaps(&[1, 2], &[2, 1]), IssueGaps::default());
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("tasks/api")).unwrap();
+        for file in ["tasks/a.md", "tasks/b.md", "tasks/api/c.md", "tasks/notes.txt", "tasks/.draft.md"] {
+            fs::write(dir.path().join(file), "# T\n").unwrap();
+        }
+
+        let project = parse_project_file("backend: github\nrepo: a/b\n---\n* [#1] - ./tasks/a.md - A\n* [new] - Quick fix\n").unwrap();
+        assert_eq!(
+            find_orphans(dir.path(), Path::new(DEFAULT_TASK_DIR), &project).unwrap(),
+            vec![PathBuf::from("tasks/api/c.md"), PathBuf::from("tasks/b.md")]
+        );
+        assert!(find_orphans(dir.path(), Path::new("missing"), &project).is_err());
+    }
+
+    #[test]
+    fn test_find_task_files_skips_ignored() {
+        use crate::ignore::IGNORE_FILE;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("tasks/templates")).unwrap();
+        fs::create_dir_all(dir.path().join("tasks/api")).unwrap();
+        for file in ["tasks/a.md", "tasks/a.draft.md", "tasks/keep.draft.md", "tasks/templates/bug.md", "tasks/api/b.md", "tasks/api/c.md"] {
+            fs::write(dir.path().join(file), "# T\n").unwrap();
+        }
+        fs::write(dir.path().join(IGNORE_FILE), "# Not tasks\n*.draft.md\n!keep.draft.md\ntemplates/\n/tasks/api/c.md\n").unwrap();
+
+        assert_eq!(
+            find_task_files(dir.path(), Path::new(DEFAULT_TASK_DIR)).unwrap(),
+            vec![PathBuf::from("tasks/a.md"), PathBuf::from("tasks/api/b.md"), PathBuf::from("tasks/keep.draft.md")]
+        );
+    }
+
+    #[test]
+    fn test_generate_project() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("plans/api")).unwrap();
+        fs::write(dir.path().join("plans/a.md"), "---\nissue_id: 7\n---\n# Design the API\n").unwrap();
+        fs::write(dir.path().join("plans/api/auth.md"), "---\ntype: feature\n---\nNo title here.\n").unwrap();
+        fs::write(dir.path().join("plans/broken.md"), "---\ntags: [unclosed\n---\n# Broken\n").unwrap();
+
+        let content = generate_project(dir.path(), Path::new("plans"), "github", "acme/app").unwrap();
+        assert!(content.starts_with(&format!("version: {}\nbackend: github\nrepo: acme/app\n---\n", FORMAT_VERSION)));
+
+        let project = parse_project_file(&content).unwrap();
+        assert_eq!(project.config.repo, "acme/app");
+        assert_eq!(
+            project.tasks.iter().map(|t| t.line()).collect::<Vec<_>>(),
+            vec!["* [#7] - plans/a.md - Design the API", "* [new] - plans/api/auth.md - auth"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_existing_task_not_rewritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        let task_path = dir.path().join("a.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#3] - a.md - A\n").unwrap();
+        fs::write(&task_path, "---\ntype: task # kept\n---\n# A\n\nBody.\n").unwrap();
+
+        let project = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap();
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+
+        let first = engine.sync(&project_file).await.unwrap();
+        assert_eq!(first.updated, vec![(project.tasks[0].clone(), 3)]);
+
+        let content = fs::read_to_string(&task_path).unwrap();
+        let mtime = fs::metadata(&task_path).unwrap().modified().unwrap();
+        assert!(content.contains("issue_id: 3"));
+
+        let second = engine.sync(&project_file).await.unwrap();
+        assert_eq!(second.skipped, vec![project.tasks[0].clone()]);
+        assert_eq!(*engine.backend.updated.lock().unwrap(), vec![3]);
+        assert_eq!(fs::read_to_string(&task_path).unwrap(), content);
+        assert_eq!(fs::metadata(&task_path).unwrap().modified().unwrap(), mtime);
+    }
+
+    #[test]
+    fn test_resolve_labels_wip() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\nwip_label: doing\n---\n* [wip:#1] - a.md - A\n* [#2] - b.md - B\n",
+        )
+        .unwrap();
+        let task_config = parse_task_file("---\ntags: [infra, doing]\n---\n# T\n").unwrap().config;
+        let untagged = parse_task_file("---\n---\n# T\n").unwrap().config;
+
+        assert_eq!(resolve_labels(&project.tasks[0], &untagged, &project.config), vec!["doing"]);
+        assert_eq!(resolve_labels(&project.tasks[0], &task_config, &project.config), vec!["infra", "doing"]);
+        assert!(resolve_labels(&project.tasks[1], &untagged, &project.config).is_empty());
+    }
+
+    #[test]
+    fn test_status_label_map() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\nstatus_label_map:\n  new: triage\n  wip: doing\n  archived: archived\n---\n* [new] - a.md - A\n* [#2] - b.md - B\n* [wip:#3] - c.md - C\n",
+        )
+        .unwrap();
+        let plain = parse_task_file("---\ntags: [api]\n---\n# T\n").unwrap().config;
+        let archived = parse_task_file("---\narchived: true\n---\n# T\n").unwrap().config;
+
+        let labels = |task: usize, task_config: &TaskFileConfig| resolve_labels(&project.tasks[task], task_config, &project.config);
+        assert_eq!(labels(0, &plain), vec!["api", "triage"]);
+        assert_eq!(labels(1, &plain), vec!["api"]);
+        assert_eq!(labels(2, &plain), vec!["api", "in-progress", "doing"]);
+        assert_eq!(labels(1, &archived), vec!["archived"]);
+
+        // Kept labels lose the state labels a task no longer has
+        let mut state_labels = project.config.state_labels();
+        state_labels.sort();
+        assert_eq!(state_labels, vec!["archived", "doing", "in-progress", "triage"]);
+        let current = ["triage".to_string(), "bug".to_string()];
+        assert_eq!(merge_labels(&current, labels(2, &TaskFileConfig::default()), &state_labels), vec!["bug", "in-progress", "doing"]);
+    }
+
+    #[tokio::test]
+    async fn test_managed_label_on_every_issue() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(
+            &project_file,
+            "backend: github\nrepo: a/b\nmanaged_label: projectmd\n---\n* [new] - a.md - A\n* [new] - Quick fix\n* [wip:#9] - b.md - B\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("a.md"), "---\ntags: [infra]\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\ntags: [projectmd]\n---\n# B\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let labels = engine.backend.labels.lock().unwrap();
+        assert_eq!(labels[&1], vec!["infra", "projectmd"]);
+        assert_eq!(labels[&2], vec!["projectmd"]);
+        assert_eq!(labels[&9], vec!["projectmd", "in-progress"]);
+    }
+
+    #[test]
+    fn test_archived_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("old.md"), "---\narchived: true\n---\n# Old\n").unwrap();
+        fs::write(dir.path().join("kept.md"), "---\narchived: false\n---\n# Kept\n").unwrap();
+        fs::write(dir.path().join("plain.md"), "---\n---\n# Plain\n").unwrap();
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\n---\n* [#1] - old.md - Old\n* [#2] - kept.md - Kept\n* [new] - plain.md - Plain\n* [new] - missing.md - Gone\n* [new] - Quick fix\n",
+        )
+        .unwrap();
+
+        let listed: Vec<String> = project
+            .tasks
+            .iter()
+            .filter(|task| !is_archived(dir.path(), task, &project.config))
+            .map(|task| task.name())
+            .collect();
+        assert_eq!(listed, vec!["kept.md", "plain.md", "missing.md", "\"Quick fix\""]);
+    }
+
+    #[test]
+    fn test_resolve_assignees_from_tags() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\ntag_assignees:\n  team/platform: [alice, bob]\n  infra: [bob, carol]\n---\n",
+        )
+        .unwrap();
+        let task_config = parse_task_file(
+            "---\ntags: [team/platform, infra, docs]\nassignees: [bob, dave]\n---\n# T\n",
+        )
+        .unwrap()
+        .config;
+
+        assert_eq!(
+            resolve_assignees(&task_config, &project.config, &[]),
+            vec!["bob", "dave", "alice", "carol"]
+        );
+
+        // Code owners are merged in after them
+        let owners = vec!["carol".to_string(), "erin".to_string()];
+        assert_eq!(
+            resolve_assignees(&task_config, &project.config, &owners),
+            vec!["bob", "dave", "alice", "carol", "erin"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_label_map_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(
+            &project_file,
+            "backend: github\nrepo: a/b\nlabel_map_file: labels.yml\ntag_labels:\n  p1: urgent\n---\n* [new] - a.md - A\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("a.md"), "---\ntags: [p1, api, be, docs]\n---\n# A\n").unwrap();
+        fs::write(
+            dir.path().join("labels.yml"),
+            "tag_labels:\n  p1: priority/high\n  api: area/api\n  be: area/api\nlabel_color_rules:\n  area/: 1d76db\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("labels.json"), r#"{"tag_labels": {"docs": "area/docs"}}"#).unwrap();
+
+        // Inline entries win, and tags mapped to the same label are pushed once
+        let mut config = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap().config;
+        load_label_map(&mut config, dir.path(), None).unwrap();
+        let task_config = parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap().config;
+        let task = TaskItem::new(TaskStatus::New, Some("a.md".into()), None);
+        assert_eq!(resolve_labels(&task, &task_config, &config), vec!["urgent", "area/api", "docs"]);
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        engine.sync(&project_file).await.unwrap();
+        assert_eq!(engine.backend.labels.lock().unwrap()[&1], vec!["urgent", "area/api", "docs"]);
+        assert_eq!(
+            *engine.backend.ensured_labels.lock().unwrap(),
+            vec![
+                ("urgent".to_string(), DEFAULT_LABEL_COLOR.to_string()),
+                ("area/api".to_string(), "1d76db".to_string()),
+                ("docs".to_string(), DEFAULT_LABEL_COLOR.to_string()),
+            ]
+        );
+
+        // A file given on the command line replaces the configured one
+        let mut config = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap().config;
+        load_label_map(&mut config, dir.path(), Some(&dir.path().join("labels.json"))).unwrap();
+        assert_eq!(resolve_labels(&task, &task_config, &config), vec!["urgent", "api", "be", "area/docs"]);
+        assert!(load_label_map(&mut config, dir.path(), Some(Path::new("missing.yml"))).is_err());
+    }
+
+    #[test]
+    fn test_check_task_repo() {
+        let task_config = |repo: &str| parse_task_file(&format!("---\nrepo: {}\n---\n# T\n", repo)).unwrap().config;
+        let default = parse_project_file("backend: github\nrepo: a/b\n---\n").unwrap().config;
+
+        assert!(check_task_repo(&TaskFileConfig::default(), &default).is_ok());
+        assert!(check_task_repo(&task_config("a/b"), &default).is_ok());
+        let error = check_task_repo(&task_config("a/typo"), &default).unwrap_err().to_string();
+        assert!(error.contains("isn't the project's repo a/b"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_repo_defaults() {
+        let task_config = parse_task_file("---\ntags: [infra]\n---\n# T\n").unwrap().config;
+        let assigned = parse_task_file("---\nassignees: [bob]\n---\n# T\n").unwrap().config;
+
+        for (defaults, labels, assignees, milestone) in [
+            ("repo_defaults:\n  labels: [team-a]\n  assignees: [alice]\n  milestone: v1\n", vec!["infra", "team-a"], vec!["alice"], Some("v1")),
+            ("repo_defaults:\n  labels: [team-c, infra]\n", vec!["infra", "team-c"], vec![], None),
+            ("", vec!["infra"], vec![], None),
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            let project = format!("backend: github\nrepo: a/b\n{}---\n* [new] - a.md - A\n", defaults);
+            fs::write(&project_file, &project).unwrap();
+            fs::write(dir.path().join("a.md"), "---\ntags: [infra]\n---\n# A\n").unwrap();
+
+            let parsed = parse_project_file(&project).unwrap();
+            assert_eq!(resolve_labels(&parsed.tasks[0], &task_config, &parsed.config), labels);
+            assert_eq!(resolve_assignees(&task_config, &parsed.config, &[]), assignees);
+            assert_eq!(resolve_assignees(&assigned, &parsed.config, &[]), vec!["bob"]);
+
+            let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+            let result = engine.sync(&project_file).await.unwrap();
+            assert!(result.errors.is_empty(), "{:?}", result.errors);
+            assert_eq!(engine.backend.labels.lock().unwrap()[&1], labels);
+            assert_eq!(engine.backend.milestones.lock().unwrap().get(&1).map(String::as_str), milestone);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_label_colors_from_prefix_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        let project = "backend: github\nrepo: a/b\nlabel_color_rules:\n  area/: \"#1d76db\"\n  area/api/: 5319e7\n  type/: 0e8a16\n---\n* [new] - a.md - A\n";
+        fs::write(&project_file, project).unwrap();
+        fs::write(dir.path().join("a.md"), "---\ntags: [area/db, area/api/v2, type/bug, misc]\n---\n# A\n").unwrap();
+
+        let rules = parse_project_file(project).unwrap().config.label_color_rules;
+        assert_eq!(label_color("area/db", &rules), "1d76db");
+        assert_eq!(label_color("area/api/v2", &rules), "5319e7");
+        assert_eq!(label_color("type/bug", &rules), "0e8a16");
+        assert_eq!(label_color("misc", &rules), DEFAULT_LABEL_COLOR);
+        assert_eq!(label_color("area", &rules), DEFAULT_LABEL_COLOR);
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        engine.sync(&project_file).await.unwrap();
+        let ensured = engine.backend.ensured_labels.lock().unwrap();
+        assert_eq!(
+            *ensured,
+            [("area/db", "1d76db"), ("area/api/v2", "5319e7"), ("type/bug", "0e8a16"), ("misc", DEFAULT_LABEL_COLOR)]
+                .map(|(label, color)| (label.to_string(), color.to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_assignees() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\ntag_assignees:\n  infra: [carol]\n---\n* [new] - a.md - A\n* [#2] - b.md - B\n* [new] - Quick fix\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("a.md"), "---\nassignees: [alice, bbo]\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\ntags: [infra]\nassignees: [bob]\n---\n# B\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        assert_eq!(
+            engine.unknown_assignees(&project).await.unwrap(),
+            vec![(project.tasks[0].clone(), "bbo".to_string()), (project.tasks[1].clone(), "carol".to_string())]
+        );
+
+        // Backends without collaborators have nothing to check against
+        let offline = SyncEngine::new(crate::queue::OfflineBackend, dir.path().to_path_buf());
+        assert!(offline.unknown_assignees(&project).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_board_columns() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\nproject_board:\n  project_id: PVT_1\n  columns:\n    open: Backlog\n---\n",
+        )
+        .unwrap();
+        let board = project.config.project_board.unwrap();
+
+        assert_eq!(board.status_field, "Status");
+        assert_eq!(board.column_for(board_marker(&TaskStatus::New, true)), Some("Todo"));
+        assert_eq!(board.column_for(board_marker(&TaskStatus::InProgress(1), true)), Some("Todo"));
+        assert_eq!(board.column_for(board_marker(&TaskStatus::InProgress(1), false)), Some("In Progress"));
+        assert_eq!(board.column_for(board_marker(&TaskStatus::Existing(1), false)), Some("Backlog"));
+        assert_eq!(board.column_for("done"), None);
+    }
+
+    #[tokio::test]
+    async fn test_close_issues_in_batches() {
+        let backend = MockBackend::default();
+        let numbers: Vec<u64> = (1..=CLOSE_BATCH_SIZE as u64 + 2).collect();
+
+        let results = close_issues(&backend, &numbers).await;
+
+        assert_eq!(results.iter().map(|(n, _)| *n).collect::<Vec<_>>(), numbers);
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+        let mut closed = backend.closed.lock().unwrap().clone();
+        closed.sort();
+        assert_eq!(closed, numbers);
+    }
+
+    #[tokio::test]
+    async fn test_pull_round_trips_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#3] - a.md - A\n* [#4] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\ntype: task # kept\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+
+        // Render a's issue body as sync would for a task with different metadata
+        let remote = parse_task_file("---\ntype: bug\ntags: [infra, \"p1\"]\n---\n# A\n\nBody.\n").unwrap();
+        let project = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap();
+        let fields = prepare_issue(&project.tasks[0], &remote, &project.config, &SyncOptions::default());
+
+        let backend = MockBackend::default();
+        backend.bodies.lock().unwrap().insert(3, fields.body);
+        backend.bodies.lock().unwrap().insert(4, "Written by hand".to_string());
+
+        let engine = SyncEngine::new(backend, dir.path().to_path_buf());
+        let result = engine.pull(&project_file).await.unwrap();
+
+        assert_eq!(result.updated, vec![(project.tasks[0].clone(), 3)]);
+        assert_eq!(result.skipped, vec![project.tasks[1].clone()]);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("a.md")).unwrap(),
+            "---\ntype: \"bug\" # kept\ntags: [\"infra\",\"p1\"]\nissue_id: 3\n---\n# A\n"
+        );
+
+        let pulled = parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap();
+        assert_eq!(pulled.config.task_type.as_deref(), Some("bug"));
+        assert_eq!(pulled.config.tags, remote.config.tags);
+
+        // Pulling again changes nothing
+        assert!(engine.pull(&project_file).await.unwrap().updated.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_remote_only_fetches_changed_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#3] - a.md - A\n* [#4] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+
+        let backend = MockBackend::default();
+        backend.bodies.lock().unwrap().insert(3, "Written by hand".to_string());
+        backend.bodies.lock().unwrap().insert(4, "Written by hand".to_string());
+        let engine = SyncEngine::new(backend, dir.path().to_path_buf());
+
+        let mut cycles = Vec::new();
+        let stop = tokio::time::sleep(Duration::from_secs(25));
+        engine
+            .watch_remote(&project_file, Duration::from_secs(10), stop, |result| {
+                result.unwrap();
+                let mut fetched = std::mem::take(&mut *engine.backend.fetched.lock().unwrap());
+                fetched.sort();
+                if cycles.is_empty() {
+                    engine.backend.bodies.lock().unwrap().insert(4, "Edited".to_string());
+                }
+                cycles.push(fetched);
+            })
+            .await;
+
+        // Everything at first, then only what changed since
+        assert_eq!(cycles, vec![vec![3, 4], vec![4], vec![]]);
+    }
+
+    #[tokio::test]
+    async fn test_pull_many_tasks_concurrently() {
+        use crate::backend::fs::FileSystemBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        let backend = FileSystemBackend::new(&dir.path().join("issues"));
+
+        // Issues 1-20 carry `type: bug`; the last line shares task 1's file
+        let mut project = "backend: fs\nrepo: issues\n---\n".to_string();
+        for i in 1..=20 {
+            project.push_str(&format!("* [#{}] - t{}.md - T{}\n", i, i, i));
+            fs::write(dir.path().join(format!("t{}.md", i)), format!("---\n---\n# T{}\n", i)).unwrap();
+        }
+        project.push_str("* [#1] - t1.md - T1 again\n");
+        fs::write(&project_file, &project).unwrap();
+
+        let parsed = parse_project_file(&project).unwrap();
+        let remote = parse_task_file("---\ntype: bug\n---\n# T\n").unwrap();
+        for task in &parsed.tasks[..20] {
+            let fields = prepare_issue(task, &remote, &parsed.config, &SyncOptions::default());
+            backend.create_issue(&fields.title, &fields.body, Vec::new(), Vec::new()).await.unwrap();
+        }
+
+        let options = SyncOptions { concurrency: Some(8), ..Default::default() };
+        let engine = SyncEngine::new(backend, dir.path().to_path_buf()).with_options(options);
+        let result = engine.pull(&project_file).await.unwrap();
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        let expected: Vec<(TaskItem, u64)> = parsed.tasks[..20].iter().cloned().zip(1..).collect();
+        assert_eq!(result.updated, expected);
+        assert_eq!(result.skipped, vec![parsed.tasks[20].clone()]);
+        for i in 1..=20 {
+            let pulled = parse_task_file(&fs::read_to_string(dir.path().join(format!("t{}.md", i))).unwrap()).unwrap();
+            assert_eq!(pulled.config.task_type.as_deref(), Some("bug"));
+            assert_eq!(pulled.config.issue_id, Some(i));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offline_queue_flush_round_trip() {
+        use crate::backend::fs::FileSystemBackend;
+        use crate::queue::{OfflineBackend, Queue};
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        let project_md = "backend: fs\nrepo: issues\n---\n* [new] - a.md - A\n* [new] - Quick fix\n";
+        fs::write(&project_file, project_md).unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n\nFirst draft.\n").unwrap();
+
+        let offline = SyncEngine::new(OfflineBackend, dir.path().to_path_buf())
+            .with_options(SyncOptions { offline: true, ..Default::default() });
+
+        let result = offline.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.queued.len(), 2);
+
+        // Syncing offline again keeps one operation per task, with the latest content
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n\nFinal draft.\n").unwrap();
+        offline.sync(&project_file).await.unwrap();
+        let queue = Queue::load(dir.path()).unwrap();
+        assert_eq!(queue.ops.len(), 2);
+        assert!(queue.ops[0].fields.body.contains("Final draft."));
+
+        // Nothing local changes until the flush
+        assert_eq!(fs::read_to_string(&project_file).unwrap(), project_md);
+        assert_eq!(fs::read_to_string(dir.path().join("a.md")).unwrap(), "---\n---\n# A\n\nFinal draft.\n");
+
+        let backend = FileSystemBackend::new(&dir.path().join("issues"));
+        let online = SyncEngine::new(backend, dir.path().to_path_buf());
+        let result = online.flush(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![1, 2]);
+
+        assert_eq!(
+            fs::read_to_string(&project_file).unwrap(),
+            "backend: fs\nrepo: issues\n---\n* [#1] - a.md - A\n* [#2] - Quick fix\n"
+        );
+        assert!(fs::read_to_string(dir.path().join("a.md")).unwrap().contains("issue_id: 1"));
+        assert!(online.backend.get_issue(1).await.unwrap().body.contains("Final draft."));
+        assert!(!Queue::path(dir.path()).exists());
+    }
+
+    #[tokio::test]
+    async fn test_flush_requeues_a_sent_create_as_an_update() {
+        use crate::backend::fs::FileSystemBackend;
+        use crate::queue::{OfflineBackend, Queue};
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: fs\nrepo: issues\n---\n* [new] - a.md - A\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n\nFirst draft.\n").unwrap();
+        let offline = SyncEngine::new(OfflineBackend, dir.path().to_path_buf())
+            .with_options(SyncOptions { offline: true, ..Default::default() });
+        offline.sync(&project_file).await.unwrap();
+
+        // Too big to read back once the issue is created
+        fs::write(&project_file, "backend: fs\nrepo: issues\nmax_task_file_bytes: 8\n---\n* [new] - a.md - A\n").unwrap();
+        let online = SyncEngine::new(FileSystemBackend::new(&dir.path().join("issues")), dir.path().to_path_buf());
+        let result = online.flush(&project_file).await.unwrap();
+        assert_eq!(result.errors.len(), 1);
+
+        // The issue is recorded and left to be updated, not created again
+        assert_eq!(Queue::load(dir.path()).unwrap().ops[0].issue, Some(1));
+        assert!(fs::read_to_string(&project_file).unwrap().ends_with("* [#1] - a.md - A\n"));
+
+        let project_md = fs::read_to_string(&project_file).unwrap().replace("max_task_file_bytes: 8\n", "");
+        fs::write(&project_file, project_md).unwrap();
+        let result = online.flush(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.updated.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![1]);
+        assert!(fs::read_to_string(dir.path().join("a.md")).unwrap().contains("issue_id: 1"));
+        assert_eq!(online.backend.list_issues().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_is_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#3] - a.md - A\n* [new] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+
+        let project = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap();
+        assert!(is_pending(dir.path(), &project.tasks[0], &project.config).unwrap());
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        engine.sync(&project_file).await.unwrap();
+
+        let project = parse_project_file(&fs::read_to_string(&project_file).unwrap()).unwrap();
+        assert!(!is_pending(dir.path(), &project.tasks[0], &project.config).unwrap());
+        assert!(!is_pending(dir.path(), &project.tasks[1], &project.config).unwrap());
+
+        // Moving to [wip:#N] is pending even though the file didn't change
+        let wip = TaskItem { status: TaskStatus::InProgress(3), ..project.tasks[0].clone() };
+        assert!(is_pending(dir.path(), &wip, &project.config).unwrap());
+    }
+
+    #[test]
+    fn test_clear_wip_markers() {
+        let config = parse_project_file("backend: github\nrepo: a/b\n---\n").unwrap().config;
+        let content = "* [wip:#1] - a.md - A\n* [wip:#2] - b.md - B\n* [#3] - c.md - C\n";
+        assert_eq!(
+            clear_wip_markers(content, &[1, 3], &config),
+            "* [#1] - a.md - A\n* [wip:#2] - b.md - B\n* [#3] - c.md - C\n"
+        );
+
+        let config = ProjectConfig { issue_prefix: Some("GH-".to_string()), ..config };
+        assert_eq!(clear_wip_markers("* [wip:GH-1] - a.md - A\n", &[1], &config), "* [GH-1] - a.md - A\n");
+        assert_eq!(clear_wip_markers("- [wip:GH-1] - A\n3. [wip:GH-1] - B", &[1], &config), "- [GH-1] - A\n3. [GH-1] - B");
+    }
+
+    #[tokio::test]
+    async fn test_completed_tasks_close_their_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md - A ✅\n* [new] - Quick fix [x]\n* [#4] - Bump the version [x]\n* [#5] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let closed: Vec<u64> = result.closed.iter().map(|(_, num)| *num).collect();
+        assert_eq!(closed, vec![1, 2, 4]);
+        assert_eq!(*engine.backend.closed.lock().unwrap(), vec![1, 2, 4]);
+        // New numbers are written back, keeping the checkboxes
+        assert_eq!(
+            fs::read_to_string(&project_file).unwrap(),
+            "backend: github\nrepo: a/b\n---\n* [#1] - a.md - A ✅\n* [#2] - Quick fix [x]\n* [#4] - Bump the version [x]\n* [#5] - b.md - B\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filtered_out_tasks_are_not_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#4] - a.md - A ✅\n* [#5] - b.md - B ✅\n* [#6] - c.md - C\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\nissue_id: 4\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\nissue_id: 5\ntags: [api]\n---\n# B\n").unwrap();
+        fs::write(dir.path().join("c.md"), "---\nissue_id: 6\nduplicate_of: 5\n---\n# C\n").unwrap();
+
+        let options = SyncOptions { tag: Some("api".to_string()), ..Default::default() };
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf()).with_options(options);
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(*engine.backend.closed.lock().unwrap(), vec![5]);
+        assert!(result.duplicates.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_are_closed_before_commenting() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#6] - c.md - C\n* [#7] - d.md - D ✅\n").unwrap();
+        fs::write(dir.path().join("c.md"), "---\nissue_id: 6\nduplicate_of: 5\n---\n# C\n").unwrap();
+        fs::write(dir.path().join("d.md"), "---\nissue_id: 7\nduplicate_of: 5\n---\n# D\n").unwrap();
+
+        // The mock can't comment, so #6 is closed but its comment fails
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].1.contains("Closed, but failed to comment"), "{}", result.errors[0].1);
+
+        // A checked-off duplicate is handled as a completed task
+        assert_eq!(*engine.backend.closed.lock().unwrap(), vec![6, 7]);
+        assert!(result.duplicates.is_empty());
+        assert_eq!(result.closed.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![7]);
+    }
+
+    #[tokio::test]
+    async fn test_issue_prefix_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\nissue_prefix: GH-\n---\n* [new] - a.md - A\n* [new] - Quick fix\n* [wip:GH-7] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\nissue_id: 7\n---\n# B\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        let content = fs::read_to_string(&project_file).unwrap();
+        assert_eq!(content, "backend: github\nrepo: a/b\nissue_prefix: GH-\n---\n* [GH-1] - a.md - A\n* [GH-2] - Quick fix\n* [wip:GH-7] - b.md - B\n");
+        let project = parse_project_file(&content).unwrap();
+        assert_eq!(project.tasks[1].status, TaskStatus::Existing(2));
+        assert_eq!(project.tasks[1].line_in(&project.config), "* [GH-2] - Quick fix");
+    }
+
+    #[test]
+    fn test_append_task_line_under_custom_section() {
+        let line = "* [new] - tasks/c.md - C";
+        let content = "backend: github\nrepo: a/b\ntasks_section: Backlog\n---\n# Plan\n\n## Backlog\n\n* [#1] - tasks/a.md - A\n* [new] - B\n\n### Notes\n\nText\n\n## Done\n\n* [#9] - tasks/z.md - Z\n";
+
+        let appended = append_task_line(content, "backlog", line);
+        assert_eq!(
+            appended,
+            content.replace("* [new] - B\n", "* [new] - B\n* [new] - tasks/c.md - C\n")
+        );
+        let project = parse_project_file(&appended).unwrap();
+        assert_eq!(project.config.tasks_section(), "Backlog");
+        assert_eq!(project.tasks.len(), 4);
+
+        // An empty section directly followed by another heading
+        assert_eq!(
+            append_task_line("---\n## Backlog\n## Done\n", "Backlog", line),
+            "---\n## Backlog\n\n* [new] - tasks/c.md - C\n\n## Done\n"
+        );
+
+        // `#` lines in code blocks are neither the section nor its end
+        assert_eq!(
+            append_task_line("---\n```\n# Backlog\n```\n", "Backlog", line),
+            "---\n```\n# Backlog\n```\n\n## Backlog\n\n* [new] - tasks/c.md - C\n"
+        );
+        assert_eq!(
+            append_task_line("---\n## Backlog\n\n* [#1] - A\n\n~~~sh\n# comment\n~~~\n## Done\n", "Backlog", line),
+            "---\n## Backlog\n\n* [#1] - A\n\n~~~sh\n# comment\n~~~\n* [new] - tasks/c.md - C\n\n## Done\n"
+        );
+
+        // A missing section is created at the end, keeping CRLF endings
+        assert_eq!(
+            append_task_line("---\r\n# Plan\r\n\r\n", DEFAULT_TASKS_SECTION, line),
+            "---\r\n# Plan\r\n\r\n## Tasks\r\n\r\n* [new] - tasks/c.md - C\r\n"
+        );
+    }
+
+    #[test]
+    fn test_identical_description_only_lines_change_one_at_a_time() {
+        let content = "backend: github\nrepo: a/b\n---\n\n* [#1] - Fix typo\n* [#1] - Fix typo\n* [#2] - tasks/a.md - A\n";
+        let project = parse_project_file(content).unwrap();
+        let (first, second) = (&project.tasks[0], &project.tasks[1]);
+
+        let reset = set_status(content, first, TaskStatus::New, &project.config);
+        assert_eq!(reset, content.replacen("* [#1] - Fix typo", "* [new] - Fix typo", 1));
+        assert_eq!(set_status(&reset, second, TaskStatus::New, &project.config), content.replace("* [#1] - Fix typo", "* [new] - Fix typo"));
+
+        let checked = check_off(content, first, &project.config);
+        assert_eq!(checked, content.replacen("* [#1] - Fix typo\n", "* [#1] - Fix typo ✅\n", 1));
+        assert_eq!(check_off(&checked, second, &project.config), content.replace("* [#1] - Fix typo\n", "* [#1] - Fix typo ✅\n"));
+    }
+
+    #[test]
+    fn test_adaptive_concurrency_follows_rate_limit_headers() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let reset = "1700003600";
+        let rate = |remaining: &str| {
+            let headers = HashMap::from([("x-ratelimit-limit", "5000"), ("x-ratelimit-remaining", remaining), ("x-ratelimit-reset", reset)]);
+            RateLimit::from_headers(|name| headers.get(name).copied()).unwrap()
+        };
+
+        // The budget shrinks response by response, then resets
+        let concurrency: Vec<usize> = ["5000", "3000", "1200", "10", "0"]
+            .into_iter()
+            .map(|remaining| adaptive_concurrency(8, Some(&rate(remaining)), now))
+            .collect();
+        assert_eq!(concurrency, vec![8, 5, 2, 1, 1]);
+        let after_reset = DateTime::from_timestamp(1_700_003_600, 0).unwrap();
+        assert_eq!(adaptive_concurrency(8, Some(&rate("0")), after_reset), 8);
+        assert_eq!(adaptive_concurrency(8, None, now), 8);
+
+        assert_eq!(rate("12").resource, "core");
+        assert!(RateLimit::from_headers(|name| (name == "x-ratelimit-limit").then_some("5000")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_limit_holds_requests_back() {
+        let state = Arc::new(RateLimitState::default());
+        let reset = Utc::now() + chrono::Duration::hours(1);
+        state.update(RateLimit { resource: "core".to_string(), limit: 5000, remaining: 10, reset });
+        let limit = AdaptiveLimit::new(4, Some(state.clone()));
+        let peak = Mutex::new(0);
+
+        let request = || async {
+            limit.acquire().await;
+            let in_flight = *limit.in_flight.lock().unwrap();
+            {
+                let mut peak = peak.lock().unwrap();
+                *peak = (*peak).max(in_flight);
+            }
+            tokio::task::yield_now().await;
+            limit.release();
+        };
+        join_all((0..6).map(|_| request())).await;
+        assert_eq!(*peak.lock().unwrap(), 1);
+
+        // A full budget lets every request run at once
+        state.update(RateLimit { resource: "core".to_string(), limit: 5000, remaining: 5000, reset });
+        join_all((0..6).map(|_| request())).await;
+        assert_eq!(*peak.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_truncate_title() {
+        assert_eq!(truncate_title("Short", 10), "Short");
+        assert_eq!(truncate_title("Exactly ten", 11), "Exactly ten");
+        assert_eq!(truncate_title("Setup the authentication flow", 10), "Setup the…");
+        assert_eq!(truncate_title("Résumé parsing", 5), "Résu…");
+    }
+
+    #[tokio::test]
+    async fn test_long_titles_match_on_create_and_update() {
+        let long = "Long ".repeat(60).trim_end().to_string();
+        for (max_title_len, truncate, expected) in [("", false, BACKEND_TITLE_LIMIT), ("max_title_len: 20\n", true, 20), ("max_title_len: 20\n", false, BACKEND_TITLE_LIMIT)] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            fs::write(&project_file, format!("backend: github\nrepo: a/b\n{}---\n* [new] - a.md - A\n* [new] - {}\n", max_title_len, long)).unwrap();
+            fs::write(dir.path().join("a.md"), format!("---\n---\n# {}\n", long)).unwrap();
+
+            let options = SyncOptions { truncate_titles: truncate, ..Default::default() };
+            let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf()).with_options(options);
+            let created = engine.sync(&project_file).await.unwrap();
+            assert!(created.errors.is_empty(), "{:?}", created.errors);
+            let first = engine.backend.titles.lock().unwrap().clone();
+
+            // Dropping updated_at and the synced description makes both tasks
+            // sync again, and updating sends exactly the titles that were created
+            fs::write(dir.path().join("a.md"), format!("---\n---\n# {}\n", long)).unwrap();
+            forget_synced_descriptions(dir.path());
+            let updated = engine.sync(&project_file).await.unwrap();
+            assert_eq!(updated.updated.len(), 2, "{:?}", updated.errors);
+            assert_eq!(*engine.backend.titles.lock().unwrap(), first);
+            for title in first.values() {
+                assert!(title.chars().count() <= expected, "{}", title);
+                assert!(title.ends_with('…'));
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_body_for_empty_task_files() {
+        let project = parse_project_file("backend: github\nrepo: a/b\ndefault_body: \"See {{path}} ({{title}})\"\n---\n* [new] - tasks/a.md - A\n").unwrap();
+        let body = |content: &str, config: &ProjectConfig| {
+            let task_file = parse_task_file(content).unwrap();
+            let body = prepare_issue(&project.tasks[0], &task_file, config, &SyncOptions::default()).body;
+            split_meta(&body).1.to_string()
+        };
+
+        assert!(body("---\n---\n# Task A\n\n  \n", &project.config).starts_with("See tasks/a.md (Task A)"));
+        assert!(body("---\n---\n# Task A\n\nWritten.\n", &project.config).starts_with("Written."));
+        // Without default_body, empty bodies stay empty
+        let plain = ProjectConfig { default_body: None, ..project.config.clone() };
+        assert!(!body("---\n---\n# Task A\n", &plain).contains("See"));
+    }
+
+    #[test]
+    fn test_tag_as_type() {
+        let project = parse_project_file("backend: github\nrepo: a/b\n---\n* [new] - tasks/a.md - A\n").unwrap();
+        let task_file = parse_task_file("---\ntags: [api, backend]\n---\n# Task A\n").unwrap();
+        let meta_type = |config: &ProjectConfig| {
+            let body = prepare_issue(&project.tasks[0], &task_file, config, &SyncOptions::default()).body;
+            split_meta(&body).0.unwrap().task_type
+        };
+
+        assert_eq!(meta_type(&project.config), None);
+        let config = ProjectConfig { tag_as_type: true, ..project.config.clone() };
+        assert_eq!(meta_type(&config).as_deref(), Some("api"));
+
+        // An explicit type wins, and a derived one isn't pulled back, even
+        // once tag_as_type is turned off
+        let typed = parse_task_file("---\ntype: bug\ntags: [api]\n---\n# Task A\n").unwrap();
+        assert_eq!(typed.config.effective_type(true), Some("bug"));
+        let meta = split_meta(&prepare_issue(&project.tasks[0], &task_file, &config, &SyncOptions::default()).body).0.unwrap();
+        assert!(meta_updates(&task_file.config, &meta).is_empty());
+        let retyped = IssueMeta { task_type: Some("feature".to_string()), ..meta };
+        assert_eq!(meta_updates(&task_file.config, &retyped), vec![("type", "\"feature\"".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_write_back_keeps_every_new_number() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        let mut content = "backend: github\nrepo: a/b\n---\n".to_string();
+        for i in 0..40 {
+            content.push_str(&format!("* [new] - Task {}\n", i));
+        }
+        // Identical lines are separate tasks, each needing its own number
+        content.push_str("* [new] - Fix typo\n- [new] - Fix typo ✅\n* [new] - Fix typo\n");
+        fs::write(&project_file, &content).unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.len(), 43);
+
+        let written = fs::read_to_string(&project_file).unwrap();
+        let numbers: Vec<Option<u64>> = parse_project_file(&written).unwrap().tasks.iter().map(|t| t.status.issue_id()).collect();
+        assert_eq!(numbers, (1..=43).map(Some).collect::<Vec<_>>());
+        assert!(written.ends_with("* [#41] - Fix typo\n- [#42] - Fix typo ✅\n* [#43] - Fix typo\n"), "{}", written);
+    }
+
+    #[test]
+    fn test_append_child_task_list() {
+        let body = "Epic description.\n\n- [x] #3 done already";
+
+        assert_eq!(
+            append_child_task_list(body, &[3, 4]).as_deref(),
+            Some("Epic description.\n\n- [x] #3 done already\n\n- [ ] #4")
+        );
+        assert_eq!(append_child_task_list(body, &[3]), None);
+        assert_eq!(append_child_task_list("", &[7]).as_deref(), Some("- [ ] #7"));
+    }
+
+    #[test]
+    fn test_epics_sync_first() {
+        let project = parse_project_file(
+            "backend: github\nrepo: test/epic\n---\n\n* [new] - tasks/child.md - Child\n* [new] - tasks/epic.md - Epic\n",
+        )
+        .unwrap();
+        let epics = HashMap::from([(PathBuf::from("tasks/child.md"), EpicRef::parse("tasks/epic.md"))]);
+
+        let order: Vec<_> = sync_order(&project.tasks, &epics).iter().map(|t| t.path.clone()).collect();
+        assert_eq!(order, vec![Some(PathBuf::from("tasks/epic.md")), Some(PathBuf::from("tasks/child.md"))]);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_policies() {
+        for (policy, created, skipped, errors) in [("error", 0, 0, 1), ("skip", 0, 1, 0), ("create_stub", 1, 0, 0)] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            let project = format!("backend: github\nrepo: a/b\non_missing_file: {}\n---\n* [new] - tasks/gone.md - Write the docs\n", policy);
+            fs::write(&project_file, &project).unwrap();
+
+            let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+            let result = engine.sync(&project_file).await.unwrap();
+
+            assert_eq!(result.created.len(), created, "{}", policy);
+            assert_eq!(result.skipped.len(), skipped, "{}", policy);
+            assert_eq!(result.errors.len(), errors, "{}", policy);
+            assert_eq!(dir.path().join("tasks/gone.md").exists(), policy == "create_stub");
+
+            if policy == "create_stub" {
+                let stub = parse_task_file(&fs::read_to_string(dir.path().join("tasks/gone.md")).unwrap()).unwrap();
+                assert_eq!(stub.title, "Write the docs");
+                assert_eq!(stub.config.issue_id, Some(1));
+
+                let untitled = TaskItem { description: None, ..parse_project_file(&project).unwrap().tasks[0].clone() };
+                assert_eq!(stub_task_file(&untitled), "---\n---\n# gone\n");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_come_from_clock() {
+        use crate::clock::FixedClock;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md - A\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        let created: DateTime<Utc> = "2025-03-01T09:00:00Z".parse().unwrap();
+        let edited: DateTime<Utc> = "2025-03-02T17:30:00Z".parse().unwrap();
+        let config = || parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap().config;
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf()).with_clock(FixedClock(created));
+        assert_eq!(engine.sync(&project_file).await.unwrap().created.len(), 1);
+        assert_eq!(config().created_at, Some(created.to_rfc3339()));
+        assert_eq!(config().updated_at, Some(created.to_rfc3339()));
+
+        // An edit since then (the file's mtime is now later than `created`) is
+        // synced at the clock's new time, keeping `created_at`
+        fs::write(dir.path().join("a.md"), fs::read_to_string(dir.path().join("a.md")).unwrap() + "More.\n").unwrap();
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf()).with_clock(FixedClock(edited));
+        assert_eq!(engine.sync(&project_file).await.unwrap().updated.len(), 1);
+        assert_eq!(config().created_at, Some(created.to_rfc3339()));
+        assert_eq!(config().updated_at, Some(edited.to_rfc3339()));
+    }
+
+    #[tokio::test]
+    async fn test_sync_writes_audit_record() {
+        use crate::clock::FixedClock;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md - A\n* [#4] - gone.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+        let finished: DateTime<Utc> = "2025-03-01T09:00:00Z".parse().unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf()).with_clock(FixedClock(finished));
+        engine.sync(&project_file).await.unwrap();
+
+        let audit: SyncAudit = serde_json::from_str(&fs::read_to_string(dir.path().join(AUDIT_FILE)).unwrap()).unwrap();
+        assert_eq!(audit.finished_at, finished.to_rfc3339());
+        assert_eq!(audit.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(audit.created.len(), 1);
+        assert_eq!((audit.created[0].task.as_str(), audit.created[0].issue), ("a.md", Some(1)));
+        assert_eq!((audit.errors[0].task.as_str(), audit.errors[0].issue), ("gone.md", Some(4)));
+        assert!(audit.errors[0].error.is_some());
+
+        // The path is configurable, relative to project.md
+        fs::write(&project_file, "backend: github\nrepo: a/b\naudit_file: logs/sync.json\n---\n* [#1] - a.md - A\n").unwrap();
+        engine.sync(&project_file).await.unwrap();
+        let audit: SyncAudit = serde_json::from_str(&fs::read_to_string(dir.path().join("logs/sync.json")).unwrap()).unwrap();
+        assert_eq!(audit.skipped.len() + audit.updated.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sync_reports_events() {
+        use std::sync::Arc;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md - A\n* [new] - missing.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf())
+            .with_events(move |event| seen.lock().unwrap().push(event));
+        let result = engine.sync(&project_file).await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], SyncEvent::Created(result.created[0].0.clone(), result.created[0].1));
+        assert!(matches!(&events[1], SyncEvent::Failed(task, _) if task.description.as_deref() == Some("B")));
+        assert_eq!(result.errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_epics_link_sub_issues_or_fall_back_to_task_lists() {
+        for (sub_issues, supported, linked) in [(true, true, true), (true, false, false), (false, true, false)] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            let project = format!(
+                "backend: github\nrepo: a/b\nsub_issues: {}\n---\n* [new] - child.md - Child\n* [new] - epic.md - Epic\n",
+                sub_issues
+            );
+            fs::write(&project_file, project).unwrap();
+            fs::write(dir.path().join("epic.md"), "---\n---\n# Epic\n").unwrap();
+            fs::write(dir.path().join("child.md"), "---\nepic: epic.md\n---\n# Child\n").unwrap();
+
+            let backend = MockBackend { sub_issues: supported.then(Default::default), ..Default::default() };
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf());
+            let result = engine.sync(&project_file).await.unwrap();
+            assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+            // The epic syncs first, so it is #1 and the child #2
+            let backend = &engine.backend;
+            let links = backend.sub_issues.as_ref().map(|links| links.lock().unwrap().clone()).unwrap_or_default();
+            assert_eq!(links == vec![(1, 2)], linked, "{} {}", sub_issues, supported);
+            // Without a sub-issue the child is listed in the epic body instead
+            assert_eq!(*backend.updated.lock().unwrap() == vec![1], !linked, "{} {}", sub_issues, supported);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_run_retries_failed_tasks() {
+        for retry_run in [0, 1] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#5] - a.md - A\n* [new] - b.md - B\n").unwrap();
+            fs::write(dir.path().join("a.md"), "---\nissue_id: 5\n---\n# A\n").unwrap();
+            fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+
+            // Only the first attempt's update of #5 fails
+            let backend = MockBackend { failures: Mutex::new(vec![ErrorKind::Other]), ..Default::default() };
+            let options = SyncOptions { retry_run, ..Default::default() };
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf()).with_options(options);
+            let result = engine.sync(&project_file).await.unwrap();
+
+            assert_eq!(result.created.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![1]);
+            if retry_run == 0 {
+                assert_eq!(result.errors.len(), 1);
+                assert!(result.updated.is_empty());
+                continue;
+            }
+
+            // The retry updates #5 and skips b.md, which the first attempt created
+            assert!(result.errors.is_empty(), "{:?}", result.errors);
+            assert_eq!(result.updated.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![5]);
+            assert!(result.skipped.is_empty());
+            assert_eq!(result.reasons[1].1, SyncReason::New);
+            assert_eq!(*engine.backend.updated.lock().unwrap(), vec![5]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_run_doesnt_resend_ambiguous_creates() {
+        for idempotent in [false, true] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            fs::write(&project_file, "backend: github\nrepo: a/b\nerror_policy:\n  server: fail\n---\n* [new] - a.md - A\n").unwrap();
+            fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+
+            let backend = MockBackend { create_failures: Mutex::new(vec![ErrorKind::Server]), ..Default::default() };
+            let options = SyncOptions { retry_run: 2, idempotent, ..Default::default() };
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf()).with_options(options);
+            let result = engine.sync(&project_file).await.unwrap();
+
+            // Only with --idempotent is the create sent again
+            assert_eq!(result.created.len(), usize::from(idempotent), "{:?}", result.errors);
+            assert_eq!(result.errors.len(), usize::from(!idempotent));
+            assert_eq!(*engine.backend.next_number.lock().unwrap(), u64::from(idempotent));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_task_without_front_matter() {
+        for (front_matter, created, errors) in [("required", 0, 1), ("optional", 1, 0)] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            let project = format!("backend: github\nrepo: a/b\nfront_matter: {}\n---\n* [new] - a.md - A\n", front_matter);
+            fs::write(&project_file, project).unwrap();
+            fs::write(dir.path().join("a.md"), "# Plain\n\nJust markdown.\n").unwrap();
+
+            let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+            let result = engine.sync(&project_file).await.unwrap();
+            assert_eq!(result.created.len(), created, "{}", front_matter);
+            assert_eq!(result.errors.len(), errors, "{}", front_matter);
+
+            // Syncing gives the file front matter to record the issue in
+            if front_matter == "optional" {
+                let task_file = parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap();
+                assert_eq!(task_file.config.issue_id, Some(1));
+                assert_eq!(task_file.title, "Plain");
+                assert_eq!(task_file.body, "Just markdown.");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_task_without_description() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md\n* [new] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# Title of A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# Title of B\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&project_file).unwrap(),
+            "backend: github\nrepo: a/b\n---\n* [#1] - a.md\n* [#2] - b.md - B\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_description_only_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(
+            &project_file,
+            "backend: github\nrepo: a/b\n---\n* [new] - Quick fix the header\n* [#4] - Bump the version\n",
+        )
+        .unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.len(), 1);
+        assert_eq!(result.created[0].0.path, None);
+        assert_eq!(result.created[0].1, 1);
+        assert_eq!(*engine.backend.updated.lock().unwrap(), vec![4]);
+        assert_eq!(
+            fs::read_to_string(&project_file).unwrap(),
+            "backend: github\nrepo: a/b\n---\n* [#1] - Quick fix the header\n* [#4] - Bump the version\n"
+        );
+        // No task files are created, just the audit record
+        let entries: Vec<_> = fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.contains(&".projectmd".into()));
+    }
+
+    #[tokio::test]
+    async fn test_repo_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [#4] - Bump the version\n").unwrap();
+        let options = SyncOptions { repo_check: true, ..Default::default() };
+
+        for (access, error) in [(RepoAccess::NotFound, "not found"), (RepoAccess::ReadOnly, "No write access")] {
+            let backend = MockBackend { access: Some(access), ..Default::default() };
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf()).with_options(options.clone());
+            let message = engine.sync(&project_file).await.unwrap_err().to_string();
+            assert!(message.contains(error), "{}", message);
+            assert!(engine.backend.updated.lock().unwrap().is_empty());
+        }
+
+        for access in [RepoAccess::Write, RepoAccess::Unchecked] {
+            forget_synced_descriptions(dir.path());
+            let backend = MockBackend { access: Some(access), ..Default::default() };
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf()).with_options(options.clone());
+            engine.sync(&project_file).await.unwrap();
+            assert_eq!(*engine.backend.updated.lock().unwrap(), vec![4]);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_error_policy_retry_and_skip() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(
+            &project_file,
+            "backend: github\nrepo: a/b\nerror_policy:\n  validation: skip\n---\n* [#4] - Bump the version\n",
+        )
+        .unwrap();
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+
+        // Server errors are retried by default
+        *engine.backend.failures.lock().unwrap() = vec![ErrorKind::Server, ErrorKind::Server];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(*engine.backend.updated.lock().unwrap(), vec![4]);
+
+        forget_synced_descriptions(dir.path());
+        *engine.backend.failures.lock().unwrap() = vec![ErrorKind::Validation];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.skipped.len(), 1);
+
+        // Retries give up after `max_retries`
+        forget_synced_descriptions(dir.path());
+        *engine.backend.failures.lock().unwrap() = vec![ErrorKind::Server; 4];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(engine.backend.failures.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ambiguous_create_failures_are_only_retried_when_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - a.md - A\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\n---\n# A\n").unwrap();
+
+        let engine = SyncEngine::new(MockBackend::default(), dir.path().to_path_buf());
+        *engine.backend.create_failures.lock().unwrap() = vec![ErrorKind::Server];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].1.contains("--idempotent"), "{}", result.errors[0].1);
+        assert!(result.created.is_empty());
+
+        // Rate limits reject the request, so retrying can't duplicate anything
+        *engine.backend.create_failures.lock().unwrap() = vec![ErrorKind::RateLimited];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![1]);
+
+        fs::write(&project_file, "backend: github\nrepo: a/b\n---\n* [new] - b.md - B\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\n---\n# B\n").unwrap();
+        let engine = engine.with_options(SyncOptions { idempotent: true, ..Default::default() });
+        *engine.backend.create_failures.lock().unwrap() = vec![ErrorKind::Network];
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_error_policy_recreates_missing_issues() {
+        use crate::backend::fs::FileSystemBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(
+            &project_file,
+            "backend: fs\nrepo: issues\nerror_policy:\n  not_found: recreate\n---\n* [#5] - a.md - A\n* [wip:#6] - Gone\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("a.md"), "---\nissue_id: 5\n---\n# A\n").unwrap();
+
+        let backend = FileSystemBackend::new(&dir.path().join("issues"));
+        let engine = SyncEngine::new(backend, dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.created.iter().map(|(_, n)| *n).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(
+            fs::read_to_string(&project_file).unwrap(),
+            "backend: fs\nrepo: issues\nerror_policy:\n  not_found: recreate\n---\n* [#1] - a.md - A\n* [wip:#2] - Gone\n"
+        );
+        let task_file = parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap();
+        assert_eq!(task_file.config.issue_id, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_detect_transferred_issues() {
+        let dir = tempfile::tempdir().unwrap();
+        let project_file = dir.path().join("project.md");
+        fs::write(&project_file, "backend: github\nrepo: acme/app\n---\n* [#2] - a.md - A\n").unwrap();
+        fs::write(dir.path().join("a.md"), "---\nissue_id: 2\n---\n# A\n").unwrap();
+
+        let transfer = Transfer { repo: "acme/tracker".to_string(), number: 12, url: "https://github.com/acme/tracker/issues/12".to_string() };
+        let backend = || MockBackend { transfers: HashMap::from([(2, transfer.clone())]), ..Default::default() };
+
+        // Neither run writes over the issue where it was moved to
+        let engine = SyncEngine::new(backend(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].1.contains("transferred to acme/tracker#12"), "{}", result.errors[0].1);
+        assert!(engine.backend.updated.lock().unwrap().is_empty());
+
+        let options = SyncOptions { follow_transfers: true, ..Default::default() };
+        let engine = SyncEngine::new(backend(), dir.path().to_path_buf()).with_options(options);
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.skipped.len(), 1);
+        assert!(engine.backend.updated.lock().unwrap().is_empty());
+        let task_file = parse_task_file(&fs::read_to_string(dir.path().join("a.md")).unwrap()).unwrap();
+        assert_eq!(task_file.config.repo.as_deref(), Some("acme/tracker"));
+        assert_eq!(task_file.config.issue_id, Some(12));
+
+        // project.md still says #2, but the followed task is skipped rather than failing
+        let engine = SyncEngine::new(backend(), dir.path().to_path_buf());
+        let result = engine.sync(&project_file).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.skipped.len(), 1);
+        assert!(engine.backend.updated.lock().unwrap().is_empty());
+
+        // Nor does a title-only update for a changed description
+        fs::write(&project_file, "backend: github\nrepo: acme/app\ntitle_source: description\n---\n* [#2] - a.md - A, renamed\n").unwrap();
+        for follow_transfers in [false, true] {
+            fs::write(dir.path().join("a.md"), "---\nissue_id: 2\nupdated_at: 2999-01-01T00:00:00Z\nsynced_description: A\n---\n# A\n").unwrap();
+            let options = SyncOptions { follow_transfers, ..Default::default() };
+            let engine = SyncEngine::new(backend(), dir.path().to_path_buf()).with_options(options);
+            let result = engine.sync(&project_file).await.unwrap();
+            assert_eq!(result.errors.is_empty(), follow_transfers, "{:?}", result.errors);
+            assert!(engine.backend.updated.lock().unwrap().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_absent_tags_keep_labels_and_empty_tags_clear_them() {
+        use crate::backend::fs::FileSystemBackend;
+
+        // Kept labels lose the WIP label all the same
+        for (front_matter, expected) in [
+            ("", vec!["help wanted", "api", "projectmd"]),
+            ("tags: [api]\n", vec!["api", "projectmd"]),
+            ("tags: []\n", vec!["projectmd"]),
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let project_file = dir.path().join("project.md");
+            fs::write(&project_file, "backend: fs\nrepo: issues\nmanaged_label: projectmd\n---\n* [#1] - a.md - A\n").unwrap();
+            fs::write(dir.path().join("a.md"), format!("---\n{}---\n# A\n", front_matter)).unwrap();
+
+            let backend = FileSystemBackend::new(&dir.path().join("issues"));
+            let labels_before = vec!["help wanted".to_string(), "in-progress".to_string(), "api".to_string()];
+            backend.create_issue("A", "", labels_before, Vec::new()).await.unwrap();
+
+            let engine = SyncEngine::new(backend, dir.path().to_path_buf());
+            let result = engine.sync(&project_file).await.unwrap();
+            assert!(result.errors.is_empty(), "{:?}", result.errors);
+            assert_eq!(engine.backend.get_issue(1).await.unwrap().labels, expected, "{:?}", front_matter);
+        }
+    }
+
+    #[test]
+    fn test_merge_labels() {
+        let current = ["bug".to_string(), "in-progress".to_string(), "p1".to_string()];
+        assert_eq!(merge_labels(&current, vec!["p1".to_string(), "managed".to_string()], &["in-progress"]), vec!["bug", "p1", "managed"]);
+        assert_eq!(merge_labels(&current, vec!["in-progress".to_string()], &["in-progress"]), vec!["bug", "p1", "in-progress"]);
+        assert_eq!(merge_labels(&current, Vec::new(), &["in-progress", "bug"]), vec!["p1"]);
+    }
+
+    #[test]
+    fn test_prepare_description_only_issue() {
+        let project = parse_project_file("backend: github\nrepo: a/b\n---\n* [wip:#2] - Quick fix\n").unwrap();
+        let task = &project.tasks[0];
+
+        let fields = prepare_issue(task, &description_task_file(task), &project.config, &SyncOptions::default());
+        assert_eq!(fields.title, "Quick fix");
+        assert_eq!(fields.body, "Quick fix");
+        assert_eq!(fields.labels, vec!["in-progress"]);
     }
 }
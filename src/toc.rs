@@ -0,0 +1,138 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::newline::{to_lf, NewlineStyle};
+
+/// Where `toc` puts the table of contents in project.md
+pub const TOC_MARKER: &str = "<!-- toc -->";
+/// Ends the generated table, so it can be replaced on the next run
+pub const TOC_END_MARKER: &str = "<!-- tocstop -->";
+
+/// A markdown heading: its level (1 for `#`) and text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub text: String,
+}
+
+/// Headings after project.md's front matter, skipping code blocks
+pub fn headings(content: &str) -> Vec<Heading> {
+    let mut lines = content.lines();
+    // Front matter ends at the first `---`, and may hold `#` comments
+    if content.lines().any(|line| line == "---") {
+        lines.by_ref().find(|line| *line == "---");
+    }
+
+    let mut headings = Vec::new();
+    let mut in_code = false;
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        } else if !in_code {
+            let level = line.chars().take_while(|c| *c == '#').count();
+            if let Some(text) = line[level..].strip_prefix(' ').filter(|_| (1..=6).contains(&level)) {
+                headings.push(Heading { level, text: text.trim().trim_end_matches('#').trim_end().to_string() });
+            }
+        }
+    }
+    headings
+}
+
+/// GitHub's anchor for a heading: lowercased, punctuation dropped and spaces
+/// turned into hyphens
+pub fn anchor(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// A nested list linking to each heading, indented relative to the top level
+pub fn render_toc(headings: &[Heading]) -> String {
+    let top = headings.iter().map(|h| h.level).min().unwrap_or(1);
+    // Repeated anchors get `-1`, `-2`, ... as on GitHub
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    headings
+        .iter()
+        .map(|heading| {
+            let base = anchor(&heading.text);
+            let count = seen.entry(base.clone()).or_insert(0);
+            let target = match *count {
+                0 => base,
+                n => format!("{}-{}", base, n),
+            };
+            *count += 1;
+            format!("{}- [{}](#{})\n", "  ".repeat(heading.level - top), heading.text, target)
+        })
+        .collect()
+}
+
+/// Write the table of contents of project.md content at its `<!-- toc -->`
+/// marker, replacing the one generated before
+///
+/// Running it again on its own output changes nothing. Line endings are kept.
+pub fn update_toc(content: &str) -> Result<String> {
+    let style = NewlineStyle::detect(content);
+    let content = to_lf(content);
+
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(start) = lines.iter().position(|line| line.trim() == TOC_MARKER) else {
+        anyhow::bail!("No {} marker in project.md; add one where the table of contents should go", TOC_MARKER);
+    };
+    let end = lines[start..].iter().position(|line| line.trim() == TOC_END_MARKER).map(|i| start + i + 1);
+
+    let toc = format!("{}\n{}{}", TOC_MARKER, render_toc(&headings(&content)), TOC_END_MARKER);
+    let mut updated: Vec<&str> = lines[..start].to_vec();
+    updated.push(&toc);
+    updated.extend(&lines[end.unwrap_or(start + 1)..]);
+
+    let mut updated = updated.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(style.apply(&updated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = "backend: github\nrepo: a/b\n# not a heading\n---\n\n# Roadmap\n\n<!-- toc -->\n\n## Auth & Login\n\n* [new] - tasks/auth.md - Auth\n\n```\n# code\n```\n\n### Tests\n\n## Auth & login\n";
+
+    #[test]
+    fn test_update_toc() {
+        let updated = update_toc(PROJECT).unwrap();
+        assert!(updated.contains(
+            "<!-- toc -->\n\
+             - [Roadmap](#roadmap)\n\
+             \x20 - [Auth & Login](#auth--login)\n\
+             \x20   - [Tests](#tests)\n\
+             \x20 - [Auth & login](#auth--login-1)\n\
+             <!-- tocstop -->\n\n## Auth & Login\n"
+        ), "{}", updated);
+
+        // Rerunning is a no-op, and a stale table is replaced
+        assert_eq!(update_toc(&updated).unwrap(), updated);
+        let renamed = updated.replace("### Tests", "### Testing");
+        assert_eq!(update_toc(&renamed).unwrap(), update_toc(&PROJECT.replace("### Tests", "### Testing")).unwrap());
+
+        let crlf = update_toc(&PROJECT.replace('\n', "\r\n")).unwrap();
+        assert_eq!(crlf, updated.replace('\n', "\r\n"));
+
+        assert!(update_toc("backend: github\n---\n# Roadmap\n").is_err());
+    }
+
+    #[test]
+    fn test_toc_round_trips_through_the_parser() {
+        let project = "backend: github\nrepo: a/b\n---\n\n<!-- toc -->\n\n## WIP items\n\n* [new] - Draft\n\n## New features\n\n## new\n";
+        let updated = update_toc(project).unwrap();
+        assert!(updated.contains("- [WIP items](#wip-items)\n- [New features](#new-features)\n- [new](#new)\n"), "{}", updated);
+
+        let parsed = crate::parser::parse_project_file(&updated).unwrap();
+        assert!(parsed.task_errors.is_empty(), "{:?}", parsed.task_errors);
+        assert_eq!(parsed.tasks.len(), 1);
+    }
+}
@@ -1,43 +1,443 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
+use crate::backend::error::ErrorKind;
+use crate::newline::{FinalNewline, LineEnding};
+
 /// Configuration from the main project.md YAML front matter
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectConfig {
+    /// Format version the file was written for (see `FORMAT_VERSION`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
     pub backend: String,
     pub repo: String,
+    /// Longest issue title allowed before `validate` warns; 0 is rejected
+    pub max_title_len: Option<NonZeroUsize>,
+    /// Label applied to `[wip:#N]` tasks (defaults to `in-progress`)
+    pub wip_label: Option<String>,
+    /// What comes before the issue number in markers, e.g. `GH-` for `[GH-42]` (defaults to `#`)
+    pub issue_prefix: Option<String>,
+    /// Label added to every issue projectmd creates or updates
+    pub managed_label: Option<String>,
+    /// Label per marker (`new`, `open`, `wip`) or flag (`archived`), so issue
+    /// labels mirror each task's local state
+    #[serde(default)]
+    pub status_label_map: HashMap<String, String>,
+    /// Assignee logins added to tasks carrying a tag, e.g. `team/platform: [alice]`
+    #[serde(default)]
+    pub tag_assignees: HashMap<String, Vec<String>>,
+    /// Backend-specific settings, e.g. `base_url` for GitHub Enterprise
+    #[serde(default)]
+    pub backend_config: HashMap<String, serde_json::Value>,
+    /// GitHub Projects (v2) board to place synced issues on
+    pub project_board: Option<ProjectBoardConfig>,
+    /// Line ending (`lf` or `crlf`) for written files; each file's own is kept by default
+    pub line_ending: Option<LineEnding>,
+    /// `single` to end project.md with exactly one newline when sync writes it
+    /// back; by default its trailing newlines are kept as they are
+    pub final_newline: Option<FinalNewline>,
+    /// Shell command each task body is piped through before it is pushed
+    pub body_preprocessor: Option<String>,
+    /// Git ref to point relative image paths in task bodies at, as raw.githubusercontent.com URLs
+    pub raw_image_ref: Option<String>,
+    /// Body for issues of task files with none, with `{{title}}` and `{{path}}`
+    /// filled in
+    pub default_body: Option<String>,
+    /// Logins that new issues with no assignees are assigned to in turn
+    pub round_robin_assignees: Option<Vec<String>>,
+    /// Use a task's first tag as its type when it sets no `type`
+    #[serde(default)]
+    pub tag_as_type: bool,
+    /// Color (hex, e.g. `1d76db`) per label prefix, e.g. `area/`, for labels projectmd creates
+    #[serde(default)]
+    pub label_color_rules: HashMap<String, String>,
+    /// Label each tag is pushed as, e.g. `p1: priority/high`; other tags are pushed as they are
+    #[serde(default)]
+    pub tag_labels: HashMap<String, String>,
+    /// YAML or JSON file with more `tag_labels` and `label_color_rules`, relative to project.md
+    pub label_map_file: Option<PathBuf>,
+    /// Heading of the project.md section new task lines are added under (defaults to `Tasks`)
+    pub tasks_section: Option<String>,
+    /// Defaults for every issue synced to `repo`
+    pub repo_defaults: Option<RepoDefaults>,
+    /// What sync does when a task fails, per kind of error
+    #[serde(default)]
+    pub error_policy: ErrorPolicy,
+    /// What sync does with tasks whose file doesn't exist
+    #[serde(default)]
+    pub on_missing_file: MissingFilePolicy,
+    /// Front matter fields every task file must set, e.g. `[type, priority]`
+    #[serde(default)]
+    pub required_fields: Vec<String>,
+    /// Task files larger than this many bytes fail to sync without being read
+    pub max_task_file_bytes: Option<u64>,
+    /// Where issue titles come from
+    #[serde(default)]
+    pub title_source: TitleSource,
+    /// Where each sync records what it did, relative to project.md (defaults to `.projectmd/last-sync.json`)
+    pub audit_file: Option<PathBuf>,
+    /// Link children to epics as native sub-issues where the backend supports them
+    #[serde(default)]
+    pub sub_issues: bool,
+    /// Whether task files without front matter are an error (the default) or plain markdown
+    #[serde(default)]
+    pub front_matter: FrontMatter,
     #[serde(flatten)]
-    pub extra: std::collections::HashMap<String, serde_yaml::Value>,
+    pub extra: HashMap<String, serde_yaml::Value>,
+}
+
+/// Contents of a `label_map_file`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LabelMap {
+    #[serde(default)]
+    pub tag_labels: HashMap<String, String>,
+    #[serde(default)]
+    pub label_color_rules: HashMap<String, String>,
+}
+
+/// Labels, assignees and milestone given to every issue synced to the project's repo
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepoDefaults {
+    /// Added to each task's own labels
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Used for tasks that resolve no assignees of their own
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// Title of the milestone to put issues in
+    pub milestone: Option<String>,
+}
+
+/// What to do with a task whose file is missing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingFilePolicy {
+    /// Report the task as failed
+    #[default]
+    Error,
+    /// Leave the task out of the sync, with a warning
+    Skip,
+    /// Write a task file titled with the task's description, then sync it
+    CreateStub,
+}
+
+/// What an issue's title is taken from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleSource {
+    /// The task file's `# heading`
+    #[default]
+    Title,
+    /// The task's description in project.md, or the file's title without one
+    Description,
+}
+
+/// Whether task files must start with YAML front matter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontMatter {
+    /// Files without front matter fail to parse
+    #[default]
+    Required,
+    /// Files without front matter are read as plain markdown with an empty config
+    Optional,
+}
+
+/// What to do with a task whose sync failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorAction {
+    /// Try again after a backoff, up to `max_retries` times
+    Retry,
+    /// Leave the task for the next sync, without counting it as an error
+    Skip,
+    /// Record the error and carry on with the next task
+    Fail,
+    /// Create a new issue for the task, for issues that no longer exist
+    Recreate,
+}
+
+/// Action per kind of error; unset kinds use the defaults from `action`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorPolicy {
+    pub not_found: Option<ErrorAction>,
+    pub validation: Option<ErrorAction>,
+    pub rate_limited: Option<ErrorAction>,
+    pub server: Option<ErrorAction>,
+    pub network: Option<ErrorAction>,
+    pub other: Option<ErrorAction>,
+    /// Retries per task before giving up (defaults to 3)
+    pub max_retries: Option<u32>,
+}
+
+/// Retries per task when `max_retries` isn't configured
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+impl ErrorPolicy {
+    /// Action for an error kind: transient errors are retried, the rest fail
+    pub fn action(&self, kind: ErrorKind) -> ErrorAction {
+        let (configured, default) = match kind {
+            ErrorKind::NotFound => (self.not_found, ErrorAction::Fail),
+            ErrorKind::Validation => (self.validation, ErrorAction::Fail),
+            ErrorKind::RateLimited => (self.rate_limited, ErrorAction::Retry),
+            ErrorKind::Server => (self.server, ErrorAction::Retry),
+            ErrorKind::Network => (self.network, ErrorAction::Retry),
+            ErrorKind::Other => (self.other, ErrorAction::Fail),
+        };
+        configured.unwrap_or(default)
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+}
+
+/// A GitHub Projects (v2) board whose column tracks each task's marker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectBoardConfig {
+    /// Node ID of the project, e.g. `PVT_kwDOABCD1234`
+    pub project_id: String,
+    /// Single-select field holding the column
+    #[serde(default = "default_status_field")]
+    pub status_field: String,
+    /// Column per marker: `new` (on create), `open` (`[#N]`) and `wip` (`[wip:#N]`)
+    #[serde(default)]
+    pub columns: HashMap<String, String>,
+}
+
+fn default_status_field() -> String {
+    "Status".to_string()
+}
+
+impl ProjectBoardConfig {
+    /// Column for a marker, falling back to `Todo` for new and `In Progress` for wip
+    pub fn column_for(&self, marker: &str) -> Option<&str> {
+        match (self.columns.get(marker), marker) {
+            (Some(column), _) => Some(column.as_str()),
+            (None, "new") => Some("Todo"),
+            (None, "wip") => Some("In Progress"),
+            (None, _) => None,
+        }
+    }
+}
+
+/// Section new task lines go under when `tasks_section` isn't configured
+pub const DEFAULT_TASKS_SECTION: &str = "Tasks";
+
+/// Label used for in-progress tasks when `wip_label` isn't configured
+pub const DEFAULT_WIP_LABEL: &str = "in-progress";
+
+/// Prefix of the issue number in markers when `issue_prefix` isn't configured
+pub const DEFAULT_ISSUE_PREFIX: &str = "#";
+
+impl ProjectConfig {
+    pub fn wip_label(&self) -> &str {
+        self.wip_label.as_deref().unwrap_or(DEFAULT_WIP_LABEL)
+    }
+
+    pub fn issue_prefix(&self) -> &str {
+        self.issue_prefix.as_deref().unwrap_or(DEFAULT_ISSUE_PREFIX)
+    }
+
+    /// Labels that follow a task's state: the WIP label and every
+    /// `status_label_map` label
+    pub fn state_labels(&self) -> Vec<&str> {
+        let mut labels = vec![self.wip_label()];
+        labels.extend(self.status_label_map.values().map(String::as_str));
+        labels
+    }
+
+    pub fn tasks_section(&self) -> &str {
+        self.tasks_section.as_deref().unwrap_or(DEFAULT_TASKS_SECTION)
+    }
 }
 
 /// Status of a task in the project file
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TaskStatus {
     /// Existing issue with ID
     Existing(u64),
+    /// Existing issue that is being worked on
+    InProgress(u64),
     /// New issue to be created
     New,
 }
 
+/// Bullet for task lines projectmd writes itself
+pub const DEFAULT_LIST_MARKER: &str = "*";
+
+fn default_list_marker() -> String {
+    DEFAULT_LIST_MARKER.to_string()
+}
+
+fn is_default_list_marker(marker: &str) -> bool {
+    marker == DEFAULT_LIST_MARKER
+}
+
+/// Split a list item line into its bullet (`*`, `-`, `+` or a number like
+/// `1.`) and the text after the space following it
+pub fn split_list_marker(line: &str) -> Option<(&str, &str)> {
+    let (marker, rest) = line.split_once(' ')?;
+    let numbered = marker
+        .strip_suffix('.')
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+    (matches!(marker, "*" | "-" | "+") || numbered).then_some((marker, rest))
+}
+
+/// Trailing checkboxes marking a task line completed
+pub const COMPLETED_MARKERS: [&str; 3] = [" ✅", " [x]", " [X]"];
+
+/// Split a line into its text and the completion checkbox it ends with, if any
+pub fn split_completed(line: &str) -> (&str, &str) {
+    COMPLETED_MARKERS
+        .iter()
+        .find_map(|marker| line.strip_suffix(marker).map(|text| (text, &line[text.len()..])))
+        .unwrap_or((line, ""))
+}
+
 /// A single task item from the bulleted list
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskItem {
     pub status: TaskStatus,
-    pub path: PathBuf,
-    pub description: String,
+    /// The task file, or `None` for a description-only task
+    pub path: Option<PathBuf>,
+    /// Always set for description-only tasks; tasks with a file may omit it
+    pub description: Option<String>,
+    /// The line's bullet, kept when the line is rewritten
+    #[serde(default = "default_list_marker", skip_serializing_if = "is_default_list_marker")]
+    pub list_marker: String,
+    /// The line ends with a ` ✅` or ` [x]` checkbox, so sync closes its issue
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub completed: bool,
+}
+
+impl TaskItem {
+    /// A task with the default bullet and no completion checkbox
+    pub fn new(status: TaskStatus, path: Option<PathBuf>, description: Option<String>) -> Self {
+        Self { status, path, description, list_marker: default_list_marker(), completed: false }
+    }
+
+    /// The task's line as it appears in project.md, with the default `#` issue
+    /// prefix and without its completion checkbox
+    pub fn line(&self) -> String {
+        self.line_with(&self.status.marker())
+    }
+
+    /// The task's line as it appears in a project.md using `config`
+    pub fn line_in(&self, config: &ProjectConfig) -> String {
+        self.line_with(&format_marker(&self.status, config))
+    }
+
+    fn line_with(&self, marker: &str) -> String {
+        match (&self.path, &self.description) {
+            (Some(path), Some(description)) => format!("{} {} - {} - {}", self.list_marker, marker, path.display(), description),
+            (Some(path), None) => format!("{} {} - {}", self.list_marker, marker, path.display()),
+            (None, description) => format!("{} {} - {}", self.list_marker, marker, description.as_deref().unwrap_or_default()),
+        }
+    }
+
+    /// How the task is referred to in output: its path, or its description
+    pub fn name(&self) -> String {
+        match &self.path {
+            Some(path) => path.display().to_string(),
+            None => format!("\"{}\"", self.description.as_deref().unwrap_or_default()),
+        }
+    }
+
+    /// The description, or `title` (the task file's) for tasks that omit it
+    pub fn description_or<'a>(&'a self, title: &'a str) -> &'a str {
+        self.description.as_deref().unwrap_or(title)
+    }
 }
 
 /// YAML front matter from individual task files
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskFileConfig {
     pub issue_id: Option<u64>,
     #[serde(rename = "type")]
     pub task_type: Option<String>,
     pub tags: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignees: Option<Vec<String>>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
+    /// Whether the task was last synced as `[wip:#N]` (managed by projectmd)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub in_progress: Option<bool>,
+    /// Parent epic, as a task path or an issue number
+    #[serde(default, deserialize_with = "string_or_number", skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
+    /// Size of the task in any unit, e.g. points, for `burndown` (defaults to 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<f64>,
+    /// Date the task should be done by, e.g. `2025-03-01`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_date: Option<String>,
+    /// Repo the task's issue belongs in; must be the project's `repo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    /// Hide the task from `status` and `tasks` unless `--include-archived` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<bool>,
+    /// Issue this task duplicates; sync closes the task's issue as not planned
+    /// with a `Duplicate of #N` comment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<u64>,
+    /// Never change `created_at` once set, even with `sync --reset-timestamps`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freeze_created_at: Option<bool>,
+    /// The project.md description last synced as the title, with
+    /// `title_source: description` (managed by projectmd)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synced_description: Option<String>,
     #[serde(flatten)]
-    pub extra: std::collections::HashMap<String, serde_yaml::Value>,
+    pub extra: HashMap<String, serde_yaml::Value>,
+}
+
+impl TaskFileConfig {
+    /// The task's `type`, or with `tag_as_type` its first tag when it has none
+    pub fn effective_type(&self, tag_as_type: bool) -> Option<&str> {
+        let first_tag = || self.tags.iter().flatten().next().filter(|_| tag_as_type);
+        self.task_type.as_ref().or_else(first_tag).map(String::as_str)
+    }
+}
+
+/// Accept `epic: 12` as well as `epic: "#12"` or `epic: tasks/auth.md`
+fn string_or_number<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_yaml::Value>::deserialize(deserializer)?;
+    match value {
+        None | Some(serde_yaml::Value::Null) => Ok(None),
+        Some(serde_yaml::Value::String(s)) => Ok(Some(s)),
+        Some(serde_yaml::Value::Number(n)) => Ok(Some(n.to_string())),
+        Some(other) => Err(serde::de::Error::custom(format!(
+            "expected a task path or issue number, got {:?}", other
+        ))),
+    }
+}
+
+/// Reference from a task to its parent epic
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpicRef {
+    /// An issue number, written as `12` or `"#12"`
+    Issue(u64),
+    /// Path of another task in project.md
+    Task(PathBuf),
+}
+
+impl EpicRef {
+    pub fn parse(reference: &str) -> Self {
+        let reference = reference.trim();
+        match reference.trim_start_matches('#').parse::<u64>() {
+            Ok(number) => EpicRef::Issue(number),
+            Err(_) => EpicRef::Task(PathBuf::from(reference)),
+        }
+    }
 }
 
 /// A parsed task file
@@ -53,6 +453,8 @@ pub struct TaskFile {
 pub struct ProjectMd {
     pub config: ProjectConfig,
     pub tasks: Vec<TaskItem>,
+    /// Lines that look like tasks but couldn't be parsed, as (line number, error)
+    pub task_errors: Vec<(usize, String)>,
 }
 
 impl TaskStatus {
@@ -60,10 +462,37 @@ impl TaskStatus {
         matches!(self, TaskStatus::New)
     }
 
+    pub fn is_in_progress(&self) -> bool {
+        matches!(self, TaskStatus::InProgress(_))
+    }
+
     pub fn issue_id(&self) -> Option<u64> {
         match self {
-            TaskStatus::Existing(id) => Some(*id),
+            TaskStatus::Existing(id) | TaskStatus::InProgress(id) => Some(*id),
             TaskStatus::New => None,
         }
     }
+
+    /// The marker as written in project.md with the default issue prefix,
+    /// e.g. `[#12]` or `[new]`
+    pub fn marker(&self) -> String {
+        self.marker_with(DEFAULT_ISSUE_PREFIX)
+    }
+
+    /// The marker with issue numbers written after `prefix`, e.g. `[GH-12]`
+    pub fn marker_with(&self, prefix: &str) -> String {
+        match self {
+            TaskStatus::Existing(id) => format!("[{}{}]", prefix, id),
+            TaskStatus::InProgress(id) => format!("[wip:{}{}]", prefix, id),
+            TaskStatus::New => "[new]".to_string(),
+        }
+    }
+}
+
+/// The marker for a status in a project.md using `config`'s `issue_prefix`
+///
+/// Both writing markers and matching existing lines to replace go through this,
+/// so they agree on the format.
+pub fn format_marker(status: &TaskStatus, config: &ProjectConfig) -> String {
+    status.marker_with(config.issue_prefix())
 }
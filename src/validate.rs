@@ -0,0 +1,231 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::encoding::read_utf8_within;
+use crate::parser::parse_task_file_with;
+use crate::sync::{description_task_file, missing_required_fields};
+use crate::types::ProjectMd;
+
+/// Problems found while validating a project and its task files
+///
+/// Each problem is keyed by the task's `TaskItem::name`.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<(String, String)>,
+    pub warnings: Vec<(String, String)>,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    pub fn print(&self) {
+        for (task, warning) in &self.warnings {
+            println!("  [WARN] {}: {}", task, warning);
+        }
+
+        for (task, error) in &self.errors {
+            println!("  [ERROR] {}: {}", task, error);
+        }
+
+        if !self.errors.is_empty() || !self.warnings.is_empty() {
+            println!();
+        }
+        println!("{} errors, {} warnings", self.errors.len(), self.warnings.len());
+    }
+}
+
+/// Check every task file referenced by the project without syncing
+pub fn validate_project(project_root: &Path, project: &ProjectMd) -> ValidationReport {
+    let mut report = ValidationReport::default();
+
+    for task in &project.tasks {
+        let task_file = match &task.path {
+            Some(path) => {
+                let content = match read_utf8_within(project_root.join(path), project.config.max_task_file_bytes) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        report.errors.push((task.name(), format!("Failed to read task file: {}", e)));
+                        continue;
+                    }
+                };
+
+                match parse_task_file_with(&content, project.config.front_matter) {
+                    Ok(task_file) => task_file,
+                    Err(e) => {
+                        report.errors.push((task.name(), format!("{:#}", e)));
+                        continue;
+                    }
+                }
+            }
+            None => description_task_file(task),
+        };
+
+        let missing = missing_required_fields(&task_file.config, &project.config);
+        if task.path.is_some() && !missing.is_empty() {
+            report.errors.push((task.name(), format!("missing required front matter fields: {}", missing.join(", "))));
+        }
+
+        if let Some(max) = project.config.max_title_len {
+            let len = task_file.title.chars().count();
+            if len > max.get() {
+                report.warnings.push((
+                    task.name(),
+                    format!("title is {} characters, longer than max_title_len ({})", len, max),
+                ));
+            }
+        }
+    }
+
+    for (line, error) in &project.task_errors {
+        report.errors.push((format!("line {}", line), error.clone()));
+    }
+
+    for (issue_id, tasks) in find_duplicate_issue_ids(project_root, project) {
+        for task in &tasks {
+            report.errors.push((
+                task.clone(),
+                format!("issue #{} is also claimed by {}", issue_id, others(&tasks, task)),
+            ));
+        }
+    }
+
+    report
+}
+
+fn others(tasks: &[String], task: &str) -> String {
+    tasks.iter().filter(|t| *t != task).cloned().collect::<Vec<_>>().join(", ")
+}
+
+/// Issues claimed by more than one task, with the names of the tasks claiming them
+///
+/// A task claims the `issue_id` in its front matter, or else the number in its
+/// project.md marker. Unreadable task files fall back to their marker.
+pub fn find_duplicate_issue_ids(project_root: &Path, project: &ProjectMd) -> BTreeMap<u64, Vec<String>> {
+    let mut claims: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+
+    for task in &project.tasks {
+        // A file listed twice is the same task, not a conflicting one
+        if task.path.is_some() && !seen.insert(&task.path) {
+            continue;
+        }
+
+        let file_issue_id = task.path.as_ref().and_then(|path| {
+            let content = read_utf8_within(project_root.join(path), project.config.max_task_file_bytes).ok()?;
+            parse_task_file_with(&content, project.config.front_matter).ok()?.config.issue_id
+        });
+
+        if let Some(issue_id) = file_issue_id.or(task.status.issue_id()) {
+            claims.entry(issue_id).or_default().push(task.name());
+        }
+    }
+
+    claims.retain(|_, tasks| tasks.len() > 1);
+    claims
+}
+
+/// Fail if any task line in project.md couldn't be parsed
+///
+/// Syncing the rest would silently skip the broken task, or create a second
+/// issue for it once its line is fixed.
+pub fn check_task_errors(project: &ProjectMd) -> Result<()> {
+    if project.task_errors.is_empty() {
+        return Ok(());
+    }
+
+    let lines: Vec<String> = project
+        .task_errors
+        .iter()
+        .map(|(line, error)| format!("  - line {}: {}", line, error))
+        .collect();
+
+    anyhow::bail!(
+        "project.md has task lines that couldn't be parsed:\n{}",
+        lines.join("\n")
+    )
+}
+
+/// Fail if several tasks would sync to the same issue
+///
+/// Otherwise each sync would overwrite the issue with whichever task ran last.
+pub fn check_duplicate_issue_ids(project_root: &Path, project: &ProjectMd) -> Result<()> {
+    let duplicates = find_duplicate_issue_ids(project_root, project);
+
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+
+    let lines: Vec<String> = duplicates
+        .iter()
+        .map(|(issue_id, tasks)| format!("  - #{}: {}", issue_id, tasks.join(", ")))
+        .collect();
+
+    anyhow::bail!(
+        "Multiple tasks point to the same issue:\n{}",
+        lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_project_file;
+    use std::fs;
+
+    #[test]
+    fn test_title_length_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("short.md"), "---\n---\n# Short title\n").unwrap();
+        fs::write(dir.path().join("long.md"), "---\n---\n# A title that is far too long for the team\n").unwrap();
+
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\nmax_title_len: 20\n---\n* [new] - short.md - Short\n* [new] - long.md - Long\n* [new] - missing.md - Missing\n",
+        )
+        .unwrap();
+
+        let report = validate_project(dir.path(), &project);
+
+        assert_eq!(report.warnings.len(), 1);
+        assert_eq!(report.warnings[0].0, "long.md");
+        assert!(report.warnings[0].1.contains("longer than max_title_len (20)"));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, "missing.md");
+    }
+
+    #[test]
+    fn test_task_errors_reported() {
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\n---\n* [new] - Good\n* [wip] - bad.md - Bad\n",
+        )
+        .unwrap();
+
+        let report = validate_project(Path::new("."), &project);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, "line 5");
+        assert!(check_task_errors(&project).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_issue_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "---\nissue_id: 5\n---\n# A\n").unwrap();
+        fs::write(dir.path().join("b.md"), "---\nissue_id: 5\n---\n# B\n").unwrap();
+        fs::write(dir.path().join("c.md"), "---\nissue_id: 6\n---\n# C\n").unwrap();
+
+        let project = parse_project_file(
+            "backend: github\nrepo: a/b\n---\n* [#5] - a.md - A\n* [new] - b.md - B\n* [#6] - c.md - C\n* [#6] - c.md - C again\n",
+        )
+        .unwrap();
+
+        let duplicates = find_duplicate_issue_ids(dir.path(), &project);
+        assert_eq!(duplicates, BTreeMap::from([(5, vec!["a.md".to_string(), "b.md".to_string()])]));
+        assert!(check_duplicate_issue_ids(dir.path(), &project).is_err());
+
+        let report = validate_project(dir.path(), &project);
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0], ("a.md".to_string(), "issue #5 is also claimed by b.md".to_string()));
+    }
+}
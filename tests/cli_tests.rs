@@ -0,0 +1,444 @@
+use projectmd::parser::parse_task_file;
+use std::fs;
+use std::process::Command;
+
+fn projectmd(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_projectmd")).args(args).output().unwrap()
+}
+
+#[test]
+fn test_chdir_targets_a_subproject() {
+    let dir = tempfile::tempdir().unwrap();
+    let subproject = dir.path().join("services/api");
+    fs::create_dir_all(subproject.join("tasks")).unwrap();
+    fs::write(subproject.join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n").unwrap();
+    fs::write(subproject.join("tasks/a.md"), "---\n---\n# Task A\n").unwrap();
+
+    // The default project file, task paths and the fs backend's issue
+    // directory are all found under the subproject
+    let output = projectmd(&["-C", subproject.to_str().unwrap(), "sync"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(fs::read_to_string(subproject.join("project.md")).unwrap().contains("* [#1] - tasks/a.md - A"));
+    let task_file = parse_task_file(&fs::read_to_string(subproject.join("tasks/a.md")).unwrap()).unwrap();
+    assert_eq!(task_file.config.issue_id, Some(1));
+    assert!(subproject.join("issues").exists());
+    assert!(!dir.path().join("issues").exists());
+
+    let output = projectmd(&["-C", dir.path().join("missing").to_str().unwrap(), "tasks"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to change to directory"));
+}
+
+#[test]
+fn test_verify_finds_and_resets_missing_issues() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\n---\n# Task A\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+
+    // #1 was just created; #7 never existed
+    let project = dir.path().join("project.md");
+    let content = fs::read_to_string(&project).unwrap() + "* [wip:#7] - Gone\n";
+    fs::write(&project, &content).unwrap();
+
+    let output = projectmd(&["-C", root, "verify"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[MISSING] #7"), "{}", stdout);
+    assert!(!stdout.contains("#1 "), "{}", stdout);
+    assert!(!projectmd(&["-C", root, "verify", "--fix"]).status.success());
+    assert_eq!(fs::read_to_string(&project).unwrap(), content);
+
+    let output = projectmd(&["-C", root, "verify", "--fix", "--confirm"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(fs::read_to_string(&project).unwrap(), content.replace("[wip:#7]", "[new]"));
+    assert!(projectmd(&["-C", root, "verify"]).status.success());
+}
+
+#[test]
+fn test_sync_summary_only_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [new] - Quick fix\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\n---\n# Task A\n").unwrap();
+
+    let output = projectmd(&["-C", root, "sync", "--output", "summary-only"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "created=2 updated=0 skipped=0 queued=0 errors=0\n");
+
+    // Failures still fail the command
+    fs::remove_file(dir.path().join("tasks/a.md")).unwrap();
+    let output = projectmd(&["-C", root, "sync", "--output", "summary-only"]);
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "created=0 updated=0 skipped=1 queued=0 errors=1\n");
+}
+
+#[test]
+fn test_sync_dump_plan_md() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [new] - tasks/b.md - B\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\n---\n# Task A\n\nFirst.\n").unwrap();
+    fs::write(dir.path().join("tasks/b.md"), "---\n---\n# Task B\n\nSecond.\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+
+    // One task renamed, one unchanged and one new
+    let a = dir.path().join("tasks/a.md");
+    fs::write(&a, fs::read_to_string(&a).unwrap().replace("updated_at", "old_updated_at").replace("# Task A", "# Task A, renamed")).unwrap();
+    fs::write(dir.path().join("tasks/c.md"), "---\n---\n# Task C\n").unwrap();
+    let project = dir.path().join("project.md");
+    fs::write(&project, fs::read_to_string(&project).unwrap() + "* [new] - tasks/c.md - C\n").unwrap();
+    let before = fs::read_to_string(&project).unwrap();
+
+    let output = projectmd(&["-C", root, "sync", "--dump-plan-md", "plan.md"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let plan = fs::read_to_string(dir.path().join("plan.md")).unwrap();
+    assert!(plan.contains("1 to create, 1 to update, 0 to close, 1 unchanged."), "{}", plan);
+    assert!(plan.contains("## Create\n\n### tasks/c.md\n\n- Title: Task C\n"), "{}", plan);
+    assert!(plan.contains("### #1 tasks/a.md\n\n````diff\n-# Task A\n+# Task A, renamed\n"), "{}", plan);
+    assert!(plan.contains("## Unchanged\n\n- tasks/b.md\n"), "{}", plan);
+
+    // Like a dry run, nothing was synced
+    assert_eq!(fs::read_to_string(&project).unwrap(), before);
+}
+
+#[test]
+fn test_dry_run_passes_once_description_only_tasks_sync() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let project = dir.path().join("project.md");
+    fs::write(&project, "backend: fs\nrepo: issues\n---\n\n* [new] - Quick fix\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+
+    let output = projectmd(&["-C", root, "sync", "--dry-run", "--dry-run-exit-code"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+
+    // Until its description changes
+    fs::write(&project, fs::read_to_string(&project).unwrap().replace("Quick fix", "Quick fix, revised")).unwrap();
+    let output = projectmd(&["-C", root, "sync", "--dry-run", "--dry-run-exit-code"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[UPDATE] #1"), "{}", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn test_dry_run_reports_pending_closes() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    let project = dir.path().join("project.md");
+    fs::write(&project, "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\n---\n# Task A\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+
+    // Checking the task off leaves its file unchanged, but sync would close its issue
+    fs::write(&project, fs::read_to_string(&project).unwrap().replace(" - A\n", " - A ✅\n")).unwrap();
+    let output = projectmd(&["-C", root, "sync", "--dry-run", "--dry-run-exit-code", "--dump-plan-md", "plan.md"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[CLOSE] #1 tasks/a.md\n"), "{}", stdout);
+    assert!(stdout.contains("1 pending changes"), "{}", stdout);
+    let plan = fs::read_to_string(dir.path().join("plan.md")).unwrap();
+    assert!(plan.contains("0 to create, 0 to update, 1 to close, 1 unchanged.") && plan.contains("## Close\n\n- #1 tasks/a.md\n"), "{}", plan);
+
+    // Once closed, there's nothing left to do
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+    let output = projectmd(&["-C", root, "sync", "--dry-run", "--dry-run-exit-code"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stdout));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("[CLOSE]"));
+}
+
+#[test]
+fn test_status_ndjson_prints_a_line_per_task() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [#4] - Quick fix\n* [wip:#5] - tasks/b.md\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\ntags: [api]\n---\n# Task A\n").unwrap();
+    fs::write(dir.path().join("tasks/b.md"), "---\n---\n# Task B\n").unwrap();
+
+    let output = projectmd(&["-C", root, "status", "-v", "--format", "ndjson"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let tasks: Vec<serde_json::Value> = stdout.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[0]["details"]["tags"], serde_json::json!(["api"]));
+    assert_eq!(tasks[1]["issue_number"], 4);
+    assert_eq!(tasks[2]["description"], "Task B");
+
+    let output = projectmd(&["-C", root, "status", "--format", "ndjson", "--limit", "2"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn test_sync_render_to_writes_issue_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [#3] - Quick fix\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\ntype: bug\ntags: [api]\n---\n# Task A\n\nFirst.\n").unwrap();
+
+    let output = projectmd(&["-C", root, "sync", "--render-to", "rendered"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("rendered/tasks/a.md.issue.md")).unwrap(),
+        "---\ntitle: Task A\nlabels:\n- api\nassignees: []\n---\n\
+         <!-- projectmd-meta\npath: tasks/a.md\ntype: bug\ntags:\n- api\n-->\n\n\
+         First.\n\n<!-- projectmd: key=projectmd-76e12978fbd36fb0 path=tasks/a.md -->"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("rendered/description-1.issue.md")).unwrap(),
+        "---\ntitle: Quick fix\nlabels: []\nassignees: []\n---\nQuick fix"
+    );
+
+    // Like any dry run, nothing was synced
+    assert!(!dir.path().join("issues").exists());
+    assert!(fs::read_to_string(dir.path().join("project.md")).unwrap().contains("[new] - tasks/a.md"));
+}
+
+#[test]
+fn test_render_to_keeps_files_inside_the_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(dir.path().join("shared")).unwrap();
+    fs::create_dir(&root).unwrap();
+    fs::write(root.join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - ../shared/b.md - B\n").unwrap();
+    fs::write(dir.path().join("shared/b.md"), "---\n---\n# Task B\n").unwrap();
+
+    let output = projectmd(&["-C", root.to_str().unwrap(), "sync", "--render-to", "rendered"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("../shared/b.md: not rendered: the path leads outside rendered"));
+    assert!(!root.join("shared").exists());
+}
+
+#[test]
+fn test_tags_counts_each_tag_once_per_task() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tags");
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    for file in ["project.md", "tasks/deploy.md", "tasks/servers.md", "tasks/login.md", "tasks/notes.md"] {
+        fs::copy(fixture.join(file), dir.path().join(file)).unwrap();
+    }
+
+    let output = projectmd(&["-C", root, "tags", "--format", "json"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let counts: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        counts,
+        serde_json::json!([
+            {"tag": "ci", "tasks": 2},
+            {"tag": "infra", "tasks": 2},
+            {"tag": "frontend", "tasks": 1},
+            {"tag": "infrastructure", "tasks": 1},
+        ])
+    );
+
+    // Read again from the cache, as text
+    let output = projectmd(&["-C", root, "tags"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2  ci\n2  infra\n1  frontend\n1  infrastructure\n");
+}
+
+#[test]
+fn test_dry_run_output_grows_with_verbosity() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [new] - tasks/b.md - B\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\ntags: [api]\n---\n# Task A\n\nFirst.\n").unwrap();
+    fs::write(dir.path().join("tasks/b.md"), "---\n---\n# Task B\n\nSecond.\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+    fs::write(dir.path().join("tasks/a.md"), "---\ntags: [api]\nissue_id: 1\n---\n# Task A\n\nFirst, edited.\n").unwrap();
+
+    let dry_run = |flags: &[&str]| {
+        let output = projectmd(&[&["-C", root, "sync", "--dry-run"], flags].concat());
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let (brief, verbose, full) = (dry_run(&[]), dry_run(&["-v"]), dry_run(&["-vv"]));
+
+    assert!(brief.contains("[UPDATE] #1 tasks/a.md"), "{}", brief);
+    assert!(!brief.contains("Labels:"), "{}", brief);
+    assert!(verbose.contains("       Labels: api\n"), "{}", verbose);
+    assert!(!verbose.contains("First"), "{}", verbose);
+    // The body only for pending tasks, diffed against the issue it would update
+    assert!(full.contains("       Diff:\n"), "{}", full);
+    assert!(full.contains("         -First.\n"), "{}", full);
+    assert!(full.contains("         +First, edited.\n"), "{}", full);
+    assert!(!full.contains("Second."), "{}", full);
+    assert!(brief.len() < verbose.len() && verbose.len() < full.len());
+}
+
+#[test]
+fn test_gaps_compares_tracked_and_remote_issues() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    let project = dir.path().join("project.md");
+    fs::write(&project, "backend: fs\nrepo: issues\n---\n\n* [new] - First\n* [new] - Second\n").unwrap();
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+    fs::write(&project, fs::read_to_string(&project).unwrap().replace("[#2]", "[#7]")).unwrap();
+
+    let output = projectmd(&["-C", root, "gaps"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Tracked but missing remotely (1):\n  - #7 \"Second\"\n\nRemote but untracked (1):\n  - #2 Second\n"
+    );
+}
+
+#[test]
+fn test_dry_run_doesnt_read_oversized_task_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\nmax_task_file_bytes: 64\n---\n\n* [new] - tasks/a.md - A\n").unwrap();
+    // Valid, so only the size guard tells it apart
+    fs::write(dir.path().join("tasks/a.md"), format!("---\n---\n# Task A\n\n{}\n", "Long. ".repeat(20))).unwrap();
+
+    let output = projectmd(&["-C", root, "sync", "--dry-run", "-v"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("over max_task_file_bytes (64)"), "{}", stdout);
+    assert!(!stdout.contains("Title: Task A"), "{}", stdout);
+}
+
+#[test]
+fn test_adopt_links_existing_issues() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [new] - tasks/a.md - A\n* [new] - tasks/b.md - B\n* [new] - tasks/c.md - C\n").unwrap();
+    for name in ["a", "b", "c"] {
+        fs::write(dir.path().join(format!("tasks/{}.md", name)), format!("---\n---\n# Task {}\n", name)).unwrap();
+    }
+    assert!(projectmd(&["-C", root, "sync"]).status.success());
+
+    // Forget #2 and #3, leaving a line for a new task and a file linked to #1
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [#1] - tasks/a.md - A\n* [new] - tasks/d.md - D\n").unwrap();
+    fs::remove_file(dir.path().join("tasks/b.md")).unwrap();
+    fs::remove_file(dir.path().join("tasks/c.md")).unwrap();
+    fs::write(dir.path().join("tasks/e.md"), "---\nissue_id: 1\n---\n# E\n").unwrap();
+
+    let output = projectmd(&["-C", root, "adopt", "#2", "tasks/e.md"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("tasks/e.md already has issue_id: 1"));
+    assert_eq!(fs::read_to_string(dir.path().join("tasks/e.md")).unwrap(), "---\nissue_id: 1\n---\n# E\n");
+
+    // An existing line gets the number; a new file gets a line of its own
+    let output = projectmd(&["-C", root, "adopt", "#2", "tasks/d.md"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let output = projectmd(&["-C", root, "adopt", "#3", "tasks/c.md"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let project_md = fs::read_to_string(dir.path().join("project.md")).unwrap();
+    assert!(project_md.contains("* [#2] - tasks/d.md - D\n"), "{}", project_md);
+    assert!(project_md.ends_with("* [#3] - tasks/c.md - Task c\n"), "{}", project_md);
+    assert_eq!(fs::read_to_string(dir.path().join("tasks/d.md")).unwrap(), "---\nissue_id: 2\n---\n# Task b\n");
+    assert_eq!(fs::read_to_string(dir.path().join("tasks/c.md")).unwrap(), "---\nissue_id: 3\n---\n# Task c\n");
+}
+
+#[test]
+fn test_backend_config_flag_sets_the_api_root() {
+    // Stands in for a GitHub Enterprise server
+    let (port, request) = record_one_request();
+
+    // The flag wins over project.md's unreachable base_url
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("project.md"), "backend: github\nrepo: a/b\nbackend_config:\n  base_url: http://127.0.0.1:1/api/v3\n---\n").unwrap();
+    let backend_config = format!(r#"{{"base_url": "http://127.0.0.1:{}/api/v3"}}"#, port);
+    projectmd(&["-C", dir.path().to_str().unwrap(), "--github-token", "token", "--backend-config", &backend_config, "ratelimit"]);
+    // Unblocks the server if the request went elsewhere
+    drop(std::net::TcpStream::connect(("127.0.0.1", port)));
+
+    let request = request.join().unwrap();
+    assert!(request.starts_with("GET /api/v3/rate_limit HTTP/1.1\r\n"), "{}", request);
+}
+
+/// Listen like a GitHub API server, returning the port and the head of the
+/// first request, which is answered with a 404
+fn record_one_request() -> (u16, std::thread::JoinHandle<String>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = server.local_addr().unwrap().port();
+    let request = std::thread::spawn(move || {
+        let (mut stream, _) = server.accept().unwrap();
+        let mut head = String::new();
+        let mut reader = BufReader::new(&mut stream);
+        while reader.read_line(&mut head).is_ok_and(|read| read > 0) && !head.ends_with("\r\n\r\n") {}
+        let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        head
+    });
+    (port, request)
+}
+
+/// Run projectmd with GITHUB_TOKEN unset and GITHUB_TOKEN_FILE set as given
+fn projectmd_with_token_file_env(args: &[&str], token_file: Option<&std::path::Path>) -> std::process::Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_projectmd"));
+    command.args(args).env_remove("GITHUB_TOKEN").env_remove("GITHUB_TOKEN_FILE");
+    if let Some(path) = token_file {
+        command.env("GITHUB_TOKEN_FILE", path);
+    }
+    command.output().unwrap()
+}
+
+#[test]
+fn test_token_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("project.md"), "backend: fs\nrepo: issues\n---\n").unwrap();
+    let missing = dir.path().join("missing");
+    let empty = dir.path().join("empty");
+    fs::write(&empty, " \n").unwrap();
+
+    let from_flag = projectmd_with_token_file_env(&["-C", root, "--github-token-file", missing.to_str().unwrap(), "sync", "--dry-run"], None);
+    let from_env = projectmd_with_token_file_env(&["-C", root, "sync", "--dry-run"], Some(&missing));
+    for output in [from_flag, from_env] {
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to read GitHub token file"), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let output = projectmd_with_token_file_env(&["-C", root, "--github-token-file", empty.to_str().unwrap(), "sync", "--dry-run"], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is empty"), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // An explicit --github-token wins over GITHUB_TOKEN_FILE, so the missing
+    // file isn't read
+    let output = projectmd_with_token_file_env(&["-C", root, "--github-token", "token", "--print-token-source", "sync", "--dry-run"], Some(&missing));
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Using GitHub token from the --github-token flag"));
+
+    // The file's trailing newline isn't sent
+    let (port, request) = record_one_request();
+    let token_file = dir.path().join("token");
+    fs::write(&token_file, "ghp_from_file\n").unwrap();
+    fs::write(dir.path().join("project.md"), format!("backend: github\nrepo: a/b\nbackend_config:\n  base_url: http://127.0.0.1:{}/api/v3\n---\n", port)).unwrap();
+    let output = projectmd_with_token_file_env(&["-C", root, "--print-token-source", "ratelimit"], Some(&token_file));
+    drop(std::net::TcpStream::connect(("127.0.0.1", port)));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Using GitHub token from the file named by the GITHUB_TOKEN_FILE environment variable"), "{}", String::from_utf8_lossy(&output.stderr));
+    let request = request.join().unwrap().to_lowercase();
+    assert!(request.contains("\r\nauthorization: bearer ghp_from_file\r\n"), "{}", request);
+}
+
+#[test]
+fn test_close_all_dry_run_needs_no_token() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("tasks")).unwrap();
+    fs::write(dir.path().join("project.md"), "backend: github\nrepo: a/b\n---\n\n* [#1] - tasks/a.md - A\n* [#2] - tasks/b.md - B\n").unwrap();
+    fs::write(dir.path().join("tasks/a.md"), "---\ntags: [sprint-12]\n---\n# Task A\n").unwrap();
+    fs::write(dir.path().join("tasks/b.md"), "---\n---\n# Task B\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_projectmd"))
+        .args(["-C", dir.path().to_str().unwrap(), "close-all", "--tag", "sprint-12", "--dry-run"])
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GITHUB_TOKEN_FILE")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would close 1 issues tagged 'sprint-12'") && stdout.contains("[CLOSE] #1 tasks/a.md"), "{}", stdout);
+}
@@ -1,3 +1,4 @@
+use projectmd::migrate::upgrade_project;
 use projectmd::parser::{parse_project_file, parse_task_file};
 use projectmd::types::TaskStatus;
 use std::fs;
@@ -9,7 +10,7 @@ fn load_fixture(name: &str) -> String {
     path.push("tests");
     path.push("fixtures");
     path.push(name);
-    fs::read_to_string(path).expect(&format!("Failed to load fixture: {}", name))
+    fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to load fixture: {}", name))
 }
 
 #[test]
@@ -22,12 +23,12 @@ fn test_simple_project() {
     assert_eq!(result.tasks.len(), 2);
 
     assert_eq!(result.tasks[0].status, TaskStatus::New);
-    assert_eq!(result.tasks[0].path.to_str().unwrap(), "tasks/task1.md");
-    assert_eq!(result.tasks[0].description, "First task");
+    assert_eq!(result.tasks[0].path.as_ref().unwrap().to_str().unwrap(), "tasks/task1.md");
+    assert_eq!(result.tasks[0].description.as_deref(), Some("First task"));
 
     assert_eq!(result.tasks[1].status, TaskStatus::Existing(1));
-    assert_eq!(result.tasks[1].path.to_str().unwrap(), "tasks/task2.md");
-    assert_eq!(result.tasks[1].description, "Second task");
+    assert_eq!(result.tasks[1].path.as_ref().unwrap().to_str().unwrap(), "tasks/task2.md");
+    assert_eq!(result.tasks[1].description.as_deref(), Some("Second task"));
 }
 
 #[test]
@@ -68,16 +69,16 @@ fn test_complex_project() {
     assert_eq!(result.tasks.len(), 4);
 
     assert_eq!(result.tasks[0].status, TaskStatus::New);
-    assert_eq!(result.tasks[0].description, "Setup the project");
+    assert_eq!(result.tasks[0].description.as_deref(), Some("Setup the project"));
 
     assert_eq!(result.tasks[1].status, TaskStatus::Existing(1));
-    assert_eq!(result.tasks[1].description, "Build the application");
+    assert_eq!(result.tasks[1].description.as_deref(), Some("Build the application"));
 
     assert_eq!(result.tasks[2].status, TaskStatus::Existing(42));
-    assert_eq!(result.tasks[2].description, "Deploy to production");
+    assert_eq!(result.tasks[2].description.as_deref(), Some("Deploy to production"));
 
     assert_eq!(result.tasks[3].status, TaskStatus::New);
-    assert_eq!(result.tasks[3].description, "Write tests");
+    assert_eq!(result.tasks[3].description.as_deref(), Some("Write tests"));
 }
 
 #[test]
@@ -101,16 +102,73 @@ fn test_mixed_content() {
 
     // Verify tasks are parsed correctly despite mixed content
     assert_eq!(result.tasks[0].status, TaskStatus::New);
-    assert_eq!(result.tasks[0].path.to_str().unwrap(), "tasks/first.md");
+    assert_eq!(result.tasks[0].path.as_ref().unwrap().to_str().unwrap(), "tasks/first.md");
 
     assert_eq!(result.tasks[1].status, TaskStatus::Existing(10));
-    assert_eq!(result.tasks[1].path.to_str().unwrap(), "tasks/second.md");
+    assert_eq!(result.tasks[1].path.as_ref().unwrap().to_str().unwrap(), "tasks/second.md");
 
     assert_eq!(result.tasks[2].status, TaskStatus::Existing(20));
-    assert_eq!(result.tasks[2].path.to_str().unwrap(), "tasks/third.md");
+    assert_eq!(result.tasks[2].path.as_ref().unwrap().to_str().unwrap(), "tasks/third.md");
 
     assert_eq!(result.tasks[3].status, TaskStatus::New);
-    assert_eq!(result.tasks[3].path.to_str().unwrap(), "tasks/fourth.md");
+    assert_eq!(result.tasks[3].path.as_ref().unwrap().to_str().unwrap(), "tasks/fourth.md");
+}
+
+#[test]
+fn test_description_only_tasks() {
+    let content = load_fixture("description_only.md");
+    let result = parse_project_file(&content).expect("Failed to parse description_only.md");
+
+    assert_eq!(result.tasks.len(), 4);
+
+    assert_eq!(result.tasks[0].status, TaskStatus::New);
+    assert_eq!(result.tasks[0].path, None);
+    assert_eq!(result.tasks[0].description.as_deref(), Some("Quick fix the header"));
+
+    // A " - " separator still means "path - description"
+    assert_eq!(result.tasks[1].status, TaskStatus::Existing(7));
+    assert_eq!(result.tasks[1].path.as_ref().unwrap().to_str().unwrap(), "Bump the version");
+    assert_eq!(result.tasks[1].description.as_deref(), Some("for the release"));
+
+    assert_eq!(result.tasks[2].path.as_ref().unwrap().to_str().unwrap(), "tasks/docs.md");
+
+    assert_eq!(result.tasks[3].status, TaskStatus::InProgress(8));
+    assert_eq!(result.tasks[3].path, None);
+    assert_eq!(result.tasks[3].line(), "* [wip:#8] - Tidy the footer");
+}
+
+#[test]
+fn test_empty_descriptions_are_task_errors() {
+    let content = "backend: github\nrepo: a/b\n---\n* [new] - \n* [new] - tasks/x.md - \n* [#3] -   ✅\n* [new] - tasks/y.md\n";
+    let result = parse_project_file(content).unwrap();
+
+    assert_eq!(result.tasks.len(), 1);
+    assert_eq!(result.tasks[0].path.as_ref().unwrap().to_str().unwrap(), "tasks/y.md");
+    let lines: Vec<usize> = result.task_errors.iter().map(|(line, _)| *line).collect();
+    assert_eq!(lines, vec![4, 5, 6]);
+    assert!(result.task_errors.iter().all(|(_, error)| error.contains("Empty task description")), "{:?}", result.task_errors);
+}
+
+#[test]
+fn test_path_only_tasks() {
+    let content = load_fixture("path_only.md");
+    let result = parse_project_file(&content).expect("Failed to parse path_only.md");
+
+    assert_eq!(result.tasks.len(), 4);
+
+    assert_eq!(result.tasks[0].status, TaskStatus::New);
+    assert_eq!(result.tasks[0].path.as_ref().unwrap().to_str().unwrap(), "tasks/setup.md");
+    assert_eq!(result.tasks[0].description, None);
+    assert_eq!(result.tasks[0].line(), "* [new] - tasks/setup.md");
+
+    assert_eq!(result.tasks[1].status, TaskStatus::Existing(3));
+    assert_eq!(result.tasks[1].description_or("Endpoints"), "Endpoints");
+
+    assert_eq!(result.tasks[2].description.as_deref(), Some("Build the UI"));
+
+    // Words around a file name make it a description
+    assert_eq!(result.tasks[3].path, None);
+    assert_eq!(result.tasks[3].description.as_deref(), Some("Update notes.md"));
 }
 
 #[test]
@@ -123,12 +181,14 @@ fn test_all_fixtures_parse() {
         "complex.md",
         "no_tasks.md",
         "mixed_content.md",
+        "description_only.md",
+        "path_only.md",
     ];
 
     for fixture in fixtures {
         let content = load_fixture(fixture);
         parse_project_file(&content)
-            .expect(&format!("Failed to parse fixture: {}", fixture));
+            .unwrap_or_else(|_| panic!("Failed to parse fixture: {}", fixture));
     }
 }
 
@@ -141,7 +201,7 @@ fn test_issue_numbers() {
         matches!(t.status, TaskStatus::Existing(42))
     });
     assert!(issue_42.is_some());
-    assert_eq!(issue_42.unwrap().path.to_str().unwrap(), "tasks/deploy.md");
+    assert_eq!(issue_42.unwrap().path.as_ref().unwrap().to_str().unwrap(), "tasks/deploy.md");
 }
 
 #[test]
@@ -153,6 +213,16 @@ fn test_yaml_frontmatter_extra_fields() {
     assert_eq!(result.config.extra.get("extra_field").and_then(|v| v.as_str()), Some("some_value"));
 }
 
+#[test]
+fn test_zero_max_title_len_is_rejected() {
+    // Every title would be cut down to just `…`
+    let error = parse_project_file("backend: github\nrepo: a/b\nmax_title_len: 0\n---\n").unwrap_err();
+    assert!(format!("{:#}", error).contains("nonzero"), "{:#}", error);
+
+    let result = parse_project_file("backend: github\nrepo: a/b\nmax_title_len: 20\n---\n").unwrap();
+    assert_eq!(result.config.max_title_len.map(|max| max.get()), Some(20));
+}
+
 #[test]
 fn test_task_file_with_timestamps() {
     let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -190,3 +260,37 @@ Some details go here.
     assert_eq!(result.config.updated_at, None);
     assert_eq!(result.title, "Setup the authentication");
 }
+
+#[test]
+fn test_list_markers() {
+    let content = load_fixture("list_markers.md");
+    let result = parse_project_file(&content).expect("Failed to parse list_markers.md");
+    assert!(result.task_errors.is_empty(), "{:?}", result.task_errors);
+
+    let markers: Vec<&str> = result.tasks.iter().map(|task| task.list_marker.as_str()).collect();
+    assert_eq!(markers, vec!["*", "-", "+", "1.", "12."]);
+    assert_eq!(result.tasks[3].description.as_deref(), Some("First"));
+    assert_eq!(result.tasks[4].status, TaskStatus::InProgress(1));
+
+    // Each task's line is written back with the bullet it was read with
+    let lines: Vec<String> = result.tasks.iter().map(|task| task.line()).collect();
+    for line in &lines {
+        assert!(content.contains(&format!("{}\n", line)), "{}", line);
+    }
+}
+
+#[test]
+fn test_upgrade_fixture() {
+    let old = load_fixture("upgrade_old.md");
+    let new = load_fixture("upgrade_new.md");
+    assert_eq!(upgrade_project(&old).unwrap(), new);
+
+    // The upgraded file is already current, and reads as the same tasks
+    assert_eq!(upgrade_project(&new).unwrap(), new);
+    let (old, new) = (parse_project_file(&old).unwrap(), parse_project_file(&new).unwrap());
+    assert_eq!(old.tasks.len(), 4);
+    for (old, new) in old.tasks.iter().zip(&new.tasks) {
+        assert_eq!((&old.status, &old.path, &old.description, old.completed), (&new.status, &new.path, &new.description, new.completed));
+    }
+    assert_eq!(new.task_errors.len(), 1);
+}
@@ -0,0 +1,462 @@
+use projectmd::backend::fs::FileSystemBackend;
+use projectmd::backend::Backend;
+use projectmd::clock::FixedClock;
+use projectmd::parser::parse_task_file;
+use projectmd::sync::{SyncEngine, SyncOptions, SyncReason, SyncResult};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const PROJECT: &str = "backend: fs
+repo: issues
+---
+
+# Round trip
+
+* [new] - tasks/a.md - A
+* [#1] - tasks/c.md - C
+* [new] - tasks/b.md - B
+* [new] - Quick fix
+";
+
+/// A project with three task files and an issue already created for one of them
+async fn setup(root: &Path) {
+    fs::create_dir(root.join("tasks")).unwrap();
+    fs::write(root.join("project.md"), PROJECT).unwrap();
+    fs::write(root.join("tasks/a.md"), "---\ntype: feature\ntags: [api]\n---\n# Task A\n\nFirst.\n").unwrap();
+    fs::write(root.join("tasks/b.md"), "---\n---\n# Task B\n\nSecond.\n").unwrap();
+    fs::write(root.join("tasks/c.md"), "---\nissue_id: 1\n---\n# Task C\n\nThird.\n").unwrap();
+
+    backend(root).create_issue("Old title", "Old body", Vec::new(), Vec::new()).await.unwrap();
+}
+
+fn backend(root: &Path) -> FileSystemBackend {
+    FileSystemBackend::new(&root.join("issues"))
+}
+
+async fn sync(root: &Path) -> SyncResult {
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let result = engine.sync(&root.join("project.md")).await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    result
+}
+
+fn numbers(tasks: &[(projectmd::TaskItem, u64)]) -> Vec<u64> {
+    tasks.iter().map(|(_, number)| *number).collect()
+}
+
+#[tokio::test]
+async fn test_sync_creates_updates_and_writes_back() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+
+    let result = sync(root).await;
+    assert_eq!(numbers(&result.created), vec![2, 3, 4]);
+    assert_eq!(numbers(&result.updated), vec![1]);
+    assert!(result.skipped.is_empty());
+
+    // New issue numbers are written back into project.md...
+    assert_eq!(
+        fs::read_to_string(root.join("project.md")).unwrap(),
+        PROJECT
+            .replace("* [new] - tasks/a.md", "* [#2] - tasks/a.md")
+            .replace("* [new] - tasks/b.md", "* [#3] - tasks/b.md")
+            .replace("* [new] - Quick fix", "* [#4] - Quick fix")
+    );
+
+    // ...and into the task files, along with their timestamps
+    for (file, number) in [("a.md", 2), ("b.md", 3), ("c.md", 1)] {
+        let task_file = parse_task_file(&fs::read_to_string(root.join("tasks").join(file)).unwrap()).unwrap();
+        assert_eq!(task_file.config.issue_id, Some(number), "{}", file);
+        assert!(task_file.config.created_at.is_some(), "{}", file);
+        assert!(task_file.config.updated_at.is_some(), "{}", file);
+    }
+
+    let backend = backend(root);
+    let a = backend.get_issue(2).await.unwrap();
+    assert_eq!(a.title, "Task A");
+    assert!(a.body.contains("First."));
+    assert_eq!(a.labels, vec!["api"]);
+    assert_eq!(backend.get_issue(1).await.unwrap().title, "Task C");
+    assert_eq!(backend.get_issue(4).await.unwrap().body, "Quick fix");
+    assert_eq!(backend.list_issues().await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_second_sync_skips_unchanged_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    sync(root).await;
+    let project = fs::read_to_string(root.join("project.md")).unwrap();
+
+    let result = sync(root).await;
+    assert!(result.created.is_empty());
+    assert!(result.updated.is_empty());
+    assert_eq!(result.skipped.len(), 4);
+    assert_eq!(fs::read_to_string(root.join("project.md")).unwrap(), project);
+
+    // Editing a task file makes it sync again
+    let b = root.join("tasks/b.md");
+    let content = fs::read_to_string(&b).unwrap().replace("Second.", "Second, revised.");
+    fs::write(&b, content).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&b)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(60)))
+        .unwrap();
+
+    let result = sync(root).await;
+    assert_eq!(numbers(&result.updated), vec![3]);
+    assert_eq!(result.skipped.len(), 3);
+    assert!(backend(root).get_issue(3).await.unwrap().body.contains("Second, revised."));
+
+    // Description-only tasks have no file, so their description is compared
+    // with the one last synced
+    fs::write(root.join("project.md"), project.replace("- Quick fix", "- Quick fix, revised")).unwrap();
+    let result = sync(root).await;
+    assert_eq!(numbers(&result.updated), vec![4]);
+    assert_eq!(backend(root).get_issue(4).await.unwrap().title, "Quick fix, revised");
+}
+
+#[tokio::test]
+async fn test_sync_only_tagged_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    fs::write(root.join("tasks/b.md"), "---\ntags: [docs, api]\n---\n# Task B\n\nSecond.\n").unwrap();
+
+    let options = SyncOptions { tag: Some("api".to_string()), ..Default::default() };
+    let engine = SyncEngine::new(backend(root), root.to_path_buf()).with_options(options);
+    let result = engine.sync(&root.join("project.md")).await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+    // Only a.md and b.md are tagged api; c.md and the description-only task aren't tagged at all
+    assert_eq!(numbers(&result.created), vec![2, 3]);
+    assert!(result.updated.is_empty());
+    assert_eq!(result.skipped.len(), 2);
+    assert_eq!(backend(root).get_issue(1).await.unwrap().title, "Old title");
+    assert_eq!(backend(root).list_issues().await.unwrap().len(), 3);
+}
+
+fn reasons(result: &SyncResult) -> Vec<(String, SyncReason)> {
+    result.reasons.iter().map(|(task, reason)| (task.name(), reason.clone())).collect()
+}
+
+#[tokio::test]
+async fn test_sync_explains_decisions() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+
+    let result = sync(root).await;
+    assert_eq!(
+        reasons(&result),
+        vec![
+            ("tasks/a.md".to_string(), SyncReason::New),
+            ("tasks/c.md".to_string(), SyncReason::NeverSynced),
+            ("tasks/b.md".to_string(), SyncReason::New),
+            ("\"Quick fix\"".to_string(), SyncReason::New),
+        ]
+    );
+
+    let b = root.join("tasks/b.md");
+    fs::File::options()
+        .write(true)
+        .open(&b)
+        .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(60)))
+        .unwrap();
+
+    let result = sync(root).await;
+    assert_eq!(
+        reasons(&result),
+        vec![
+            ("tasks/a.md".to_string(), SyncReason::Unchanged),
+            ("tasks/c.md".to_string(), SyncReason::Unchanged),
+            ("tasks/b.md".to_string(), SyncReason::FileModified),
+            ("\"Quick fix\"".to_string(), SyncReason::DescriptionUnchanged),
+        ]
+    );
+    assert_eq!(SyncReason::Unchanged.to_string(), "file not modified since updated_at");
+}
+
+#[tokio::test]
+async fn test_sync_keeps_list_markers() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let project = fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/list_markers.md")).unwrap();
+    fs::create_dir(root.join("tasks")).unwrap();
+    fs::write(root.join("project.md"), &project).unwrap();
+    for file in ["a.md", "b.md", "c.md"] {
+        fs::write(root.join("tasks").join(file), "---\n---\n# Task\n").unwrap();
+    }
+    backend(root).create_issue("Old title", "Old body", Vec::new(), Vec::new()).await.unwrap();
+
+    let result = sync(root).await;
+    assert_eq!(numbers(&result.created), vec![2, 3, 4, 5]);
+    assert_eq!(
+        fs::read_to_string(root.join("project.md")).unwrap(),
+        project
+            .replace("* [new] - tasks/a.md", "* [#2] - tasks/a.md")
+            .replace("- [new] - tasks/b.md", "- [#3] - tasks/b.md")
+            .replace("+ [new] - Plus", "+ [#4] - Plus")
+            .replace("1. [new] - tasks/c.md", "1. [#5] - tasks/c.md")
+    );
+}
+
+#[tokio::test]
+async fn test_sync_closes_duplicates() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    fs::write(root.join("tasks/c.md"), "---\nissue_id: 1\nduplicate_of: 9\n---\n# Task C\n\nThird.\n").unwrap();
+
+    let result = sync(root).await;
+    assert_eq!(result.duplicates.iter().map(|(_, num, original)| (*num, *original)).collect::<Vec<_>>(), vec![(1, 9)]);
+    assert!(result.closed.is_empty());
+
+    // Closed as not planned, with a comment GitHub links the issues by
+    let stored: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("issues/issues.json")).unwrap()).unwrap();
+    assert_eq!(stored[0]["state"], "closed");
+    assert_eq!(stored[0]["state_reason"], "not_planned");
+    let comments = backend(root).list_comments(1).await.unwrap();
+    assert_eq!(comments.iter().map(|c| c.body.as_str()).collect::<Vec<_>>(), vec!["Duplicate of #9"]);
+    assert_eq!(stored[1]["state"], "open");
+
+    let project = fs::read_to_string(root.join("project.md")).unwrap();
+    assert!(project.contains("* [#1] - tasks/c.md - C ✅\n"), "{}", project);
+    assert!(project.contains("* [#2] - tasks/a.md - A\n"), "{}", project);
+
+    // The next sync finds the issue closed and leaves it be
+    let result = sync(root).await;
+    assert!(result.duplicates.is_empty());
+    assert_eq!(backend(root).list_comments(1).await.unwrap().len(), 1);
+    assert_eq!(fs::read_to_string(root.join("project.md")).unwrap(), project);
+}
+
+#[tokio::test]
+async fn test_round_robin_assignees() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("tasks")).unwrap();
+    let project = "backend: fs\nrepo: issues\nround_robin_assignees: [alice, bob, carol]\n---\n\n* [new] - One\n* [new] - tasks/mine.md - Mine\n* [new] - Two\n* [new] - Three\n* [new] - Four\n";
+    fs::write(root.join("project.md"), project).unwrap();
+    fs::write(root.join("tasks/mine.md"), "---\nassignees: [dave]\n---\n# Mine\n").unwrap();
+    sync(root).await;
+
+    let assignees = || {
+        let stored: serde_json::Value = serde_json::from_str(&fs::read_to_string(root.join("issues/issues.json")).unwrap()).unwrap();
+        stored.as_array().unwrap().iter().map(|issue| issue["assignees"][0].as_str().unwrap().to_string()).collect::<Vec<_>>()
+    };
+    // Tasks with their own assignees don't take a turn
+    assert_eq!(assignees(), vec!["alice", "dave", "bob", "carol", "alice"]);
+
+    // The rotation carries on where the last sync left off, and only for creates
+    let content = fs::read_to_string(root.join("project.md")).unwrap() + "* [new] - Five\n";
+    fs::write(root.join("project.md"), content).unwrap();
+    let result = sync(root).await;
+    assert_eq!(numbers(&result.created), vec![6]);
+    assert_eq!(assignees(), vec!["alice", "dave", "bob", "carol", "alice", "bob"]);
+}
+
+#[tokio::test]
+async fn test_reset_timestamps_and_freeze_created_at() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("tasks")).unwrap();
+    fs::write(root.join("project.md"), "backend: fs\nrepo: issues\n---\n\n* [#1] - tasks/a.md - A\n* [#2] - tasks/b.md - B\n").unwrap();
+    let old = "2020-01-01T00:00:00+00:00";
+    let write_tasks = || {
+        fs::write(root.join("tasks/a.md"), format!("---\ncreated_at: \"{}\"\nfreeze_created_at: true\n---\n# A\n", old)).unwrap();
+        fs::write(root.join("tasks/b.md"), format!("---\ncreated_at: \"{}\"\n---\n# B\n", old)).unwrap();
+    };
+    write_tasks();
+    for title in ["A", "B"] {
+        backend(root).create_issue(title, "", Vec::new(), Vec::new()).await.unwrap();
+    }
+
+    let now = chrono::DateTime::parse_from_rfc3339("2025-06-01T12:00:00+00:00").unwrap().with_timezone(&chrono::Utc);
+    let sync_at = |reset_timestamps: bool| async move {
+        let options = SyncOptions { reset_timestamps, ..Default::default() };
+        let engine = SyncEngine::new(backend(root), root.to_path_buf()).with_options(options).with_clock(FixedClock(now));
+        let result = engine.sync(&root.join("project.md")).await.unwrap();
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(numbers(&result.updated), vec![1, 2]);
+    };
+    let created_at = |file: &str| parse_task_file(&fs::read_to_string(root.join("tasks").join(file)).unwrap()).unwrap().config.created_at.unwrap();
+
+    // Syncing keeps created_at...
+    sync_at(false).await;
+    assert_eq!((created_at("a.md"), created_at("b.md")), (old.to_string(), old.to_string()));
+
+    // ...unless the flag resets it, which a frozen file still refuses
+    write_tasks();
+    sync_at(true).await;
+    assert_eq!(created_at("a.md"), old);
+    assert_eq!(created_at("b.md"), now.to_rfc3339());
+}
+
+#[tokio::test]
+async fn test_write_back_keeps_or_normalizes_the_final_newline() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    let project_file = root.join("project.md");
+
+    let synced = |config: &str, ending: &str| {
+        let project_file = project_file.clone();
+        let content = format!("backend: fs\nrepo: issues\n{}---\n\n* [new] - Quick fix{}", config, ending);
+        async move {
+            fs::write(&project_file, content).unwrap();
+            sync(project_file.parent().unwrap()).await;
+            let content = fs::read_to_string(&project_file).unwrap();
+            assert!(!content.contains("[new]"), "{:?}", content);
+            content
+        }
+    };
+
+    // By default the file ends just as it did, with however many newlines
+    for ending in ["\n", "\n\n\n"] {
+        assert!(synced("", ending).await.ends_with(&format!("] - Quick fix{}", ending)));
+    }
+    for ending in ["\n", "\n\n\n"] {
+        assert!(synced("final_newline: single\n", ending).await.ends_with("] - Quick fix\n"));
+    }
+}
+
+#[tokio::test]
+async fn test_description_change_updates_only_the_title() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    let project_file = root.join("project.md");
+    fs::write(&project_file, PROJECT.replace("repo: issues\n", "repo: issues\ntitle_source: description\n")).unwrap();
+
+    sync(root).await;
+    let issue = backend(root).get_issue(2).await.unwrap();
+    assert_eq!(issue.title, "A");
+    // Changed on the backend, so a re-rendered body would show
+    backend(root).update_issue(2, "A", "Edited remotely", issue.labels.clone(), Vec::new()).await.unwrap();
+
+    let content = fs::read_to_string(&project_file).unwrap();
+    fs::write(&project_file, content.replace("tasks/a.md - A", "tasks/a.md - A, renamed")).unwrap();
+    let result = sync(root).await;
+    assert_eq!(reasons(&result)[0], ("tasks/a.md".to_string(), SyncReason::DescriptionChanged));
+    assert_eq!(reasons(&result)[1], ("tasks/c.md".to_string(), SyncReason::Unchanged));
+
+    let issue = backend(root).get_issue(2).await.unwrap();
+    assert_eq!((issue.title.as_str(), issue.body.as_str()), ("A, renamed", "Edited remotely"));
+    let task_file = parse_task_file(&fs::read_to_string(root.join("tasks/a.md")).unwrap()).unwrap();
+    assert_eq!(task_file.config.synced_description.as_deref(), Some("A, renamed"));
+
+    let result = sync(root).await;
+    assert_eq!(reasons(&result)[0], ("tasks/a.md".to_string(), SyncReason::Unchanged));
+}
+
+#[tokio::test]
+async fn test_oversized_task_files_are_skipped_with_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    fs::write(root.join("project.md"), PROJECT.replace("repo: issues\n", "repo: issues\nmax_task_file_bytes: 1024\n")).unwrap();
+    // A binary renamed to .md
+    fs::write(root.join("tasks/b.md"), vec![0xff; 4096]).unwrap();
+
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let result = engine.sync(&root.join("project.md")).await.unwrap();
+    assert_eq!(numbers(&result.created), vec![2, 3]);
+    assert_eq!(result.errors.len(), 1);
+    let (task, error) = &result.errors[0];
+    assert_eq!(task.name(), "tasks/b.md");
+    assert!(error.contains("is 4096 bytes, over max_task_file_bytes (1024)"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_required_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("tasks")).unwrap();
+    let project = "backend: fs\nrepo: issues\nrequired_fields: [type, priority]\n---\n\n\
+                   * [new] - tasks/good.md - Good\n* [new] - tasks/bad.md - Bad\n* [new] - Quick fix\n";
+    fs::write(root.join("project.md"), project).unwrap();
+    fs::write(root.join("tasks/good.md"), "---\ntype: bug\npriority: high\n---\n# Good\n").unwrap();
+    fs::write(root.join("tasks/bad.md"), "---\ntype: bug\npriority:\n---\n# Bad\n").unwrap();
+
+    let report = projectmd::validate::validate_project(root, &projectmd::parser::parse_project_file(project).unwrap());
+    assert_eq!(report.errors, vec![("tasks/bad.md".to_string(), "missing required front matter fields: priority".to_string())]);
+
+    // Description-only tasks have no front matter to require fields of
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let result = engine.sync(&root.join("project.md")).await.unwrap();
+    assert_eq!(numbers(&result.created), vec![1, 2]);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].0.name(), "tasks/bad.md");
+    assert!(result.errors[0].1.contains("Missing required front matter fields: priority"), "{}", result.errors[0].1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_watch_remote_pulls_each_interval_until_stopped() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    sync(root).await;
+
+    // Edited on the backend after the sync
+    let issue = backend(root).get_issue(2).await.unwrap();
+    let body = issue.body.replace("type: feature", "type: bug");
+    backend(root).update_issue(2, &issue.title, &body, issue.labels, Vec::new()).await.unwrap();
+
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let mut cycles = Vec::new();
+    let stop = tokio::time::sleep(Duration::from_secs(25));
+    engine
+        .watch_remote(&root.join("project.md"), Duration::from_secs(10), stop, |result| cycles.push(numbers(&result.unwrap().updated)))
+        .await;
+
+    // At 0s, 10s and 20s; only the first finds anything new
+    assert_eq!(cycles, vec![vec![2], vec![], vec![]]);
+    let task_file = parse_task_file(&fs::read_to_string(root.join("tasks/a.md")).unwrap()).unwrap();
+    assert_eq!(task_file.config.task_type.as_deref(), Some("bug"));
+}
+
+#[tokio::test]
+async fn test_pull_replaces_block_style_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    fs::write(root.join("tasks/a.md"), "---\ntype: feature\ntags:\n  - api\n  - web\npriority: high\n---\n# Task A\n\nFirst.\n").unwrap();
+    sync(root).await;
+
+    let issue = backend(root).get_issue(2).await.unwrap();
+    let body = issue.body.replace("tags:\n- api\n- web\n", "tags:\n- cli\n");
+    assert_ne!(body, issue.body, "{}", issue.body);
+    backend(root).update_issue(2, &issue.title, &body, issue.labels, Vec::new()).await.unwrap();
+
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let result = engine.pull(&root.join("project.md")).await.unwrap();
+    assert_eq!(numbers(&result.updated), vec![2]);
+
+    let content = fs::read_to_string(root.join("tasks/a.md")).unwrap();
+    let task_file = parse_task_file(&content).unwrap();
+    assert_eq!(task_file.config.tags, Some(vec!["cli".to_string()]));
+    assert!(content.contains("tags: [\"cli\"]\npriority: high\n"), "{}", content);
+}
+
+#[tokio::test]
+async fn test_pull_leaves_out_types_taken_from_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    setup(root).await;
+    fs::write(root.join("project.md"), PROJECT.replace("repo: issues\n", "repo: issues\ntag_as_type: true\n")).unwrap();
+    fs::write(root.join("tasks/b.md"), "---\ntags: [bug]\n---\n# Task B\n\nSecond.\n").unwrap();
+    sync(root).await;
+    assert!(backend(root).get_issue(3).await.unwrap().body.contains("type: bug"));
+
+    // Not even once tag_as_type is turned off
+    fs::write(root.join("project.md"), fs::read_to_string(root.join("project.md")).unwrap().replace("tag_as_type: true\n", "")).unwrap();
+    let engine = SyncEngine::new(backend(root), root.to_path_buf());
+    let result = engine.pull(&root.join("project.md")).await.unwrap();
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    let task_file = parse_task_file(&fs::read_to_string(root.join("tasks/b.md")).unwrap()).unwrap();
+    assert_eq!(task_file.config.task_type, None);
+}